@@ -1,64 +1,92 @@
 //! This module contains the build related functions
 
 use crate::hasher::Hasher;
-use crate::parser::{BuildConfig, OSConfig, TargetConfig};
+use crate::parser::{BuildConfig, OSConfig, TargetConfig, TargetType, DLL_EXT};
+use crate::utils::diagnostics;
 use crate::utils::features::cfg_feat;
-use crate::utils::log::{log, LogLevel};
+use crate::utils::log::{log, progress_enabled, LogLevel};
+use crate::utils::tools;
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
-static BUILD_DIR: &str = "ruxgo_bld";
-static BIN_DIR: &str = "ruxgo_bld/bin";
-#[cfg(target_os = "windows")]
-static OBJ_DIR: &str = "ruxgo_bld/obj_win32";
-#[cfg(target_os = "linux")]
-static OBJ_DIR: &str = "ruxgo_bld/obj_linux";
-
-// ruxlibc info and ld script
-lazy_static! {
-    static ref RUXLIBC_INC: String = {
-        let path1 = "../ruxos/ulib/ruxlibc/include";
-        let path2 = "../../../ulib/ruxlibc/include";
-        if Path::new(path1).exists() {
-            String::from(path1)
-        } else {
-            String::from(path2)
-        }
-    };
-    static ref LD_SCRIPT: String = {
-        let path1 = "../ruxos/modules/ruxhal";
-        let path2 = "../../../modules/ruxhal";
-        if Path::new(path1).exists() {
-            String::from(path1)
-        } else {
-            String::from(path2)
-        }
-    };
-}
-static RUXLIBC_BIN: &str = "ruxgo_bld/bin/libc.a";
+// ruxlibc info
 static RUXLIBC_RUST_LIB: &str = "libruxlibc.a";
 
 // ruxmusl info
-static RUXMUSL_INC: &str = "ruxgo_bld/ruxmusl/install/include";
-static RUXMUSL_BIN: &str = "ruxgo_bld/ruxmusl/install/lib/libc.a";
 static RUXMUSL_RUST_LIB: &str = "libruxmusl.a";
 
+// keys of the compiler identity and compile flags in the target hash file,
+// never valid source paths
+static COMPILER_HASH_KEY: &str = "<compiler>";
+static CFLAGS_HASH_KEY: &str = "<cflags>";
+static PRE_BUILD_HASH_KEY: &str = "<pre_build>";
+static PCH_HASH_KEY: &str = "<pch>";
+
+// whether to log why each target is rebuilt, set by `--explain`
+static EXPLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Logs the reason each source is compiled and each target relinked at the `Log` level
+pub fn set_explain(explain: bool) {
+    EXPLAIN.store(explain, Ordering::Relaxed);
+}
+
+/// Returns the level of the messages explaining rebuilds, `Log` if `--explain` is set
+fn explain_level() -> LogLevel {
+    if EXPLAIN.load(Ordering::Relaxed) {
+        LogLevel::Log
+    } else {
+        LogLevel::Debug
+    }
+}
+
+/// Returns the style of the compile progress bar, which shows the file being compiled last
+/// # Arguments
+/// * `color` - The color of the bar, empty for the default
+fn progress_style(color: &str) -> ProgressStyle {
+    let template = format!(
+        "    {}[{{bar:40.{}}}] {{pos}}/{{len}} ({{percent}}%) [{{elapsed_precise}}] {{msg}}",
+        "Compiling :".cyan(),
+        color
+    );
+    ProgressStyle::with_template(&template)
+        .unwrap()
+        .progress_chars("=>-")
+}
+
+/// Turns the progress bar red and leaves it at the failed file instead of a stalled bar
+/// # Arguments
+/// * `progress_bar` - The compile progress bar
+/// * `name` - The name of the file that failed to compile
+fn abandon_progress(progress_bar: &ProgressBar, name: &str) {
+    progress_bar.set_style(progress_style("red"));
+    progress_bar.abandon_with_message(format!("{} {}", "failed:".red(), name));
+    end_progress_line(progress_bar);
+}
+
+/// Moves past the line of a finished progress bar, so that the next log starts on its own line
+fn end_progress_line(progress_bar: &ProgressBar) {
+    if !progress_bar.is_hidden() {
+        eprintln!();
+    }
+}
+
 /// Represents a target
 pub struct Target<'a> {
     srcs: Vec<Src>,
     build_config: &'a BuildConfig,
     target_config: &'a TargetConfig,
     os_config: &'a OSConfig,
-    dependant_includes: HashMap<String, Vec<String>>,
     pub bin_path: String,
     pub elf_path: String,
     hash_file_path: String,
@@ -88,58 +116,142 @@ impl<'a> Target<'a> {
         target_config: &'a TargetConfig,
         targets: &'a Vec<TargetConfig>,
     ) -> Self {
+        Target::new_with_parents(
+            build_config,
+            os_config,
+            target_config,
+            targets,
+            &mut Vec::new(),
+        )
+    }
+
+    /// Creates a new target, recursively creating its dependant libs
+    /// # Arguments
+    /// * `parents` - Names of the targets whose dependant libs are being created
+    fn new_with_parents(
+        build_config: &'a BuildConfig,
+        os_config: &'a OSConfig,
+        target_config: &'a TargetConfig,
+        targets: &'a Vec<TargetConfig>,
+        parents: &mut Vec<String>,
+    ) -> Self {
+        if let Some(start) = parents.iter().position(|name| *name == target_config.name) {
+            let mut cycle = parents[start..].to_vec();
+            cycle.push(target_config.name.clone());
+            log(
+                LogLevel::Error,
+                &format!("Circular dependency detected: {}", cycle.join(" -> ")),
+            );
+            std::process::exit(1);
+        }
         let srcs = Vec::new();
-        let dependant_includes: HashMap<String, Vec<String>> = HashMap::new();
-        let mut bin_path = format!("{}/{}", BIN_DIR, target_config.name);
+        let mut bin_path = format!("{}/{}", build_config.bin_dir(), target_config.name);
         let mut elf_path = String::new();
         #[cfg(target_os = "windows")]
-        match target_config.typ.as_str() {
-            "exe" => bin_path.push_str(".exe"),
-            "dll" => bin_path.push_str(".dll"),
-            "static" => bin_path.push_str(".lib"),
+        match target_config.typ {
+            TargetType::Exe | TargetType::Test => bin_path.push_str(".exe"),
+            TargetType::Dll => bin_path.push_str(DLL_EXT),
+            TargetType::Static => bin_path.push_str(".lib"),
             _ => (),
         }
-        #[cfg(target_os = "linux")]
-        match target_config.typ.as_str() {
-            "exe" => {
+        #[cfg(not(target_os = "windows"))]
+        match target_config.typ {
+            TargetType::Exe | TargetType::Test => {
                 elf_path = format!("{}.elf", bin_path);
                 bin_path.push_str(".bin");
             }
-            "dll" => bin_path.push_str(".so"),
-            "static" => bin_path.push_str(".a"),
-            "object" => bin_path.push_str(".o"),
-            _ => (),
+            TargetType::Dll => bin_path.push_str(DLL_EXT),
+            TargetType::Static => bin_path.push_str(".a"),
+            TargetType::Object => bin_path.push_str(".o"),
+            TargetType::HeaderOnly => (),
+        }
+        let hash_file_path = build_config.target_hash_path(&target_config.name);
+        let mut path_hash = Hasher::load_hashes_from_file(&hash_file_path);
+        let build_hashes = [
+            (
+                COMPILER_HASH_KEY,
+                Target::compiler_hash(&build_config.compiler.read().unwrap()),
+                "Compiler",
+            ),
+            (
+                CFLAGS_HASH_KEY,
+                Hasher::hash_string(&format!(
+                    "{} {} {} {} {}",
+                    os_cflags(build_config, os_config),
+                    target_config.cflags,
+                    target_config.cstd,
+                    target_config.cppstd,
+                    target_config.include_dir.join(" ")
+                )),
+                "cflags",
+            ),
+        ];
+        let changed: Vec<&str> = build_hashes
+            .iter()
+            .filter(|(key, hash, _)| Hasher::get_hash(key, &path_hash).as_ref() != Some(hash))
+            .map(|(_, _, what)| *what)
+            .collect();
+        if !changed.is_empty() {
+            // Objects built by another compiler or with other flags are stale,
+            // so drop every source hash
+            if !path_hash.is_empty() {
+                log(
+                    if EXPLAIN.load(Ordering::Relaxed) {
+                        LogLevel::Log
+                    } else {
+                        LogLevel::Info
+                    },
+                    &format!(
+                        "{} changed, rebuilding target: {}",
+                        changed.join(" and "),
+                        target_config.name
+                    ),
+                );
+            }
+            path_hash.clear();
+            for (key, hash, _) in build_hashes {
+                path_hash.insert(key.to_string(), hash);
+            }
         }
-        #[cfg(target_os = "windows")]
-        let hash_file_path = format!("ruxgo_bld/{}.win32.hash", &target_config.name);
-        #[cfg(target_os = "linux")]
-        let hash_file_path = format!("ruxgo_bld/{}.linux.hash", &target_config.name);
-        let path_hash = Hasher::load_hashes_from_file(&hash_file_path);
         let mut dependant_libs = Vec::new();
 
+        // check that every dependant lib names a known target
+        let unresolved = target_config.unresolved_deps(targets);
+        if !unresolved.is_empty() {
+            for e in &unresolved {
+                log(LogLevel::Error, &e.to_string());
+            }
+            std::process::exit(1);
+        }
+
         // add dependant libs
+        parents.push(target_config.name.clone());
         for dependant_lib in &target_config.deps {
             for target in targets {
                 if target.name == *dependant_lib {
-                    dependant_libs.push(Target::new(build_config, os_config, target, targets));
+                    dependant_libs.push(Target::new_with_parents(
+                        build_config,
+                        os_config,
+                        target,
+                        targets,
+                        parents,
+                    ));
                 }
             }
         }
+        parents.pop();
 
         // check types of the dependant libs
         for dep_lib in &dependant_libs {
-            if dep_lib.target_config.typ != "dll"
-                && dep_lib.target_config.typ != "static"
-                && dep_lib.target_config.typ != "object"
-            {
+            if !dep_lib.target_config.typ.is_lib() {
                 log(
                     LogLevel::Error,
-                    "Can add only dll, static or object libs as dependant libs",
+                    "Can add only dll, static, object or header-only libs as dependant libs",
                 );
                 log(
                     LogLevel::Error,
                     &format!(
-                        "Target: {} is not a dll, static or object library",
+                        "Target: {} is not a dll, static, object or header-only library",
                         dep_lib.target_config.name
                     ),
                 );
@@ -156,7 +268,8 @@ impl<'a> Target<'a> {
                 LogLevel::Info,
                 &format!("Adding dependant lib: {}", dep_lib.target_config.name),
             );
-            if dep_lib.target_config.typ == "dll" && !dep_lib.target_config.name.starts_with("lib")
+            if dep_lib.target_config.typ == TargetType::Dll
+                && !dep_lib.target_config.name.starts_with("lib")
             {
                 log(
                     LogLevel::Error,
@@ -172,54 +285,128 @@ impl<'a> Target<'a> {
                 std::process::exit(1);
             }
         }
-        if target_config.deps.len() > dependant_libs.len() {
-            log(LogLevel::Error, "Dependant libs not found!");
-            log(
-                LogLevel::Error,
-                &format!("Dependant libs: {:?}", target_config.deps),
-            );
-            log(
-                LogLevel::Error,
-                &format!(
-                    "Found libs: {:?}",
-                    targets
-                        .iter()
-                        .map(|x| {
-                            if x.typ == "dll" || x.typ == "static" || x.typ == "object" {
-                                x.name.clone()
-                            } else {
-                                "".to_string()
-                            }
-                        })
-                        .collect::<Vec<String>>()
-                        .into_iter()
-                        .filter(|x| !x.is_empty())
-                        .collect::<Vec<String>>()
-                ),
-            );
-            std::process::exit(1);
-        }
         let mut target = Target::<'a> {
             srcs,
             build_config,
             target_config,
             os_config,
-            dependant_includes,
             bin_path,
             elf_path,
             path_hash,
             hash_file_path,
             dependant_libs,
         };
-        target.get_srcs(&target_config.src);
+        if target_config.typ != TargetType::HeaderOnly {
+            target.get_srcs();
+        }
         target
     }
 
+    /// Runs the pre_build command of the target, then collects its sources again so that
+    /// generated sources are built too
+    /// The command is skipped if it and its declared inputs are unchanged since the last run
+    fn pre_build(&mut self) {
+        let build_config = self.build_config;
+        let target_config = self.target_config;
+        let path_hash = &mut self.path_hash;
+        if target_config.pre_build.is_empty() {
+            return;
+        }
+        let mut inputs = target_config.pre_build.clone();
+        for input in &target_config.pre_build_inputs {
+            inputs.push(' ');
+            inputs.push_str(&Hasher::hash_file(input).unwrap_or_default());
+        }
+        let hash = Hasher::hash_string(&inputs);
+        // Without declared inputs there is no way to tell if the command is up to date
+        if !target_config.pre_build_inputs.is_empty()
+            && Hasher::get_hash(PRE_BUILD_HASH_KEY, path_hash).as_ref() == Some(&hash)
+        {
+            return;
+        }
+        log(
+            LogLevel::Log,
+            &format!("Running pre_build of target: {}", &target_config.name),
+        );
+        log(
+            LogLevel::Info,
+            &format!("  Command: {}", &target_config.pre_build),
+        );
+        match Command::new("sh")
+            .arg("-c")
+            .arg(&target_config.pre_build)
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                log(
+                    LogLevel::Error,
+                    &format!(
+                        "pre_build of target {} failed with {}",
+                        &target_config.name, status
+                    ),
+                );
+                std::process::exit(1);
+            }
+            Err(why) => {
+                log(
+                    LogLevel::Error,
+                    &format!("Could not run pre_build command: {}", why),
+                );
+                std::process::exit(1);
+            }
+        }
+        // Save the hash right away, the target may be up to date and never saved otherwise
+        path_hash.insert(PRE_BUILD_HASH_KEY.to_string(), hash);
+        fs::create_dir_all(&build_config.output_dir).unwrap_or_else(|why| {
+            log(
+                LogLevel::Error,
+                &format!("Couldn't create build dir: {}", why),
+            );
+            std::process::exit(1);
+        });
+        Hasher::save_hashes_to_file(&self.hash_file_path, path_hash);
+
+        self.srcs.clear();
+        if target_config.typ != TargetType::HeaderOnly {
+            self.get_srcs();
+        }
+    }
+
+    /// Returns the hash of the `--version` output of the compiler
+    /// Falls back to hashing the compiler name if it cannot be run
+    pub(crate) fn compiler_hash(compiler: &str) -> String {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} --version", compiler))
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                Hasher::hash_string(&String::from_utf8_lossy(&output.stdout))
+            }
+            _ => Hasher::hash_string(compiler),
+        }
+    }
+
     /// Builds the target
     /// # Arguments
-    /// * `gen_cc` - Generate compile_commands.json
+    /// * `gen_cc` - The compile_commands.json to append the entries of the sources to, if any
     /// * `relink` - Determine whether to re-link
-    pub fn build(&mut self, gen_cc: bool, relink: bool) {
+    ///
+    /// Returns the error output of the linker if the target failed to link
+    pub fn build(&mut self, gen_cc: Option<&Path>, relink: bool) -> Result<(), String> {
+        self.pre_build();
+        if self.target_config.typ == TargetType::HeaderOnly {
+            log(
+                LogLevel::Log,
+                &format!(
+                    "Target: {} is header-only, nothing to build",
+                    &self.target_config.name
+                ),
+            );
+            return Ok(());
+        }
+        self.build_pch();
         let mut to_link: bool = false;
 
         // if the source file needs to be build, then to link
@@ -228,13 +415,14 @@ impl<'a> Target<'a> {
         let total_srcs = self.srcs.len();
         let mut src_ccs = Vec::new();
         for src in &self.srcs {
-            let (to_build, _) = src.to_build(&self.path_hash);
+            let (to_build, reason) = src.to_build(&self.path_hash);
             if to_build {
+                log(explain_level(), &reason);
                 to_link = true;
                 link_causer.push(&src.path);
                 srcs_needed += 1;
             }
-            if gen_cc {
+            if gen_cc.is_some() {
                 src_ccs.push(self.gen_cc(src));
             }
         }
@@ -246,14 +434,17 @@ impl<'a> Target<'a> {
 
         // if the os config changes, then to link
         if relink {
+            log(
+                explain_level(),
+                &format!("\tOS config has changed, relinking: {}", &self.bin_path),
+            );
             to_link = true
         }
 
-        if gen_cc {
+        if let Some(cc_path) = gen_cc {
             let mut file = std::fs::OpenOptions::new()
-                .write(true)
                 .append(true)
-                .open("./compile_commands.json")
+                .open(cc_path)
                 .unwrap();
             for src_cc in src_ccs {
                 if let Err(e) = writeln!(file, "{},", src_cc) {
@@ -285,8 +476,9 @@ impl<'a> Target<'a> {
                     );
                 }
             }
-            if !Path::new(OBJ_DIR).exists() {
-                fs::create_dir(OBJ_DIR).unwrap_or_else(|why| {
+            let obj_dir = self.build_config.obj_dir();
+            if !Path::new(&obj_dir).exists() {
+                fs::create_dir_all(&obj_dir).unwrap_or_else(|why| {
                     log(
                         LogLevel::Error,
                         &format!("Couldn't create obj dir: {}", why),
@@ -299,14 +491,25 @@ impl<'a> Target<'a> {
                 LogLevel::Log,
                 &format!("Target: {} is up to date", &self.target_config.name),
             );
-            return;
+            return Ok(());
         }
 
         // parallel built
-        let progress_bar = Arc::new(Mutex::new(ProgressBar::new(srcs_needed as u64)));
-        let num_complete = Arc::new(Mutex::new(0));
+        // The bar counts the sources to compile, it is hidden when each compiled file is
+        // logged, when the log is quiet or JSON, or when not on a terminal
+        let progress_bar = if progress_enabled() {
+            ProgressBar::new(srcs_needed as u64).with_style(progress_style(""))
+        } else {
+            ProgressBar::hidden()
+        };
         let src_hash_to_update = Arc::new(Mutex::new(Vec::new()));
         let warns = Arc::new(Mutex::new(Vec::new()));
+        // Objects built against another precompiled header must not be taken from the cache
+        let compiler_hash = format!(
+            "{} {}",
+            Hasher::get_hash(COMPILER_HASH_KEY, &self.path_hash).unwrap_or_default(),
+            Hasher::get_hash(PCH_HASH_KEY, &self.path_hash).unwrap_or_default()
+        );
         self.srcs.par_iter().for_each(|src| {
             let (to_build, _message) = src.to_build(&self.path_hash);
             //log(LogLevel::Debug, &format!("{} => {}", src.path, to_build));
@@ -316,37 +519,26 @@ impl<'a> Target<'a> {
                     self.os_config,
                     self.target_config,
                     &self.dependant_libs,
+                    &compiler_hash,
+                    &self.pch_args(src),
+                    &progress_bar,
                 );
                 if let Some(warn) = warn {
                     warns.lock().unwrap().push(warn);
                 }
                 src_hash_to_update.lock().unwrap().push(src);
                 log(LogLevel::Info, &format!("Compiled: {}", src.path));
-                // If the RUXGO_LOG_LEVEL is not "Info" or "Debug", update the compilation progress bar
-                let log_level = std::env::var("RUXGO_LOG_LEVEL").unwrap_or("".to_string());
-                if !(log_level == "Info" || log_level == "Debug") {
-                    let mut num_complete = num_complete.lock().unwrap();
-                    *num_complete += 1;
-                    let progress_bar = progress_bar.lock().unwrap();
-                    let template = format!(
-                        "    {}{}",
-                        "Compiling :".cyan(),
-                        "[{bar:40.}] {pos}/{len} ({percent}%) {msg}[{elapsed_precise}] "
-                    );
-                    progress_bar.set_style(
-                        ProgressStyle::with_template(&template)
-                            .unwrap()
-                            .progress_chars("=>-"),
-                    );
-                    progress_bar.inc(1);
-                }
+                progress_bar.inc(1);
             }
         });
+        progress_bar.finish_with_message("done");
+        end_progress_line(&progress_bar);
         let warns = warns.lock().unwrap();
-        if warns.len() > 0 {
+        if !warns.is_empty() {
             log(LogLevel::Warn, "Warnings emitted during build:");
             for warn in warns.iter() {
                 log(LogLevel::Warn, &format!("\t{}", warn));
+                diagnostics::log_diagnostics(warn);
             }
         }
         for src in src_hash_to_update.lock().unwrap().iter() {
@@ -364,17 +556,245 @@ impl<'a> Target<'a> {
                 }
             }
             Hasher::save_hashes_to_file(&self.hash_file_path, &self.path_hash);
-            self.link(&self.dependant_libs);
+            self.link(&self.dependant_libs)?;
+            self.emit_asm()?;
+            self.post_build();
+        }
+        Ok(())
+    }
+
+    /// Returns the path the precompiled header is included from, its compiled file is
+    /// next to it, and whether it is a C++ header
+    fn pch_paths(&self) -> (String, bool) {
+        let pch = Path::new(&self.target_config.pch);
+        let name = pch.file_name().unwrap_or_default().to_string_lossy();
+        let cpp = pch.extension().is_none_or(|ext| ext != "h");
+        (
+            format!(
+                "{}/{}",
+                self.build_config.pch_dir(&self.target_config.name),
+                name
+            ),
+            cpp,
+        )
+    }
+
+    /// Returns the flags including the precompiled header, for the sources of its language
+    fn pch_args(&self, src: &Src) -> Vec<String> {
+        if self.target_config.pch.is_empty() {
+            return Vec::new();
+        }
+        let (include_path, cpp) = self.pch_paths();
+        if cpp == src.path.ends_with(".c") {
+            return Vec::new();
+        }
+        vec!["-include".to_string(), include_path]
+    }
+
+    /// Compiles the precompiled header if it changed or was never built, and then
+    /// rebuilds every source of the target
+    fn build_pch(&mut self) {
+        if self.target_config.pch.is_empty() {
+            return;
+        }
+        let pch = &self.target_config.pch;
+        if !Path::new(pch).is_file() {
+            log(
+                LogLevel::Error,
+                &format!(
+                    "Precompiled header {} of target {} not found",
+                    pch, &self.target_config.name
+                ),
+            );
+            std::process::exit(1);
+        }
+        let (include_path, cpp) = self.pch_paths();
+        // clang looks for `<header>.pch`, gcc for `<header>.gch`
+        let ext = if self.build_config.compiler.read().unwrap().contains("clang") {
+            "pch"
+        } else {
+            "gch"
+        };
+        let compiled = format!("{}.{}", include_path, ext);
+        let mut cmd = compile_args(
+            self.build_config,
+            self.os_config,
+            self.target_config,
+            &self.dependant_libs,
+            cpp,
+            &[],
+        );
+        cmd.push("-x".to_string());
+        cmd.push(if cpp { "c++-header" } else { "c-header" }.to_string());
+        cmd.push(pch.clone());
+        cmd.push("-o".to_string());
+        cmd.push(compiled.clone());
+
+        // The header is precompiled again if its command, the compiler or any header it
+        // includes changed
+        let mut headers = dependant_includes(pch, &self.target_config.include_dir);
+        headers.sort_unstable();
+        let mut key = format!(
+            "{} {}",
+            Hasher::get_hash(COMPILER_HASH_KEY, &self.path_hash).unwrap_or_default(),
+            cmd.join(" ")
+        );
+        for path in std::iter::once(pch).chain(&headers) {
+            key.push(' ');
+            key.push_str(&Hasher::hash_file(path).unwrap_or_default());
+        }
+        let hash = Hasher::hash_string(&key);
+        if Hasher::get_hash(PCH_HASH_KEY, &self.path_hash).as_ref() == Some(&hash)
+            && Path::new(&compiled).exists()
+        {
+            return;
+        }
+
+        // The compiled header is included through a stub next to it, which falls back to
+        // the real header if the compiled one can't be used
+        let pch_dir = Path::new(&include_path).parent().unwrap();
+        let stub = format!(
+            "#include \"{}\"\n",
+            fs::canonicalize(pch).unwrap().display()
+        );
+        if let Err(why) = fs::create_dir_all(pch_dir).and_then(|_| fs::write(&include_path, stub)) {
+            log(
+                LogLevel::Error,
+                &format!("Couldn't create {}: {}", include_path, why),
+            );
+            std::process::exit(1);
+        }
+        log(LogLevel::Log, &format!("Precompiling header: {}", pch));
+        log(
+            LogLevel::Info,
+            &format!("  Command: {}", tools::display(&cmd)),
+        );
+        let output = tools::command(&cmd).output().unwrap_or_else(|why| {
+            log(
+                LogLevel::Error,
+                &format!("Could not run {}: {}", &cmd[0], why),
+            );
+            std::process::exit(1);
+        });
+        if !output.status.success() {
+            log(LogLevel::Error, &format!("  Error: {}", pch));
+            log(
+                LogLevel::Error,
+                &format!("  Stderr: {}", String::from_utf8_lossy(&output.stderr)),
+            );
+            std::process::exit(1);
+        }
+
+        // Every source includes the header, so all of them are rebuilt
+        for src in &self.srcs {
+            self.path_hash.remove(&src.path);
+        }
+        self.path_hash.insert(PCH_HASH_KEY.to_string(), hash);
+    }
+
+    /// Returns the path of the elf of the target
+    pub fn elf(&self) -> &str {
+        // Only targets linked against the os have a separate elf, otherwise the binary is the elf
+        if self.os_config.name.is_empty() {
+            &self.bin_path
+        } else {
+            &self.elf_path
+        }
+    }
+
+    /// Writes the disassembly of the elf of an exe target to `<name>.asm` next to its binary,
+    /// if the target sets `emit_asm`
+    fn emit_asm(&self) -> Result<(), String> {
+        if !self.target_config.emit_asm || !self.target_config.typ.is_exe() {
+            return Ok(());
+        }
+        let asm_path = self.build_config.asm_path(&self.target_config.name);
+        let cmd = vec![
+            objdump(self.os_config).to_string(),
+            "-d".to_string(),
+            self.elf().to_string(),
+        ];
+        log(
+            LogLevel::Info,
+            &format!("  Command: {} > {}", tools::display(&cmd), asm_path),
+        );
+        let output = tools::command(&cmd).output().unwrap_or_else(|why| {
+            log(
+                LogLevel::Error,
+                &format!("Could not run {}: {}", &cmd[0], why),
+            );
+            std::process::exit(1);
+        });
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            log(
+                LogLevel::Error,
+                &format!("Disassembling target {} failed", &self.target_config.name),
+            );
+            log(LogLevel::Error, &format!("  Error: {}", &stderr));
+            return Err(stderr);
+        }
+        fs::write(&asm_path, &output.stdout).map_err(|why| {
+            let msg = format!("Could not write {}: {}", asm_path, why);
+            log(LogLevel::Error, &msg);
+            msg
+        })?;
+        log(LogLevel::Info, &format!(" Asm_path: {}", asm_path));
+        Ok(())
+    }
+
+    /// Runs the post_build command of the target, if any, after it has been linked
+    fn post_build(&self) {
+        if self.target_config.post_build.is_empty() {
+            return;
+        }
+        let elf_path = self.elf();
+        log(
+            LogLevel::Log,
+            &format!("Running post_build of target: {}", &self.target_config.name),
+        );
+        log(
+            LogLevel::Info,
+            &format!("  Command: {}", &self.target_config.post_build),
+        );
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&self.target_config.post_build)
+            .env("RUXGO_BIN_PATH", &self.bin_path)
+            .env("RUXGO_ELF_PATH", elf_path)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                log(
+                    LogLevel::Error,
+                    &format!(
+                        "post_build of target {} failed with {}",
+                        &self.target_config.name, status
+                    ),
+                );
+                std::process::exit(1);
+            }
+            Err(why) => {
+                log(
+                    LogLevel::Error,
+                    &format!("Could not run post_build command: {}", why),
+                );
+                std::process::exit(1);
+            }
         }
     }
 
     /// Links the dependant libs(or targets)
     /// # Arguments
     /// * `dep_targets` - The targets that this target depends on
-    pub fn link(&self, dep_targets: &Vec<Target>) {
+    ///
+    /// Returns the error output of the failed command if linking fails
+    pub fn link(&self, dep_targets: &Vec<Target>) -> Result<(), String> {
         let mut objs = Vec::new();
-        if !Path::new(BIN_DIR).exists() {
-            fs::create_dir_all(BIN_DIR).unwrap_or_else(|why| {
+        let bin_dir = self.build_config.bin_dir();
+        if !Path::new(&bin_dir).exists() {
+            fs::create_dir_all(&bin_dir).unwrap_or_else(|why| {
                 log(
                     LogLevel::Error,
                     &format!("Couldn't create build dir: {}", why),
@@ -385,270 +805,309 @@ impl<'a> Target<'a> {
         for src in &self.srcs {
             objs.push(&src.obj_name);
         }
-        let mut cmd = String::new();
-        let mut cmd_bin = String::new();
-        if self.target_config.typ == "dll" {
-            cmd = self.link_dll(objs, dep_targets);
-        } else if self.target_config.typ == "static" {
-            cmd = self.link_static(objs);
-        } else if self.target_config.typ == "object" {
-            cmd = self.link_object(objs, dep_targets);
-        } else if self.target_config.typ == "exe" {
-            (cmd, cmd_bin) = self.link_exe(objs, dep_targets);
+        let mut cmd_bin = Vec::new();
+        // Header-only deps only add include dirs when compiling, there is nothing to link
+        let dep_targets: Vec<&Target> = dep_targets
+            .iter()
+            .filter(|dep| dep.target_config.typ != TargetType::HeaderOnly)
+            .collect();
+        // Name the dependency instead of leaving the linker to report a missing file
+        if let Some(dep) = dep_targets
+            .iter()
+            .find(|dep| !Path::new(&dep.bin_path).exists())
+        {
+            let msg = format!(
+                "Dependency {} of target {} was not built, {} is missing",
+                dep.target_config.name, self.target_config.name, dep.bin_path
+            );
+            log(LogLevel::Error, &msg);
+            return Err(msg);
         }
+        let cmd = match self.target_config.typ {
+            TargetType::HeaderOnly => return Ok(()),
+            TargetType::Dll => self.link_dll(objs, &dep_targets),
+            TargetType::Static => self.link_static(objs),
+            TargetType::Object => self.link_object(objs, &dep_targets),
+            TargetType::Exe | TargetType::Test => {
+                let (cmd, bin) = self.link_exe(objs, &dep_targets);
+                cmd_bin = bin;
+                cmd
+            }
+        };
 
         log(
             LogLevel::Log,
             &format!("Linking target: {}", &self.target_config.name),
         );
-        log(LogLevel::Info, &format!("  Command: {}", &cmd));
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(&cmd)
-            .output()
-            .expect("failed to execute process");
+        log(
+            LogLevel::Info,
+            &format!("  Command: {}", tools::display(&cmd)),
+        );
+        let output = tools::command(&cmd).output().unwrap_or_else(|why| {
+            log(
+                LogLevel::Error,
+                &format!("Could not run {}: {}", &cmd[0], why),
+            );
+            std::process::exit(1);
+        });
         if output.status.success() {
             log(LogLevel::Log, "Linking successful");
             Hasher::save_hashes_to_file(&self.hash_file_path, &self.path_hash); // ? check if repeated
         } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
             log(LogLevel::Error, "Linking failed");
-            log(LogLevel::Error, &format!(" Command: {}", &cmd));
             log(
                 LogLevel::Error,
-                &format!("  Error: {}", String::from_utf8_lossy(&output.stderr)),
+                &format!(" Command: {}", tools::display(&cmd)),
             );
-            std::process::exit(1);
+            log(LogLevel::Error, &format!("  Error: {}", &stderr));
+            return Err(stderr);
         }
         if !cmd_bin.is_empty() {
-            let output_bin = Command::new("sh")
-                .arg("-c")
-                .arg(&cmd_bin)
-                .output()
-                .expect("failed to execute process");
+            let output_bin = tools::command(&cmd_bin).output().unwrap_or_else(|why| {
+                log(
+                    LogLevel::Error,
+                    &format!("Could not run {}: {}", &cmd_bin[0], why),
+                );
+                std::process::exit(1);
+            });
             if output_bin.status.success() {
                 log(LogLevel::Info, &format!(" Bin_path: {}", &self.bin_path));
-                log(LogLevel::Info, &format!(" Elf_path: {}", &self.elf_path));
+                if !self.os_config.name.is_empty() {
+                    log(LogLevel::Info, &format!(" Elf_path: {}", &self.elf_path));
+                }
             } else {
-                log(LogLevel::Error, "  Rust-objcopy failed");
-                log(LogLevel::Error, &format!(" Command: {}", &cmd_bin));
+                let stderr = String::from_utf8_lossy(&output_bin.stderr).to_string();
+                log(LogLevel::Error, &format!("  {} failed", &cmd_bin[0]));
                 log(
                     LogLevel::Error,
-                    &format!("  Error: {}", String::from_utf8_lossy(&output_bin.stderr)),
+                    &format!(" Command: {}", tools::display(&cmd_bin)),
                 );
-                std::process::exit(1);
+                log(LogLevel::Error, &format!("  Error: {}", &stderr));
+                return Err(stderr);
             }
         }
+        Ok(())
     }
 
-    /// Links the dll targets
-    fn link_dll(&self, objs: Vec<&String>, dep_targets: &Vec<Target>) -> String {
-        let mut cmd = String::new();
+    /// Returns the linker of the target, the compiler if no linker is set
+    fn linker(&self) -> Vec<String> {
         if !self.target_config.linker.is_empty() {
-            cmd.push_str(&self.target_config.linker);
+            tools::split_args(&self.target_config.linker)
         } else {
-            cmd.push_str(&self.build_config.compiler.read().unwrap());
+            tools::split_args(&self.build_config.compiler.read().unwrap())
         }
-        cmd.push_str(" -shared");
-        cmd.push_str(" -o ");
-        cmd.push_str(&self.bin_path);
-        for obj in objs {
-            cmd.push(' ');
-            cmd.push_str(obj);
+    }
+
+    /// Returns the include and -l flags of a dll dependency
+    fn dll_dep_flags(&self, dep_target: &Target) -> Vec<String> {
+        let mut flags = Vec::new();
+        for include in &dep_target.target_config.include_dir {
+            flags.push(format!("-I{}", include));
         }
-        cmd.push(' ');
+        flags.push(dep_target.target_config.name.replace("lib", "-l"));
+        flags
+    }
+
+    /// Links the dll targets
+    fn link_dll(&self, objs: Vec<&String>, dep_targets: &[&Target]) -> Vec<String> {
+        let mut cmd = self.linker();
+        cmd.push("-shared".to_string());
+        cmd.push("-o".to_string());
+        cmd.push(self.bin_path.clone());
+        cmd.extend(objs.into_iter().cloned());
 
         // link other dependant libraries
         for dep_target in dep_targets {
-            dep_target
-                .target_config
-                .include_dir
-                .iter()
-                .for_each(|include| {
-                    cmd.push_str(" -I");
-                    cmd.push_str(include);
-                });
-            cmd.push(' ');
-            let lib_name = dep_target.target_config.name.clone();
-            let lib_name = lib_name.replace("lib", "-l");
-            cmd.push_str(&lib_name);
-            cmd.push(' ');
+            cmd.extend(self.dll_dep_flags(dep_target));
         }
 
         // add -L library search path
         if !self.dependant_libs.is_empty() {
-            cmd.push_str(" -L");
-            cmd.push_str(BIN_DIR);
-            cmd.push_str(" -Wl,-rpath,\'$ORIGIN\' "); // '$ORIGIN' represents the directory path where the executable is located
-            cmd.push(' ');
+            cmd.push(format!("-L{}", self.build_config.bin_dir()));
+            // $ORIGIN represents the directory path where the executable is located
+            cmd.push("-Wl,-rpath,$ORIGIN".to_string());
         }
+        cmd.extend(self.system_lib_flags());
 
         // add ldflags
-        cmd.push_str(&self.target_config.ldflags);
+        cmd.extend(tools::split_args(&self.target_config.ldflags));
+        cmd.extend(platform_ldflags(self.os_config));
+        if self.target_config.emit_map {
+            cmd.push(format!(
+                "-Wl,-Map={}",
+                self.build_config.map_path(&self.target_config.name)
+            ));
+        }
 
         cmd
     }
 
+    /// Returns the `-L` flags of `lib_dirs` followed by the `-l` flags of `libs`
+    fn system_lib_flags(&self) -> Vec<String> {
+        let lib_dirs = self.target_config.lib_dirs.iter();
+        let libs = self.target_config.libs.iter();
+        lib_dirs
+            .map(|dir| format!("-L{}", dir))
+            .chain(libs.map(|lib| format!("-l{}", lib)))
+            .collect()
+    }
+
     /// Links the static targets
-    fn link_static(&self, objs: Vec<&String>) -> String {
-        let mut cmd = String::new();
-        cmd.push_str(&self.target_config.archive);
-        cmd.push(' ');
-        cmd.push_str(&self.target_config.ldflags);
-        cmd.push(' ');
-        cmd.push_str(&self.bin_path);
-        for obj in objs {
-            cmd.push(' ');
-            cmd.push_str(obj);
-        }
+    fn link_static(&self, objs: Vec<&String>) -> Vec<String> {
+        let mut cmd = tools::split_args(&self.target_config.archive);
+        cmd.extend(tools::split_args(&self.target_config.ldflags));
+        cmd.push(self.bin_path.clone());
+        cmd.extend(objs.into_iter().cloned());
 
         cmd
     }
 
     /// Links the object targets
-    fn link_object(&self, objs: Vec<&String>, dep_targets: &Vec<Target>) -> String {
-        let mut cmd = String::new();
-        if !self.target_config.linker.is_empty() {
-            cmd.push_str(&self.target_config.linker);
-        } else {
-            cmd.push_str(&self.build_config.compiler.read().unwrap());
-        }
-        cmd.push(' ');
-        cmd.push_str(&self.target_config.ldflags);
-        cmd.push_str(" -o ");
-        cmd.push_str(&self.bin_path);
-        for obj in objs {
-            cmd.push(' ');
-            cmd.push_str(obj);
-        }
+    fn link_object(&self, objs: Vec<&String>, dep_targets: &[&Target]) -> Vec<String> {
+        let mut cmd = self.linker();
+        cmd.extend(tools::split_args(&self.target_config.ldflags));
+        cmd.push("-o".to_string());
+        cmd.push(self.bin_path.clone());
+        cmd.extend(objs.into_iter().cloned());
         // link other dependant libraries
         for dep_target in dep_targets {
-            cmd.push(' ');
-            cmd.push_str(&dep_target.bin_path);
+            cmd.push(dep_target.bin_path.clone());
         }
 
         cmd
     }
 
-    /// Links the executable targets
-    fn link_exe(&self, objs: Vec<&String>, dep_targets: &Vec<Target>) -> (String, String) {
-        let mut cmd = String::new();
-        let mut cmd_bin = String::new();
-        if !self.target_config.linker.is_empty() {
-            cmd.push_str(&self.target_config.linker);
-        } else {
-            cmd.push_str(&self.build_config.compiler.read().unwrap());
-        }
-        cmd.push(' ');
+    /// Links the executable targets, returns the link command and the command run on the result,
+    /// objcopy for the OS or strip for native exes, empty if there is none
+    fn link_exe(&self, objs: Vec<&String>, dep_targets: &[&Target]) -> (Vec<String>, Vec<String>) {
+        let mut cmd = self.linker();
+        let mut cmd_bin = Vec::new();
 
         // consider os config
         if !self.os_config.name.is_empty() {
+            if !self.target_config.libs.is_empty() {
+                log(
+                    LogLevel::Warn,
+                    &format!(
+                        "Ignoring libs of target {}, system libraries are not linked into the OS",
+                        self.target_config.name
+                    ),
+                );
+            }
             // add os_ldflags and target_config.ldflags
-            let mut ldflags = String::new();
-            let mut os_ldflags = String::new();
-            os_ldflags.push_str("-nostdlib -static -no-pie --gc-sections");
-            let ld_script = format!(
-                "{}/linker_{}.lds",
-                LD_SCRIPT.as_str(),
+            cmd.extend(["-nostdlib", "-static", "-no-pie", "--gc-sections"].map(String::from));
+            let ld_script = self.os_config.ruxos_path(&format!(
+                "modules/ruxhal/linker_{}.lds",
                 self.os_config.platform.name
-            );
-            os_ldflags.push_str(&format!(" -T{}", &ld_script));
+            ));
+            if !Path::new(&ld_script).exists() {
+                log(
+                    LogLevel::Error,
+                    &format!(
+                        "Linker script {} not found, set `ruxos_dir` in [os] to the directory of the Ruxos sources",
+                        ld_script
+                    ),
+                );
+                std::process::exit(1);
+            }
+            cmd.push(format!("-T{}", ld_script));
             if self.os_config.platform.arch == *"x86_64" {
-                os_ldflags.push_str(" --no-relax");
+                cmd.push("--no-relax".to_string());
             }
-            ldflags.push_str(&os_ldflags);
-            ldflags.push(' ');
-            ldflags.push_str(&self.target_config.ldflags);
-            cmd.push_str(&ldflags);
+            cmd.extend(platform_ldflags(self.os_config));
+            cmd.extend(tools::split_args(&self.target_config.ldflags));
 
             // link ulib and os
+            let mode = if !self.os_config.platform.mode.is_empty() {
+                &self.os_config.platform.mode
+            } else {
+                "debug"
+            };
             if self.os_config.ulib == "ruxlibc" {
-                cmd.push(' ');
-                cmd.push_str(RUXLIBC_BIN);
-                cmd.push(' ');
-                let mode = if !self.os_config.platform.mode.is_empty() {
-                    &self.os_config.platform.mode
-                } else {
-                    "debug"
-                };
-                cmd.push_str(&format!(
-                    "{}/target/{}/{}/{}",
-                    BUILD_DIR, &self.os_config.platform.target, mode, RUXLIBC_RUST_LIB
+                cmd.push(format!("{}/libc.a", self.build_config.bin_dir()));
+                cmd.push(format!(
+                    "{}/{}/{}/{}",
+                    self.build_config.target_dir(),
+                    &self.os_config.platform.target,
+                    mode,
+                    RUXLIBC_RUST_LIB
                 ));
             } else if self.os_config.ulib == "ruxmusl" {
-                cmd.push(' ');
-                cmd.push_str(RUXMUSL_BIN);
-                cmd.push(' ');
-                let mode = if !self.os_config.platform.mode.is_empty() {
-                    &self.os_config.platform.mode
-                } else {
-                    "debug"
-                };
-                cmd.push_str(&format!(
-                    "{}/target/{}/{}/{}",
-                    BUILD_DIR, &self.os_config.platform.target, mode, RUXMUSL_RUST_LIB
+                cmd.push(format!(
+                    "{}/install/lib/libc.a",
+                    self.build_config.ruxmusl_dir()
+                ));
+                cmd.push(format!(
+                    "{}/{}/{}/{}",
+                    self.build_config.target_dir(),
+                    &self.os_config.platform.target,
+                    mode,
+                    RUXMUSL_RUST_LIB
                 ));
             }
 
             // link other obj
-            for obj in objs {
-                cmd.push(' ');
-                cmd.push_str(obj);
-            }
+            cmd.extend(objs.into_iter().cloned());
 
             // link other dependant libraries
             for dep_target in dep_targets {
-                cmd.push(' ');
-                cmd.push_str(&dep_target.bin_path);
+                cmd.push(dep_target.bin_path.clone());
+            }
+            cmd.push("-o".to_string());
+            cmd.push(self.elf_path.clone());
+            // the os link calls the linker directly, like the other os_ldflags
+            if self.target_config.emit_map {
+                cmd.push(format!(
+                    "-Map={}",
+                    self.build_config.map_path(&self.target_config.name)
+                ));
             }
-            cmd.push_str(" -o ");
-            cmd.push_str(&self.elf_path);
 
             // generate a bin file
-            cmd_bin.push_str(&format!(
-                "rust-objcopy --binary-architecture={}",
-                &self.os_config.platform.arch
-            ));
-            cmd_bin.push(' ');
-            cmd_bin.push_str(&self.elf_path);
-            cmd_bin.push_str(" --strip-all -O binary ");
-            cmd_bin.push_str(&self.bin_path);
+            cmd_bin = vec![
+                "rust-objcopy".to_string(),
+                format!("--binary-architecture={}", &self.os_config.platform.arch),
+                self.elf_path.clone(),
+                if self.target_config.strip == "never" {
+                    "--strip-debug".to_string()
+                } else {
+                    "--strip-all".to_string()
+                },
+                "-O".to_string(),
+                "binary".to_string(),
+                self.bin_path.clone(),
+            ];
         } else {
-            cmd.push_str(" -o ");
-            cmd.push_str(&self.bin_path);
-            for obj in objs {
-                cmd.push(' ');
-                cmd.push_str(obj);
-            }
-            cmd.push(' ');
+            cmd.push("-o".to_string());
+            cmd.push(self.bin_path.clone());
+            cmd.extend(objs.into_iter().cloned());
             // link other dependant libraries
             for dep_target in dep_targets {
-                if dep_target.target_config.typ == "object"
-                    || dep_target.target_config.typ == "static"
+                if dep_target.target_config.typ == TargetType::Object
+                    || dep_target.target_config.typ == TargetType::Static
                 {
-                    cmd.push_str(&dep_target.bin_path);
-                    cmd.push(' ');
-                } else if dep_target.target_config.typ == "dll" {
-                    dep_target
-                        .target_config
-                        .include_dir
-                        .iter()
-                        .for_each(|include| {
-                            cmd.push_str(" -I");
-                            cmd.push_str(include);
-                        });
-                    cmd.push(' ');
-                    let lib_name = dep_target.target_config.name.clone();
-                    let lib_name = lib_name.replace("lib", "-l");
-                    cmd.push_str(&lib_name);
-                    cmd.push(' ');
+                    cmd.push(dep_target.bin_path.clone());
+                } else if dep_target.target_config.typ == TargetType::Dll {
+                    cmd.extend(self.dll_dep_flags(dep_target));
                     // added -L library search path
-                    cmd.push_str(" -L");
-                    cmd.push_str(BIN_DIR);
-                    cmd.push_str(" -Wl,-rpath,\'$ORIGIN\' "); // '$ORIGIN' represents the directory path where the executable is located
-                    cmd.push(' ');
+                    cmd.push(format!("-L{}", self.build_config.bin_dir()));
+                    // $ORIGIN represents the directory path where the executable is located
+                    cmd.push("-Wl,-rpath,$ORIGIN".to_string());
                 }
             }
-            cmd.push_str(&self.target_config.ldflags);
+            // system libs come last so that they resolve the symbols of everything before
+            cmd.extend(self.system_lib_flags());
+            cmd.extend(tools::split_args(&self.target_config.ldflags));
+            if self.target_config.emit_map {
+                cmd.push(format!(
+                    "-Wl,-Map={}",
+                    self.build_config.map_path(&self.target_config.name)
+                ));
+            }
+            if self.target_config.strip == "always" {
+                cmd_bin = vec!["strip".to_string(), self.bin_path.clone()];
+            }
         }
 
         (cmd, cmd_bin)
@@ -656,47 +1115,23 @@ impl<'a> Target<'a> {
 
     /// Generates the compile_commands.json file for a src
     fn gen_cc(&self, src: &Src) -> String {
-        let mut cc = String::new();
-        cc.push_str("{\n"); // Json start
-        if *self.build_config.compiler.read().unwrap() == "clang++"
-            || *self.build_config.compiler.read().unwrap() == "g++"
-        {
-            cc.push_str("\t\"command\": \"c++");
-        } else if *self.build_config.compiler.read().unwrap() == "clang"
-            || *self.build_config.compiler.read().unwrap() == "gcc"
-        {
-            cc.push_str("\t\"command\": \"cc");
-        } else {
-            log(
-                LogLevel::Error,
-                &format!(
-                    "Compiler: {} is not supported",
-                    &self.build_config.compiler.read().unwrap()
-                ),
-            );
-            log(
-                LogLevel::Error,
-                "Supported compilers: clang++, g++, clang, gcc",
-            );
-            std::process::exit(1);
-        }
-        cc.push_str(" -c -o ");
-        cc.push_str(&src.obj_name);
-        self.target_config.include_dir.iter().for_each(|include| {
-            cc.push_str(" -I");
-            cc.push_str(include);
-        });
-
-        for lib in &self.dependant_libs {
-            lib.target_config.include_dir.iter().for_each(|include| {
-                cc.push_str(" -I");
-                cc.push_str(include);
-            });
-        }
-
-        cc.push(' ');
+        let driver = match self.build_config.compiler.read().unwrap().as_str() {
+            "clang++" | "g++" => "c++",
+            "clang" | "gcc" => "cc",
+            compiler => {
+                log(
+                    LogLevel::Error,
+                    &format!("Compiler: {} is not supported", compiler),
+                );
+                log(
+                    LogLevel::Error,
+                    "Supported compilers: clang++, g++, clang, gcc",
+                );
+                std::process::exit(1);
+            }
+        };
+        // Backquoted commands in the cflags are replaced by their output
         let cflags = &self.target_config.cflags;
-
         let subcmds = cflags.split('`').collect::<Vec<&str>>();
         // Take even entries are non-subcmds and odd entries are subcmds
         let (subcmds, non_subcmds): (Vec<String>, String) = subcmds.iter().enumerate().fold(
@@ -712,8 +1147,7 @@ impl<'a> Target<'a> {
             },
         );
 
-        cc.push_str(&non_subcmds);
-
+        let mut cflags = non_subcmds;
         for subcmd in subcmds {
             let cmd_output = Command::new("sh")
                 .arg("-c")
@@ -723,7 +1157,7 @@ impl<'a> Target<'a> {
             if cmd_output.status.success() {
                 let stdout = String::from_utf8_lossy(&cmd_output.stdout);
                 let stdout = stdout.replace('\n', " ");
-                cc.push_str(&stdout);
+                cflags.push_str(&stdout);
             } else {
                 let stderr = String::from_utf8_lossy(&cmd_output.stderr);
                 log(
@@ -735,14 +1169,31 @@ impl<'a> Target<'a> {
             }
         }
 
-        #[cfg(target_os = "linux")]
-        if self.target_config.typ == "dll" {
-            cc.push_str("-fPIC ");
-        }
+        let target_config = TargetConfig {
+            cflags,
+            ..self.target_config.clone()
+        };
+
+        let mut cmd = compile_args(
+            self.build_config,
+            self.os_config,
+            &target_config,
+            &self.dependant_libs,
+            !src.path.ends_with(".c"),
+            &self.pch_args(src),
+        );
+        cmd[0] = driver.to_string();
+        cmd.push("-c".to_string());
+        cmd.push(src.path.clone());
+        cmd.push("-o".to_string());
+        cmd.push(src.obj_name.clone());
 
-        cc.push_str(&src.path);
-        cc.push_str("\",\n"); // Json end
-                              // other info: "directory","file"
+        let mut cc = String::new();
+        cc.push_str("{\n"); // Json start
+        cc.push_str("\t\"command\": ");
+        cc.push_str(&serde_json::Value::String(tools::display(&cmd)).to_string());
+        cc.push_str(",\n");
+        // other info: "directory","file"
         let mut dirent = String::new();
         dirent.push_str("\t\"directory\": \"");
         dirent.push_str(
@@ -771,65 +1222,47 @@ impl<'a> Target<'a> {
         cc.push_str(&fileent);
 
         cc.push_str("\n}");
-        #[cfg(target_os = "linux")]
+        #[cfg(not(target_os = "windows"))]
         return cc.replace("\\\\", "/");
         #[cfg(target_os = "windows")]
         return cc;
     }
 
-    /// Recursively gets all the source files in the given root path
-    /// # Notes
-    /// The source is first filtered through the `src_only` and `src_exclude` fields
-    fn get_srcs(&mut self, root_path: &str) {
-        for entry in WalkDir::new(root_path).into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
-            let path_str = path.to_str().unwrap_or_default();
-            #[cfg(target_os = "windows")]
-            let path_str = path_str.replace('\\', "/");
-            if self.should_exclude(path_str) {
-                continue;
-            }
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if (ext == "cpp" || ext == "c") && self.should_include(path_str) {
-                        self.add_src(path_str.to_owned());
-                    }
-                }
+    /// Adds the source files of the target, as found by `TargetConfig::src_files`
+    fn get_srcs(&mut self) {
+        // Files of the same name in different roots would be compiled to the same object
+        if self.target_config.src.len() > 1 {
+            if let Err(e) = self.target_config.check_duplicate_srcs() {
+                log(LogLevel::Error, &e.to_string());
+                std::process::exit(1);
             }
         }
-    }
-
-    /// Exclusion logic: Check if the path is in src_exclude
-    fn should_exclude(&self, path: &str) -> bool {
-        self.target_config
-            .src_exclude
-            .iter()
-            .any(|excluded| path.contains(excluded))
-    }
-
-    /// Inclusion logic: Apply src_only logic only to files
-    fn should_include(&self, path: &str) -> bool {
-        if self.target_config.src_only.is_empty() {
-            return true;
+        for path in self.target_config.src_files() {
+            self.add_src(path);
+        }
+        if self.srcs.is_empty() && !self.target_config.src_only.is_empty() {
+            log(
+                LogLevel::Warn,
+                &format!(
+                    "No source file of target {} matches src_only {:?}",
+                    self.target_config.name, self.target_config.src_only
+                ),
+            );
         }
-        self.target_config
-            .src_only
-            .iter()
-            .any(|included| path.contains(included))
     }
 
     /// Adds a source file to the target's srcs field
     fn add_src(&mut self, path: String) {
         let name = Target::get_src_name(&path);
         let obj_name = self.get_src_obj_name(&name);
-        let dependant_includes = self.get_dependant_includes(&path);
+        let dependant_includes = dependant_includes(&path, &self.target_config.include_dir);
         let bin_path = self.bin_path.clone();
         self.srcs
             .push(Src::new(path, name, obj_name, bin_path, dependant_includes));
     }
 
     /// Returns the file name without the extension from the path
-    fn get_src_name(path: &str) -> String {
+    pub fn get_src_name(path: &str) -> String {
         let path_buf = PathBuf::from(path);
         let file_name = path_buf.file_name().unwrap().to_str().unwrap();
         let name = file_name.split('.').next().unwrap();
@@ -838,84 +1271,8 @@ impl<'a> Target<'a> {
 
     /// Returns the object file name corresponding to the source file
     fn get_src_obj_name(&self, src_name: &str) -> String {
-        let mut obj_name = String::new();
-        obj_name.push_str(OBJ_DIR);
-        obj_name.push('/');
-        obj_name.push_str(&self.target_config.name);
-        obj_name.push('-');
-        obj_name.push_str(src_name);
-        obj_name.push_str(".o");
-        obj_name
-    }
-
-    /// Returns a vector of .h or .hpp files the given C/C++ depends on
-    fn get_dependant_includes(&mut self, path: &str) -> Vec<String> {
-        let mut result = HashSet::new();
-        // Use the stack to handle recursive paths
-        let mut to_process = vec![path.to_string()];
-        let include_substrings: HashSet<String> = self
-            .get_include_substrings(path)
-            .unwrap_or_default()
-            .into_iter()
-            .collect();
-        if include_substrings.is_empty() {
-            return Vec::new();
-        }
-        while let Some(current_path) = to_process.pop() {
-            if !result.insert(current_path.clone()) {
-                // If this path has already been processed, skip it
-                continue;
-            }
-            if !self.dependant_includes.contains_key(&current_path) {
-                self.dependant_includes
-                    .insert(current_path.clone(), Vec::new());
-            }
-
-            for include_dir in &self.target_config.include_dir {
-                for entry in WalkDir::new(include_dir).into_iter().filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_file()
-                        && include_substrings
-                            .iter()
-                            .any(|substring| path.ends_with(substring))
-                    {
-                        let path_str = path.to_string_lossy().to_string();
-                        if result.insert(path_str.clone()) {
-                            self.dependant_includes
-                                .get_mut(&current_path)
-                                .unwrap()
-                                .push(path_str.clone());
-                            to_process.push(path_str);
-                        }
-                    }
-                }
-            }
-        }
-
-        result.into_iter().collect()
-    }
-
-    /// Returns a list of substrings that contain "#include \"" in the source file
-    fn get_include_substrings(&self, path: &str) -> Option<Vec<String>> {
-        let file = std::fs::File::open(path);
-        if file.is_err() {
-            // If the software is self-developed, enable this debug option
-            //log(LogLevel::Debug, &format!("Failed to get include substrings for file: {}", path));
-            return None;
-        }
-        let mut file = file.unwrap();
-        let mut buf = String::new();
-        file.read_to_string(&mut buf).unwrap();
-
-        let lines = buf.lines();
-        let mut include_substrings = Vec::new();
-        for line in lines {
-            if line.starts_with("#include \"") {
-                let include_path = line.split('\"').nth(1).unwrap().to_owned();
-                include_substrings.push(include_path);
-            }
-        }
-        Some(include_substrings)
+        self.build_config
+            .obj_path(&self.target_config.name, src_name)
     }
 }
 
@@ -967,117 +1324,345 @@ impl Src {
         )
     }
 
+    /// Returns the path of the cached object for this source, keyed on the hashes of the
+    /// compiler, the compile command and the contents of the source and its includes
+    /// # Arguments
+    /// * `cmd` - The compile command, without the object path
+    /// * `compiler_hash` - The hash of the compiler version
+    fn cached_obj_path(
+        &self,
+        build_config: &BuildConfig,
+        cmd: &str,
+        compiler_hash: &str,
+    ) -> String {
+        let mut key = format!("{} {}", compiler_hash, cmd);
+        for path in std::iter::once(&self.path).chain(&self.dependant_includes) {
+            key.push(' ');
+            key.push_str(&Hasher::hash_file(path).unwrap_or_default());
+        }
+        format!(
+            "{}/{}.o",
+            build_config.obj_cache_dir(),
+            Hasher::hash_string(&key)
+        )
+    }
+
     /// Builds the source files
+    /// If the object cache is enabled, an object built before from the same inputs is copied
+    /// instead of invoking the compiler
+    #[allow(clippy::too_many_arguments)]
     fn build(
         &self,
         build_config: &BuildConfig,
         os_config: &OSConfig,
         target_config: &TargetConfig,
         dependant_libs: &Vec<Target>,
+        compiler_hash: &str,
+        pch_args: &[String],
+        progress_bar: &ProgressBar,
     ) -> Option<String> {
-        let mut cmd = String::new();
-        cmd.push_str(&build_config.compiler.read().unwrap());
-        // If os exist
-        let mut os_cflags = String::new();
-        if !os_config.name.is_empty() {
-            os_cflags.push_str("-nostdinc -fno-builtin -ffreestanding -Wall");
-            if os_config.ulib == "ruxlibc" {
-                os_cflags.push_str(" -I");
-                os_cflags.push_str(RUXLIBC_INC.as_str());
-                let (_, lib_feats) = cfg_feat(os_config);
-                // generate the preprocessing macro definition
-                for lib_feat in lib_feats {
-                    let processed_lib_feat = lib_feat.to_uppercase().replace('-', "_");
-                    os_cflags.push_str(&format!(" -DRUX_CONFIG_{}", &processed_lib_feat));
-                }
-                os_cflags.push_str(&format!(
-                    " -DRUX_CONFIG_{}",
-                    os_config.platform.log.to_uppercase()
-                ));
-            } else if os_config.ulib == "ruxmusl" {
-                os_cflags.push_str(" -I");
-                os_cflags.push_str(RUXMUSL_INC);
-            }
-            if os_config.platform.mode == "release" {
-                os_cflags.push_str(" -O3");
-            }
-            if os_config.platform.arch == "riscv64" {
-                os_cflags.push_str(" -march=rv64gc -mabi=lp64d -mcmodel=medany");
-            }
-            if !os_config.features.contains(&"fp_simd".to_string()) {
-                if os_config.platform.arch == *"x86_64".to_string() {
-                    os_cflags.push_str(" -mno-sse");
-                } else if os_config.platform.arch == *"aarch64".to_string() {
-                    os_cflags.push_str(" -mgeneral-regs-only");
-                }
+        progress_bar.set_message(self.name.clone());
+        let mut cmd = compile_args(
+            build_config,
+            os_config,
+            target_config,
+            dependant_libs,
+            !self.path.ends_with(".c"),
+            pch_args,
+        );
+        cmd.push("-c".to_string());
+        cmd.push(self.path.clone());
+
+        // The object path is left out of the cache key, it differs between targets
+        let cached_obj = build_config
+            .obj_cache
+            .then(|| self.cached_obj_path(build_config, &cmd.join(" "), compiler_hash));
+        if let Some(cached_obj) = &cached_obj {
+            if Path::new(cached_obj).exists() && fs::copy(cached_obj, &self.obj_name).is_ok() {
+                log(LogLevel::Info, &format!("Cache hit: {}", &self.name));
+                return None;
             }
         }
-
-        // Add cflags
-        let mut cflags = String::new();
-        if !os_cflags.is_empty() {
-            cflags.push_str(&os_cflags);
-            cflags.push(' ');
-        }
-        cflags.push_str(&target_config.cflags);
-        cmd.push(' ');
-        cmd.push_str(&cflags);
-        target_config.include_dir.iter().for_each(|include| {
-            cmd.push_str(" -I");
-            cmd.push_str(include);
-        });
-        cmd.push_str(" -o ");
-        cmd.push_str(&self.obj_name);
-
-        // consider some includes in other depandant_libs
-        for dependant_lib in dependant_libs {
-            dependant_lib
-                .target_config
-                .include_dir
-                .iter()
-                .for_each(|include| {
-                    cmd.push_str(" -I");
-                    cmd.push_str(include);
-                });
-        }
-
-        cmd.push_str(" -c ");
-        cmd.push_str(&self.path);
-
-        if target_config.typ == "dll" {
-            cmd.push_str(" -fPIC");
-        }
+        cmd.push("-o".to_string());
+        cmd.push(self.obj_name.clone());
 
         log(LogLevel::Info, &format!("Building: {}", &self.name));
-        log(LogLevel::Info, &format!("  Command: {}", &cmd));
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(&cmd)
-            .output()
-            .expect("failed to execute process");
+        log(
+            LogLevel::Info,
+            &format!("  Command: {}", tools::display(&cmd)),
+        );
+        let output = tools::command(&cmd).output().unwrap_or_else(|why| {
+            abandon_progress(progress_bar, &self.name);
+            log(
+                LogLevel::Error,
+                &format!("Could not run {}: {}", &cmd[0], why),
+            );
+            std::process::exit(1);
+        });
         if output.status.success() {
             log(LogLevel::Info, &format!("  Success: {}", &self.name));
+            if let Some(cached_obj) = &cached_obj {
+                // A missing cache entry only costs a recompile, so failures are not fatal
+                if let Err(why) = fs::create_dir_all(build_config.obj_cache_dir())
+                    .and_then(|_| fs::copy(&self.obj_name, cached_obj))
+                {
+                    log(
+                        LogLevel::Warn,
+                        &format!("Could not cache object {}: {}", &self.obj_name, why),
+                    );
+                }
+            }
             let stdout = String::from_utf8_lossy(&output.stdout);
-            if stdout.len() > 0 {
+            if !stdout.is_empty() {
                 log(LogLevel::Info, &format!("  Stdout: {}", stdout));
             }
             let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.len() > 0 {
+            if !stderr.is_empty() {
                 return Some(stderr.to_string());
             }
             None
         } else {
+            abandon_progress(progress_bar, &self.name);
             log(LogLevel::Error, &format!("  Error: {}", &self.name));
-            log(LogLevel::Error, &format!("  Command: {}", &cmd));
             log(
                 LogLevel::Error,
-                &format!("  Stdout: {}", String::from_utf8_lossy(&output.stdout)),
+                &format!("  Command: {}", tools::display(&cmd)),
             );
             log(
                 LogLevel::Error,
-                &format!("  Stderr: {}", String::from_utf8_lossy(&output.stderr)),
+                &format!("  Stdout: {}", String::from_utf8_lossy(&output.stdout)),
             );
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            log(LogLevel::Error, &format!("  Stderr: {}", stderr));
+            diagnostics::log_diagnostics(&stderr);
             std::process::exit(1);
         }
     }
 }
+
+/// Returns each source file of the target mapped to the sorted headers it depends on,
+/// without running its pre_build command or the compiler
+/// # Arguments
+/// * `target_config` - The target configuration
+pub fn src_dependencies(target_config: &TargetConfig) -> BTreeMap<String, Vec<String>> {
+    if target_config.typ == TargetType::HeaderOnly {
+        return BTreeMap::new();
+    }
+    target_config
+        .src_files()
+        .into_iter()
+        .map(|src| {
+            let mut headers: Vec<String> = dependant_includes(&src, &target_config.include_dir)
+                .into_iter()
+                .filter(|header| *header != src)
+                .collect();
+            headers.sort_unstable();
+            (src, headers)
+        })
+        .collect()
+}
+
+/// Returns the source file and the headers of the include dirs it depends on
+/// # Arguments
+/// * `path` - The source file
+/// * `include_dirs` - The dirs to look the included headers up in
+fn dependant_includes(path: &str, include_dirs: &[String]) -> Vec<String> {
+    let mut result = HashSet::new();
+    // Use the stack to handle recursive paths
+    let mut to_process = vec![path.to_string()];
+    let include_substrings: HashSet<String> = include_substrings(path)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    if include_substrings.is_empty() {
+        return Vec::new();
+    }
+    while let Some(current_path) = to_process.pop() {
+        if !result.insert(current_path.clone()) {
+            // If this path has already been processed, skip it
+            continue;
+        }
+
+        for include_dir in include_dirs {
+            for entry in WalkDir::new(include_dir).into_iter().filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if path.is_file()
+                    && include_substrings
+                        .iter()
+                        .any(|substring| path.ends_with(substring))
+                {
+                    let path_str = path.to_string_lossy().to_string();
+                    if result.insert(path_str.clone()) {
+                        to_process.push(path_str);
+                    }
+                }
+            }
+        }
+    }
+
+    result.into_iter().collect()
+}
+
+/// Returns a list of substrings that contain "#include \"" in the source file
+fn include_substrings(path: &str) -> Option<Vec<String>> {
+    let file = std::fs::File::open(path);
+    if file.is_err() {
+        // If the software is self-developed, enable this debug option
+        //log(LogLevel::Debug, &format!("Failed to get include substrings for file: {}", path));
+        return None;
+    }
+    let mut file = file.unwrap();
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).unwrap();
+
+    let lines = buf.lines();
+    let mut include_substrings = Vec::new();
+    for line in lines {
+        if line.starts_with("#include \"") {
+            let include_path = line.split('\"').nth(1).unwrap().to_owned();
+            include_substrings.push(include_path);
+        }
+    }
+    Some(include_substrings)
+}
+
+/// Returns the compiler and the flags shared by the sources of a target and its precompiled header
+/// # Arguments
+/// * `build_config` - The build configuration
+/// * `os_config` - The os configuration
+/// * `target_config` - The target configuration
+/// * `dependant_libs` - The libs the target depends on, for their include dirs
+/// * `cpp` - Whether to compile C++, choosing `cppstd` over `cstd`
+/// * `pch_args` - Flags including the precompiled header, placed before the include dirs
+fn compile_args(
+    build_config: &BuildConfig,
+    os_config: &OSConfig,
+    target_config: &TargetConfig,
+    dependant_libs: &[Target],
+    cpp: bool,
+    pch_args: &[String],
+) -> Vec<String> {
+    let mut cmd = tools::split_args(&build_config.compiler.read().unwrap());
+
+    // Add cflags
+    cmd.extend(tools::split_args(&os_cflags(build_config, os_config)));
+    cmd.extend(
+        target_config
+            .defines
+            .iter()
+            .map(|define| format!("-D{}", define)),
+    );
+    cmd.extend(tools::split_args(&target_config.cflags));
+    let std = if cpp {
+        &target_config.cppstd
+    } else {
+        &target_config.cstd
+    };
+    if !std.is_empty() {
+        cmd.push(format!("-std={}", std));
+    }
+    cmd.extend(pch_args.iter().cloned());
+    for include in include_dirs(target_config, dependant_libs) {
+        cmd.push(format!("-I{}", include));
+    }
+
+    if target_config.typ == TargetType::Dll {
+        cmd.push("-fPIC".to_string());
+    }
+    cmd
+}
+
+/// Returns the include dirs of a target followed by the ones of the libs it depends on,
+/// keeping only the first occurrence of each dir
+/// # Arguments
+/// * `target_config` - The target configuration
+/// * `dependant_libs` - The libs the target depends on
+fn include_dirs<'a>(target_config: &'a TargetConfig, dependant_libs: &'a [Target]) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    target_config
+        .include_dir
+        .iter()
+        .chain(
+            dependant_libs
+                .iter()
+                .flat_map(|lib| lib.target_config.include_dir.iter()),
+        )
+        .map(String::as_str)
+        .filter(|include| seen.insert(*include))
+        .collect()
+}
+
+/// Returns the objdump used to disassemble exes, `rust-objdump` handles every OS arch
+/// # Arguments
+/// * `os_config` - The os configuration
+pub fn objdump(os_config: &OSConfig) -> &'static str {
+    if os_config.name.is_empty() {
+        "objdump"
+    } else {
+        "rust-objdump"
+    }
+}
+
+/// Returns the sysroot and library search path flags of the platform, empty if there is no OS
+/// # Arguments
+/// * `os_config` - The os configuration
+fn platform_ldflags(os_config: &OSConfig) -> Vec<String> {
+    let mut ldflags = Vec::new();
+    if !os_config.platform.sysroot.is_empty() {
+        ldflags.push(format!("--sysroot={}", os_config.platform.sysroot));
+    }
+    for lib_dir in &os_config.platform.extra_lib_dirs {
+        ldflags.push(format!("-L{}", lib_dir));
+    }
+    ldflags
+}
+
+/// Returns the compiler flags required to build for the OS, empty if there is no OS
+/// # Arguments
+/// * `build_config` - The build configuration
+/// * `os_config` - The os configuration
+pub fn os_cflags(build_config: &BuildConfig, os_config: &OSConfig) -> String {
+    let mut os_cflags = String::new();
+    if !os_config.name.is_empty() {
+        os_cflags.push_str("-nostdinc -fno-builtin -ffreestanding -Wall");
+        if os_config.ulib == "ruxlibc" {
+            os_cflags.push_str(" -I");
+            os_cflags.push_str(&os_config.ruxos_path("ulib/ruxlibc/include"));
+            let (_, lib_feats) = cfg_feat(os_config);
+            // generate the preprocessing macro definition
+            for lib_feat in lib_feats {
+                let processed_lib_feat = lib_feat.to_uppercase().replace('-', "_");
+                os_cflags.push_str(&format!(" -DRUX_CONFIG_{}", &processed_lib_feat));
+            }
+            os_cflags.push_str(&format!(
+                " -DRUX_CONFIG_{}",
+                os_config.platform.log.to_uppercase()
+            ));
+        } else if os_config.ulib == "ruxmusl" {
+            os_cflags.push_str(" -I");
+            os_cflags.push_str(&format!("{}/install/include", build_config.ruxmusl_dir()));
+        }
+        // extra includes are searched after the ulib headers, so they cannot shadow them
+        if !os_config.platform.sysroot.is_empty() {
+            os_cflags.push_str(&format!(" --sysroot={}", os_config.platform.sysroot));
+        }
+        for include in &os_config.platform.extra_includes {
+            os_cflags.push_str(" -I");
+            os_cflags.push_str(include);
+        }
+        if os_config.platform.mode == "release" {
+            os_cflags.push_str(" -O3");
+        }
+        if os_config.platform.arch == "riscv64" {
+            os_cflags.push_str(" -march=rv64gc -mabi=lp64d -mcmodel=medany");
+        }
+        if !os_config.features.contains(&"fp_simd".to_string()) {
+            if os_config.platform.arch == *"x86_64".to_string() {
+                os_cflags.push_str(" -mno-sse");
+            } else if os_config.platform.arch == *"aarch64".to_string() {
+                os_cflags.push_str(" -mgeneral-regs-only");
+            }
+        }
+    }
+    os_cflags
+}
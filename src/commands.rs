@@ -1,106 +1,280 @@
 //! This module contains code that handles various CLI flags
 
-use crate::builder::Target;
+use crate::builder::{self, objdump, os_cflags, Target};
 use crate::global_cfg::GlobalConfig;
 use crate::hasher::Hasher;
-use crate::parser::{self, BuildConfig, OSConfig, QemuConfig, TargetConfig};
+use crate::parser::{
+    self, BuildConfig, ConfigError, OSConfig, QemuConfig, TargetConfig, TargetType, DLL_EXT,
+};
 use crate::utils::env;
 use crate::utils::features;
-use crate::utils::log::{log, LogLevel};
+use crate::utils::license;
+use crate::utils::log::{log, log_level, LogLevel};
+use crate::utils::suggest::closest_match;
+use crate::utils::tools;
+use flate2::read::GzDecoder;
+use indicatif::ProgressBar;
+use notify::{RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
-use std::process::{Command, Stdio};
-
-static BUILD_DIR: &str = "ruxgo_bld";
-static BIN_DIR: &str = "ruxgo_bld/bin";
-#[cfg(target_os = "windows")]
-static OBJ_DIR: &str = "ruxgo_bld/obj_win32";
-#[cfg(target_os = "linux")]
-static OBJ_DIR: &str = "ruxgo_bld/obj_linux";
-static TARGET_DIR: &str = "ruxgo_bld/target";
-
-// OSConfig hash file
-static OSCONFIG_HASH_FILE: &str = "ruxgo_bld/os_config.hash";
-
-// ruxlibc info
-static RUXLIBC_BIN: &str = "ruxgo_bld/bin/libc.a";
-static RUXLIBC_HASH_PATH: &str = "ruxgo_bld/libc.linux.hash";
-lazy_static! {
-    static ref RUXLIBC_SRC: String = {
-        let path1 = "../ruxos/ulib/ruxlibc/c";
-        let path2 = "../../../ulib/ruxlibc/c";
-        if Path::new(path1).exists() {
-            String::from(path1)
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+use walkdir::WalkDir;
+
+/// Known sha256 checksums of the musl release tarballs
+static MUSL_SHA256: [(&str, &str); 1] = [(
+    "1.2.3",
+    "7d5b0b6062521e4627e099e4c9dc8248d32a30285e959b7eecaa780cf8cfd4a4",
+)];
+
+/// Returns the download url of the musl release tarball of the given version
+fn musl_url(version: &str) -> String {
+    format!("https://musl.libc.org/releases/musl-{}.tar.gz", version)
+}
+
+/// Returns the directory the musl sources of the configured version are extracted to
+/// # Arguments
+/// * `os_config` - The os configuration
+fn musl_src_dir(os_config: &OSConfig) -> String {
+    format!(
+        "{}/musl-{}",
+        os_config.ruxos_path("ulib/ruxmusl"),
+        os_config.musl_version
+    )
+}
+
+/// Downloads the musl release tarball of the configured version, verifies its checksum
+/// and extracts it next to ruxmusl
+/// # Arguments
+/// * `os_config` - The os configuration
+fn fetch_musl(os_config: &OSConfig) {
+    let version = &os_config.musl_version;
+    let url = musl_url(version);
+    log(LogLevel::Log, &format!("Downloading {}", url));
+    // build runs inside the tokio runtime of main, so block on the download in place
+    let tarball = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current()
+            .block_on(async { reqwest::get(&url).await?.error_for_status()?.bytes().await })
+    })
+    .unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Failed to download {}: {}", url, why),
+        );
+        std::process::exit(1);
+    });
+
+    let expected_sha256 = if !os_config.musl_sha256.is_empty() {
+        Some(os_config.musl_sha256.to_lowercase())
+    } else {
+        MUSL_SHA256
+            .iter()
+            .find(|(known, _)| known == version)
+            .map(|(_, sha256)| sha256.to_string())
+    };
+    let actual_sha256 = format!("{:x}", Sha256::digest(&tarball));
+    match expected_sha256 {
+        Some(expected) if expected != actual_sha256 => {
+            log(
+                LogLevel::Error,
+                &format!(
+                    "Checksum mismatch for {}: expected {}, got {}",
+                    url, expected, actual_sha256
+                ),
+            );
+            std::process::exit(1);
+        }
+        Some(_) => log(LogLevel::Info, &format!("Checksum verified: {}", url)),
+        None => log(
+            LogLevel::Warn,
+            &format!(
+                "No known checksum for musl-{}, set `musl_sha256` in [os] to verify it",
+                version
+            ),
+        ),
+    }
+
+    let ulib_ruxmusl = os_config.ruxos_path("ulib/ruxmusl");
+    log(
+        LogLevel::Log,
+        &format!("Extracting musl-{} to {}", version, ulib_ruxmusl),
+    );
+    extract_tar_gz(&tarball, &ulib_ruxmusl).unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Failed to extract musl-{}: {}", version, why),
+        );
+        std::process::exit(1);
+    });
+}
+
+/// Extracts a gzip compressed tarball into the given directory, creating it if needed
+fn extract_tar_gz(data: &[u8], dest: &str) -> std::io::Result<()> {
+    fs::create_dir_all(dest)?;
+    tar::Archive::new(GzDecoder::new(data)).unpack(dest)
+}
+
+/// Returns the parts of the project that can be cleaned
+/// # Arguments
+/// * `os_config` - The os configuration
+pub fn clean_items(os_config: &OSConfig) -> Vec<&'static str> {
+    let mut items = vec!["All", "App_bins", "Obj"];
+    if os_config.has_os() {
+        items.insert(1, "All_but_OS");
+        items.push("OS");
+        if !os_config.ulib.is_empty() {
+            items.push("Ulib");
+        }
+    }
+    items
+}
+
+/// Maps the part names given on the command line to clean choices
+/// Names are case insensitive and `bins` is accepted for `App_bins`
+/// # Arguments
+/// * `names` - The part names given by the user
+/// * `items` - The parts that can be cleaned, as returned by `clean_items`
+pub fn parse_clean_choices(names: &[String], items: &[&str]) -> Vec<String> {
+    names
+        .iter()
+        .map(|name| {
+            let lower = name.to_lowercase().replace('-', "_");
+            let lower = if lower == "bins" { "app_bins" } else { &lower };
+            if let Some(item) = items.iter().find(|item| item.to_lowercase() == lower) {
+                return item.to_string();
+            }
+            let valid: Vec<String> = items
+                .iter()
+                .map(|item| match *item {
+                    "App_bins" => "bins".to_string(),
+                    _ => item.to_lowercase().replace('_', "-"),
+                })
+                .collect();
+            let mut msg = format!("Unknown part to clean \"{}\"", name);
+            match closest_match(name, valid.iter().map(|v| v.as_str())) {
+                Some(suggestion) => msg.push_str(&format!(", did you mean \"{}\"?", suggestion)),
+                None => msg.push('.'),
+            }
+            msg.push_str(&format!(" Valid parts are: {}", valid.join(", ")));
+            log(LogLevel::Error, &msg);
+            std::process::exit(1);
+        })
+        .collect()
+}
+
+/// Removes a directory and logs the result
+fn remove_dir(dir_path: &str) {
+    if Path::new(dir_path).exists() {
+        if let Err(error) = fs::remove_dir_all(dir_path) {
+            log(
+                LogLevel::Error,
+                &format!("Could not remove directory '{}': {}", dir_path, error),
+            );
         } else {
-            String::from(path2)
+            log(LogLevel::Log, &format!("Cleaning: {}", dir_path));
         }
-    };
+    }
 }
 
-// ruxmusl info
-static RUXMUSL_DIR: &str = "ruxgo_bld/ruxmusl";
-lazy_static! {
-    static ref ULIB_RUXMUSL: String = {
-        let path1 = "../ruxos/ulib/ruxmusl";
-        let path2 = "../../../ulib/ruxmusl";
-        if Path::new(path1).exists() {
-            String::from(path1)
+/// Removes a file and logs the result, hash files only at the info level
+fn remove_file(file_path: &str) {
+    if Path::new(file_path).exists() {
+        if let Err(error) = fs::remove_file(file_path) {
+            log(
+                LogLevel::Error,
+                &format!("Could not remove file '{}': {}", file_path, error),
+            );
+        } else if file_path.ends_with(".hash") {
+            log(LogLevel::Info, &format!("Cleaning: {}", file_path));
         } else {
-            String::from(path2)
+            log(LogLevel::Log, &format!("Cleaning: {}", file_path));
         }
-    };
-    static ref ULIB_RUXMUSL_SRC: String = format!("{}/musl-1.2.3", *ULIB_RUXMUSL);
+    }
+}
+
+/// Removes the hash file and the binaries of a target: its bin, elf, map and asm files
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `target` - The target whose binaries are removed
+fn clean_target_bins(build_config: &BuildConfig, target: &TargetConfig) {
+    let bin_dir = build_config.bin_dir();
+    remove_file(&build_config.target_hash_path(&target.name));
+    if !Path::new(&bin_dir).exists() {
+        return;
+    }
+    let mut bin_name = format!("{}/{}", bin_dir, target.name);
+    let mut elf_name = String::new();
+    #[cfg(target_os = "windows")]
+    match target.typ {
+        TargetType::Exe | TargetType::Test => bin_name.push_str(".exe"),
+        TargetType::Dll => bin_name.push_str(DLL_EXT),
+        _ => (),
+    }
+    #[cfg(not(target_os = "windows"))]
+    match target.typ {
+        TargetType::Exe | TargetType::Test => {
+            elf_name = format!("{}.elf", bin_name);
+            bin_name.push_str(".bin");
+        }
+        TargetType::Dll => bin_name.push_str(DLL_EXT),
+        TargetType::Static => bin_name.push_str(".a"),
+        TargetType::Object => bin_name.push_str(".o"),
+        TargetType::HeaderOnly => return,
+    }
+    remove_file(&bin_name);
+    remove_file(&elf_name);
+    remove_file(&build_config.map_path(&target.name));
+    remove_file(&build_config.asm_path(&target.name));
+}
+
+/// Cleans the outputs of a single target: the objects of its sources, its precompiled header,
+/// its hash file and its binaries, leaving the other targets and the OS build intact
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `target` - The target to clean
+pub fn clean_target(build_config: &BuildConfig, target: &TargetConfig) {
+    if target.typ != TargetType::HeaderOnly {
+        for src in target.src_files() {
+            remove_file(&build_config.obj_path(&target.name, &Target::get_src_name(&src)));
+        }
+        remove_dir(&build_config.pch_dir(&target.name));
+    }
+    clean_target_bins(build_config, target);
 }
 
 /// Cleans the local targets
 /// # Arguments
+/// * `build_config` - The local build configuration
 /// * `targets` - A vector of targets to clean
 /// * `os_config` - The local os configuration
 /// * `choices` - A vector of choices to select which components to delete
-pub fn clean(targets: &Vec<TargetConfig>, os_config: &OSConfig, choices: Vec<String>) {
-    // Helper function to remove a directory or a file and log the result
-    let remove_dir = |dir_path: &str| {
-        if Path::new(dir_path).exists() {
-            if let Err(error) = fs::remove_dir_all(dir_path) {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not remove directory '{}': {}", dir_path, error),
-                );
-            } else {
-                log(LogLevel::Log, &format!("Cleaning: {}", dir_path));
-            }
-        }
-    };
-    let remove_file = |file_path: &str| {
-        if Path::new(file_path).exists() {
-            if let Err(error) = fs::remove_file(file_path) {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not remove file '{}': {}", file_path, error),
-                );
-            } else if file_path.ends_with(".hash") {
-                log(LogLevel::Info, &format!("Cleaning: {}", file_path));
-            } else {
-                log(LogLevel::Log, &format!("Cleaning: {}", file_path));
-            }
-        }
-    };
+pub fn clean(
+    build_config: &BuildConfig,
+    targets: &Vec<TargetConfig>,
+    os_config: &OSConfig,
+    choices: Vec<String>,
+) {
+    let bin_dir = build_config.bin_dir();
+    let os_config_hash_path = build_config.os_config_hash_path();
 
     // Removes os if choices includes "OS" or choices includes "All"
     if choices.contains(&String::from("OS")) || choices.contains(&String::from("All")) {
-        remove_dir(TARGET_DIR);
-        remove_file(OSCONFIG_HASH_FILE);
+        remove_dir(&build_config.target_dir());
+        remove_file(&os_config_hash_path);
     }
 
     // Removes ulib if choices includes "Ulib" or choices includes "All"
     if choices.contains(&String::from("Ulib")) || choices.contains(&String::from("All")) {
-        remove_file(OSCONFIG_HASH_FILE);
+        remove_file(&os_config_hash_path);
         if os_config.ulib == "ruxlibc" {
-            remove_file(RUXLIBC_HASH_PATH);
-            remove_file(RUXLIBC_BIN);
+            remove_file(&build_config.target_hash_path("libc"));
+            remove_file(&format!("{}/libc.a", bin_dir));
         } else if os_config.ulib == "ruxmusl" {
-            remove_dir(RUXMUSL_DIR);
+            remove_dir(&build_config.ruxmusl_dir());
         }
     }
 
@@ -108,45 +282,39 @@ pub fn clean(targets: &Vec<TargetConfig>, os_config: &OSConfig, choices: Vec<Str
     if choices.contains(&String::from("App_bins")) || choices.contains(&String::from("All")) {
         // removes local bins of targets
         for target in targets {
-            #[cfg(target_os = "windows")]
-            let hash_path = format!("ruxgo_bld/{}.win32.hash", &target.name);
-            #[cfg(target_os = "linux")]
-            let hash_path = format!("ruxgo_bld/{}.linux.hash", &target.name);
-            remove_file(&hash_path);
-            if Path::new(BIN_DIR).exists() {
-                let mut bin_name = format!("{}/{}", BIN_DIR, target.name);
-                let mut elf_name = String::new();
-                #[cfg(target_os = "windows")]
-                match target.typ.as_str() {
-                    "exe" => bin_name.push_str(".exe"),
-                    "dll" => bin_name.push_str(".dll"),
-                    _ => (),
-                }
-                #[cfg(target_os = "linux")]
-                match target.typ.as_str() {
-                    "exe" => {
-                        elf_name = format!("{}.elf", bin_name);
-                        bin_name.push_str(".bin");
-                    }
-                    "dll" => bin_name.push_str(".so"),
-                    "static" => bin_name.push_str(".a"),
-                    "object" => bin_name.push_str(".o"),
-                    _ => (),
-                }
-                remove_file(&bin_name);
-                remove_file(&elf_name);
-            }
+            clean_target_bins(build_config, target);
         }
     }
 
     // Removes obj if choices includes "Obj" or choices includes "All"
     if choices.contains(&String::from("Obj")) || choices.contains(&String::from("All")) {
-        remove_dir(OBJ_DIR);
+        remove_dir(&build_config.obj_dir());
     }
 
     // Removes all if choices includes "All"
     if choices.contains(&String::from("All")) {
-        remove_dir(BUILD_DIR);
+        remove_dir(&build_config.output_dir);
+    } else if choices.contains(&String::from("All_but_OS")) {
+        // Removes everything but the OS build and its hash, so that it is not rebuilt
+        let kept = [
+            PathBuf::from(build_config.target_dir()),
+            PathBuf::from(&os_config_hash_path),
+        ];
+        for entry in fs::read_dir(&build_config.output_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let path = entry.path();
+            if kept.contains(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                remove_dir(&path.to_string_lossy());
+            } else {
+                remove_file(&path.to_string_lossy());
+            }
+        }
     }
 }
 
@@ -155,29 +323,40 @@ pub fn clean(targets: &Vec<TargetConfig>, os_config: &OSConfig, choices: Vec<Str
 /// * `build_config` - The local build configuration
 /// * `targets` - A vector of targets to build
 /// * `os_config` - The local os configuration
-/// * `gen_cc` - Whether to generate a compile_commands.json file
+/// * `gen_cc` - The compile_commands.json to generate, if any
 /// * `gen_vsc` - Whether to generate a .vscode/c_cpp_properties.json file
+/// * `gen_clangd` - Whether to generate a .clangd file
+/// * `keep_going` - Whether to keep linking the other targets after one fails to link
+/// * `selected` - If not empty, only these targets and their dependencies are built, otherwise
+///   all targets but the tests
+#[allow(clippy::too_many_arguments)]
 pub fn build(
     build_config: &BuildConfig,
     targets: &Vec<TargetConfig>,
     os_config: &OSConfig,
-    gen_cc: bool,
+    gen_cc: Option<&Path>,
     gen_vsc: bool,
+    gen_clangd: bool,
+    keep_going: bool,
+    selected: &[&str],
 ) {
-    if !Path::new(BUILD_DIR).exists() {
-        fs::create_dir(BUILD_DIR).unwrap_or_else(|why| {
+    tools::require(&build_tools(build_config, os_config, targets));
+    if !Path::new(&build_config.output_dir).exists() {
+        fs::create_dir_all(&build_config.output_dir).unwrap_or_else(|why| {
             log(
                 LogLevel::Error,
-                &format!("Could not create ruxgo_bld directory: {}", why),
+                &format!(
+                    "Could not create {} directory: {}",
+                    build_config.output_dir, why
+                ),
             );
             std::process::exit(1);
         });
     }
-    if gen_cc {
+    if let Some(cc_path) = gen_cc {
         let mut cc_file = fs::OpenOptions::new()
-            .write(true)
             .append(true)
-            .open("compile_commands.json")
+            .open(cc_path)
             .unwrap_or_else(|why| {
                 log(LogLevel::Error, &format!("Could not open cc file: {}", why));
                 std::process::exit(1);
@@ -193,7 +372,6 @@ pub fn build(
 
     if gen_vsc {
         let mut vsc_file = fs::OpenOptions::new()
-            .write(true)
             .append(true)
             .open(".vscode/c_cpp_properties.json")
             .unwrap_or_else(|why| {
@@ -205,6 +383,30 @@ pub fn build(
             });
 
         let inc_dirs: Vec<String> = targets.iter().flat_map(|t| t.include_dir.clone()).collect();
+        let mut defines: Vec<String> = Vec::new();
+        for define in ["_DEBUG", "UNICODE", "_UNICODE"]
+            .iter()
+            .map(|define| define.to_string())
+            .chain(targets.iter().flat_map(|t| t.defines.clone()))
+        {
+            // Escaped as a JSON string, without the surrounding quotes
+            let define = serde_json::Value::String(define).to_string();
+            let define = define[1..define.len() - 1].to_string();
+            if !defines.contains(&define) {
+                defines.push(define);
+            }
+        }
+        // Use the first standard set by any target, the defaults are kept otherwise
+        let c_std = targets
+            .iter()
+            .map(|t| t.cstd.as_str())
+            .find(|std| !std.is_empty())
+            .unwrap_or("c11");
+        let cpp_std = targets
+            .iter()
+            .map(|t| t.cppstd.as_str())
+            .find(|std| !std.is_empty())
+            .unwrap_or("c++17");
         let compiler_path: String = build_config.compiler.read().unwrap().clone();
         let mut intellimode: String = String::new();
         if compiler_path == "gcc" || compiler_path == "g++" {
@@ -218,23 +420,19 @@ pub fn build(
             );
         }
 
-        #[cfg(target_os = "windows")]
-        let compiler_path = Command::new("sh")
-            .arg("-c")
-            .arg(&format!("where {}", &compiler_path))
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-
-        #[cfg(target_os = "windows")]
-        //Pick the first compiler path
-        let compiler_path = String::from_utf8(compiler_path)
-            .unwrap()
-            .split("\n")
-            .collect::<Vec<&str>>()[0]
-            .to_string()
-            .replace("\r", "")
-            .replace("\\", "/");
+        // The full path of the compiler, with `/` as separator on every platform
+        let compiler_path = match tools::find(&compiler_path) {
+            Some(path) => path
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/"),
+            None => {
+                log(
+                    LogLevel::Warn,
+                    &format!("Could not find compiler {} on PATH", compiler_path),
+                );
+                compiler_path
+            }
+        };
         #[cfg(target_os = "windows")]
         let vsc_json = format!(
             r#"{{
@@ -245,57 +443,56 @@ pub fn build(
                 "{}"
             ],
             "defines": [
-                "_DEBUG",
-                "UNICODE",
-                "_UNICODE"
+                "{}"
             ],
             "compilerPath": "{}",
-            "cStandard": "c11",
-            "cppStandard": "c++17",
+            "cStandard": "{}",
+            "cppStandard": "{}",
             "intelliSenseMode": "windows-{}"
         }}
     ],
     "version": 4
 }}"#,
             inc_dirs.join("\",\n\t\t\t\t\""),
+            defines.join("\",\n\t\t\t\t\""),
             compiler_path,
+            c_std,
+            cpp_std,
             intellimode
         );
-        #[cfg(target_os = "linux")]
-        let compiler_path = Command::new("sh")
-            .arg("-c")
-            .arg(&format!("which {}", &compiler_path))
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-
-        #[cfg(target_os = "linux")]
-        let compiler_path = String::from_utf8(compiler_path).unwrap().replace('\n', "");
-
-        #[cfg(target_os = "linux")]
+        #[cfg(not(target_os = "windows"))]
+        let (config_name, platform) = if cfg!(target_os = "macos") {
+            ("Mac", "macos")
+        } else {
+            ("Linux", "linux")
+        };
+        #[cfg(not(target_os = "windows"))]
         let vsc_json = format!(
             r#"{{
     "configurations": [
         {{
-            "name": "Linux",
+            "name": "{}",
             "includePath": [
                 "{}"
             ],
             "defines": [
-                "_DEBUG",
-                "UNICODE",
-                "_UNICODE"
+                "{}"
             ],
             "compilerPath": "{}",
-            "cStandard": "c11",
-            "cppStandard": "c++17",
-            "intelliSenseMode": "linux-{}"
+            "cStandard": "{}",
+            "cppStandard": "{}",
+            "intelliSenseMode": "{}-{}"
         }}
     ],
     "version": 4
 }}"#,
+            config_name,
             inc_dirs.join("\",\n\t\t\t\t\""),
+            defines.join("\",\n\t\t\t\t\""),
             compiler_path,
+            c_std,
+            cpp_std,
+            platform,
             intellimode
         );
 
@@ -311,117 +508,350 @@ pub fn build(
             });
     }
 
-    log(
-        LogLevel::Log,
-        &format!(
-            "Compiling OS: {}, Ulib: {}",
-            os_config.name, os_config.ulib
-        ),
-    );
-    let (rux_feats_final, lib_feats_final) = features::cfg_feat_addprefix(os_config);
-    build_os(
-        os_config,
-        &os_config.ulib,
-        &rux_feats_final,
-        &lib_feats_final,
-    );
-    if os_config.ulib == "ruxlibc" {
-        build_ruxlibc(build_config, os_config, gen_cc);
-    } else if os_config.ulib == "ruxmusl" {
-        build_ruxmusl(build_config, os_config);
+    if gen_clangd {
+        gen_clangd_file(build_config, os_config, targets, gen_cc);
     }
 
     let mut config_changed = false;
 
-    // Relinks if the os_config changes.
-    if os_config != &OSConfig::default() {
-        let os_config_str = serde_json::to_string(os_config).unwrap_or_else(|_| "".to_string());
-        let current_hash = Hasher::hash_string(&os_config_str);
-        let old_hash = Hasher::read_hash_from_file(OSCONFIG_HASH_FILE);
-        if old_hash != current_hash {
-            log(LogLevel::Log, "OS config changes, all need to be relinked");
-            config_changed = true;
-            Hasher::save_hash_to_file(OSCONFIG_HASH_FILE, &current_hash);
+    // Local projects without an [os] section, or with only a [platform], have nothing to build
+    // for the OS
+    if os_config.has_os() {
+        let unknown_services = features::unknown_services(os_config);
+        if !unknown_services.is_empty() {
+            for msg in &unknown_services {
+                log(LogLevel::Error, msg);
+            }
+            std::process::exit(1);
         }
-    };
+        let (rux_feats_final, lib_feats_final) = features::cfg_feat_addprefix(os_config);
+
+        // The OS is rebuilt, and everything relinked, only if its config, the features it is
+        // built with or the compiler changed since the last build, `ruxgo -c os` forces a rebuild
+        let mut feats_final = [&rux_feats_final[..], &lib_feats_final[..]].concat();
+        feats_final.sort();
+        let current_hash = Hasher::hash_string(&format!(
+            "{}\n{}\n{}",
+            os_config.canonical_string(),
+            feats_final.join(" "),
+            Target::compiler_hash(&build_config.compiler.read().unwrap())
+        ));
+        let old_hash = Hasher::read_hash_from_file(&build_config.os_config_hash_path());
+        if old_hash != current_hash || !Path::new(&build_config.target_dir()).exists() {
+            if old_hash != current_hash {
+                log(LogLevel::Log, "OS config changes, all need to be relinked");
+                config_changed = true;
+            }
+            log(
+                LogLevel::Log,
+                &format!("Compiling OS: {}, Ulib: {}", os_config.name, os_config.ulib),
+            );
+            build_os(
+                build_config,
+                os_config,
+                &os_config.ulib,
+                &rux_feats_final,
+                &lib_feats_final,
+            );
+            Hasher::save_hash_to_file(&build_config.os_config_hash_path(), &current_hash);
+        } else {
+            log(
+                LogLevel::Log,
+                &format!("OS: {} is up to date", os_config.name),
+            );
+        }
+        if os_config.ulib == "ruxlibc" {
+            build_ruxlibc(build_config, os_config, gen_cc);
+        } else if os_config.ulib == "ruxmusl" {
+            build_ruxmusl(build_config, os_config);
+        }
+    }
 
     // Constructs each target separately
+    let to_build = (!selected.is_empty()).then(|| {
+        selected
+            .iter()
+            .flat_map(|name| target_with_deps(targets, name))
+            .collect::<HashSet<String>>()
+    });
+    let mut linked: Vec<String> = Vec::new();
+    let mut manifest = Vec::new();
+    let mut failed: Vec<String> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
     for target in targets {
+        match to_build {
+            Some(ref to_build) if !to_build.contains(&target.name) => continue,
+            // Tests are only built when selected, e.g. by `ruxgo test`
+            None if target.typ == TargetType::Test => continue,
+            _ => {}
+        }
+        // A target can't be linked against a dependency that failed
+        if let Some(dep) = target
+            .deps
+            .iter()
+            .find(|dep| failed.contains(dep) || skipped.contains(dep))
+        {
+            log(
+                LogLevel::Warn,
+                &format!(
+                    "Skipping target: {}, its dependency {} failed to link",
+                    target.name, dep
+                ),
+            );
+            skipped.push(target.name.clone());
+            continue;
+        }
         let mut tgt = Target::new(build_config, os_config, target, targets);
 
-        let needs_relink = config_changed && target.typ == "exe";
-        tgt.build(gen_cc, needs_relink);
+        let needs_relink = config_changed && target.typ.is_exe();
+        match tgt.build(gen_cc, needs_relink) {
+            Ok(()) => {
+                linked.push(target.name.clone());
+                manifest.push(manifest_entry(&tgt, target, os_config));
+            }
+            Err(_) if keep_going => failed.push(target.name.clone()),
+            Err(_) => std::process::exit(1),
+        }
     }
 
-    if gen_cc {
-        let mut cc_file = fs::OpenOptions::new()
-            .write(true)
-            .read(true)
-            .append(true)
-            .open("compile_commands.json")
-            .unwrap_or_else(|why| {
-                log(LogLevel::Error, &format!("Could not open cc file: {}", why));
-                std::process::exit(1);
-            });
-        cc_file.write_all(b"]").unwrap_or_else(|why| {
+    if let Some(cc_path) = gen_cc {
+        finish_gen_cc(cc_path);
+    }
+    write_build_manifest(build_config, targets, manifest);
+    if !failed.is_empty() {
+        if !linked.is_empty() {
+            log(LogLevel::Log, &format!("Linked: {}", linked.join(", ")));
+        }
+        log(
+            LogLevel::Error,
+            &format!("Failed to link: {}", failed.join(", ")),
+        );
+        if !skipped.is_empty() {
             log(
                 LogLevel::Error,
-                &format!("Could not write to cc file: {}", why),
+                &format!(
+                    "Skipped because of a failed dependency: {}",
+                    skipped.join(", ")
+                ),
             );
-            std::process::exit(1);
-        });
+        }
+        std::process::exit(1);
     }
     log(LogLevel::Log, "Build complete!");
 }
 
+/// Returns the build manifest entry of a built target
+/// # Arguments
+/// * `tgt` - The built target
+/// * `target` - The config of the target
+/// * `os_config` - The os configuration
+fn manifest_entry(tgt: &Target, target: &TargetConfig, os_config: &OSConfig) -> serde_json::Value {
+    let absolute = |path: &str| {
+        fs::canonicalize(path)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string())
+    };
+    let mut outputs = Vec::new();
+    if target.typ != TargetType::HeaderOnly {
+        outputs.push(absolute(&tgt.bin_path));
+    }
+    // Only exes linked against the os have a separate elf
+    if target.typ.is_exe() && !os_config.name.is_empty() {
+        outputs.push(absolute(&tgt.elf_path));
+    }
+    serde_json::json!({
+        "name": target.name,
+        "type": target.typ.as_str(),
+        "outputs": outputs,
+    })
+}
+
+/// Writes the manifest listing the outputs of the built targets, for packaging scripts
+/// The entries of the targets built before and not now, e.g. with `--target`, are kept as long
+/// as the targets are still in the config
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `targets` - The targets of the config
+/// * `built` - The manifest entries of the built targets
+fn write_build_manifest(
+    build_config: &BuildConfig,
+    targets: &[TargetConfig],
+    built: Vec<serde_json::Value>,
+) {
+    let path = build_config.build_manifest_path();
+    let name = |entry: &serde_json::Value| entry.get("name").cloned();
+    let mut entries: Vec<serde_json::Value> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .and_then(|manifest| manifest.get("targets")?.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| {
+            targets
+                .iter()
+                .any(|target| name(entry).is_some_and(|name| name == target.name.as_str()))
+        })
+        .collect();
+    for entry in built {
+        match entries
+            .iter_mut()
+            .find(|previous| name(previous) == name(&entry))
+        {
+            Some(previous) => *previous = entry,
+            None => entries.push(entry),
+        }
+    }
+    let manifest = serde_json::json!({ "targets": entries });
+    fs::write(&path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not write {}: {}", path, why),
+        );
+        std::process::exit(1);
+    });
+}
+
+/// Writes a JSON file mapping each target to its source files and the headers each of them
+/// depends on, for external build systems. Nothing is compiled, nor pre_build run
+/// # Arguments
+/// * `targets` - The targets to analyse
+/// * `path` - The file to write
+pub fn emit_deps(targets: &[TargetConfig], path: &Path) {
+    let mut deps = serde_json::Map::new();
+    for target in targets {
+        deps.insert(
+            target.name.clone(),
+            serde_json::json!(builder::src_dependencies(target)),
+        );
+    }
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&serde_json::Value::Object(deps)).unwrap(),
+    )
+    .unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not write {}: {}", path.display(), why),
+        );
+        std::process::exit(1);
+    });
+    log(
+        LogLevel::Log,
+        &format!("Wrote dependency info to {}", path.display()),
+    );
+}
+
+/// Prints the configuration as resolved by the parser as JSON, including the cross compiler
+/// and the features the OS is built with
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `os_config` - The os configuration
+/// * `targets` - A vector of targets
+pub fn print_config(build_config: &BuildConfig, os_config: &OSConfig, targets: &Vec<TargetConfig>) {
+    let mut config = serde_json::json!({
+        "build": {
+            "compiler": *build_config.compiler.read().unwrap(),
+            "output_dir": build_config.output_dir,
+            "obj_cache": build_config.obj_cache,
+        },
+        "targets": targets,
+    });
+    if os_config.has_platform() {
+        config["os"] = serde_json::json!(os_config);
+    }
+    if os_config.has_os() {
+        let (rux_feats_final, lib_feats_final) = features::cfg_feat_addprefix(os_config);
+        config["os"]["features_final"] =
+            serde_json::json!([rux_feats_final, lib_feats_final].concat());
+    }
+    println!("{}", serde_json::to_string_pretty(&config).unwrap());
+}
+
 /// Builds the specified os
 /// # Arguments
+/// * `build_config` - The local build configuration
 /// * `os_config` - The os configuration
 /// * `ulib` - The user library, `ruxlibc` or `ruxmusl`
 /// * `rux_feats` - Features to be enabled for Ruxos modules (crate `ruxfeat`)
 /// * `lib_feats` - Features to be enabled for the user library (crate `ruxlibc`, `ruxmusl`)
-fn build_os(os_config: &OSConfig, ulib: &str, rux_feats: &[String], lib_feats: &[String]) {
+fn build_os(
+    build_config: &BuildConfig,
+    os_config: &OSConfig,
+    ulib: &str,
+    rux_feats: &[String],
+    lib_feats: &[String],
+) {
     let current_dir = std::env::current_dir().unwrap();
-    let target_dir_path = current_dir.join(TARGET_DIR);
-    let target_dir = format!("--target-dir {}", target_dir_path.to_str().unwrap());
+    let target_dir_path = current_dir.join(build_config.target_dir());
 
     // Checks if the ruxos directory exists and change to it if it does
-    let ruxos_dir = Path::new("../ruxos");
+    let ruxos_dir = if !os_config.ruxos_dir.is_empty() {
+        Path::new(&os_config.ruxos_dir)
+    } else {
+        Path::new("../ruxos")
+    };
     if ruxos_dir.exists() {
         std::env::set_current_dir(ruxos_dir).unwrap();
     }
 
-    let target = format!("--target {}", os_config.platform.target);
-    let mut mode = String::new();
+    // cmd
+    let mut cmd = vec![
+        "cargo".to_string(),
+        "build".to_string(),
+        "--target".to_string(),
+        os_config.platform.target.clone(),
+        "--target-dir".to_string(),
+        target_dir_path.to_str().unwrap().to_string(),
+    ];
     if !os_config.platform.mode.is_empty() {
-        mode = format!("--{}", os_config.platform.mode);
+        cmd.push(format!("--{}", os_config.platform.mode));
     }
-    let os_ulib = format!("-p {}", ulib);
-    let verbose = match os_config.platform.v.as_str() {
-        "1" => "-v",
-        "2" => "-vv",
-        _ => "",
-    };
-    let features = [rux_feats, lib_feats].concat().join(" ");
-
-    // cmd
-    let cmd = format!(
-        "cargo build {} {} {} {} {} --features \"{}\"",
-        target, target_dir, mode, os_ulib, verbose, features
+    cmd.push("-p".to_string());
+    cmd.push(ulib.to_string());
+    // A debug log shows at least the commands cargo runs, to help diagnose OS build failures
+    let debug = log_level() == LogLevel::Debug;
+    match os_config.platform.v.as_str() {
+        "2" => cmd.push("-vv".to_string()),
+        "1" => cmd.push("-v".to_string()),
+        _ if debug => cmd.push("-v".to_string()),
+        _ => {}
+    }
+    cmd.push("--features".to_string());
+    cmd.push([rux_feats, lib_feats].concat().join(" "));
+    // cargo only colors its output on a terminal, so ask for it when ruxgo itself colors
+    if colored::control::SHOULD_COLORIZE.should_colorize() {
+        cmd.push("--color".to_string());
+        cmd.push("always".to_string());
+    }
+    log(
+        LogLevel::Info,
+        &format!("Command: {}", tools::display(&cmd)),
     );
-    log(LogLevel::Info, &format!("Command: {}", cmd));
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(cmd)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .expect("Failed to execute command");
-    if !output.status.success() {
+    let spinner = tools::spinner("Building OS...");
+    let result = tools::run_prefixed(
+        tools::command(&cmd).stdin(Stdio::inherit()),
+        "[os]",
+        &spinner,
+    );
+    spinner.finish_and_clear();
+    let (status, stderr) = result.unwrap_or_else(|why| {
+        log(LogLevel::Error, &format!("Could not run cargo: {}", why));
+        std::process::exit(1);
+    });
+    if !status.success() {
+        // Only the errors, the progress lines before them were already logged
+        let errors = stderr
+            .lines()
+            .skip_while(|line| !line.contains("error"))
+            .collect::<Vec<_>>()
+            .join("\n");
         log(
             LogLevel::Error,
-            &format!("Command execution failed: {:?}", output.stderr),
+            &format!(
+                "OS build failed ({}): {}",
+                status,
+                if errors.is_empty() { &stderr } else { &errors }
+            ),
         );
         std::process::exit(1);
     }
@@ -434,10 +864,11 @@ fn build_os(os_config: &OSConfig, ulib: &str, rux_feats: &[String], lib_feats: &
 /// # Arguments
 /// * `os_config` - The os configuration
 /// * `build_config` - The local build configuration
-/// * `gen_cc` - Whether to generate a compile_commands.json file
-fn build_ruxlibc(build_config: &BuildConfig, os_config: &OSConfig, gen_cc: bool) {
-    if !Path::new(BIN_DIR).exists() {
-        fs::create_dir_all(BIN_DIR).unwrap_or_else(|why| {
+/// * `gen_cc` - The compile_commands.json to generate, if any
+fn build_ruxlibc(build_config: &BuildConfig, os_config: &OSConfig, gen_cc: Option<&Path>) {
+    let bin_dir = build_config.bin_dir();
+    if !Path::new(&bin_dir).exists() {
+        fs::create_dir_all(&bin_dir).unwrap_or_else(|why| {
             log(
                 LogLevel::Error,
                 &format!("Couldn't create build dir: {}", why),
@@ -447,20 +878,34 @@ fn build_ruxlibc(build_config: &BuildConfig, os_config: &OSConfig, gen_cc: bool)
     }
     let ulib_tgt = TargetConfig {
         name: "libc".to_string(),
-        src: RUXLIBC_SRC.to_string(),
+        src: vec![os_config.ruxos_path("ulib/ruxlibc/c")],
         src_only: Vec::new(),
         src_exclude: Vec::new(),
         include_dir: Vec::new(), // this is empty to avoid repetition at src build
-        typ: "static".to_string(),
+        typ: TargetType::Static,
         cflags: String::from(""),
-        archive: format!("{}-linux-musl-ar", os_config.platform.arch),
+        defines: Vec::new(),
+        cstd: String::from(""),
+        cppstd: String::from(""),
+        archive: format!("{}ar", os_config.platform.cross_compile),
         linker: String::from(""),
         ldflags: String::from("rcs"),
+        libs: Vec::new(),
+        lib_dirs: Vec::new(),
         deps: Vec::new(),
+        emit_map: false,
+        emit_asm: false,
+        strip: "auto".to_string(),
+        post_build: String::new(),
+        pre_build: String::new(),
+        pre_build_inputs: Vec::new(),
+        pch: String::new(),
     };
     let ulib_targets = Vec::new();
     let mut tgt = Target::new(build_config, os_config, &ulib_tgt, &ulib_targets);
-    tgt.build(gen_cc, false);
+    if tgt.build(gen_cc, false).is_err() {
+        std::process::exit(1);
+    }
 }
 
 /// Builds the ruxmusl
@@ -468,41 +913,37 @@ fn build_ruxlibc(build_config: &BuildConfig, os_config: &OSConfig, gen_cc: bool)
 /// * `os_config` - The os configuration
 /// * `build_config` - The local build configuration
 fn build_ruxmusl(build_config: &BuildConfig, os_config: &OSConfig) {
-    if !Path::new(RUXMUSL_DIR).exists() {
+    let ruxmusl_dir = build_config.ruxmusl_dir();
+    let musl_version = &os_config.musl_version;
+    let musl_src = musl_src_dir(os_config);
+    // ruxmusl built from another musl version is rebuilt
+    let version_file = format!("{}/musl_version", ruxmusl_dir);
+    if Path::new(&ruxmusl_dir).exists()
+        && fs::read_to_string(&version_file).is_ok_and(|v| v != *musl_version)
+    {
+        log(
+            LogLevel::Log,
+            &format!(
+                "Musl version changed to {}, rebuilding ruxmusl",
+                musl_version
+            ),
+        );
+        fs::remove_dir_all(&ruxmusl_dir).unwrap_or_else(|why| {
+            log(
+                LogLevel::Error,
+                &format!("Could not remove directory '{}': {}", ruxmusl_dir, why),
+            );
+            std::process::exit(1);
+        });
+    }
+    if !Path::new(&ruxmusl_dir).exists() {
         // download ruxmusl
-        if !Path::new(&*ULIB_RUXMUSL_SRC).exists() {
-            log(LogLevel::Info, "Downloading musl-1.2.3 source code");
-            Command::new("wget")
-                .args([
-                    "https://musl.libc.org/releases/musl-1.2.3.tar.gz",
-                    "-P",
-                    ULIB_RUXMUSL.as_str(),
-                ])
-                .spawn()
-                .expect("Failed to execute command")
-                .wait()
-                .expect("Failed to wait for command");
-            Command::new("tar")
-                .args([
-                    "-zxf",
-                    &format!("{}/musl-1.2.3.tar.gz", *ULIB_RUXMUSL),
-                    "-C",
-                    ULIB_RUXMUSL.as_str(),
-                ])
-                .spawn()
-                .expect("Failed to execute command")
-                .wait()
-                .expect("Failed to wait for command");
-            Command::new("rm")
-                .args(["-f", &format!("{}/musl-1.2.3.tar.gz", *ULIB_RUXMUSL)])
-                .spawn()
-                .expect("Failed to execute command")
-                .wait()
-                .expect("Failed to wait for command");
-        }
-
-        // create ruxgo_bld/ruxmusl
-        fs::create_dir_all(RUXMUSL_DIR).unwrap_or_else(|why| {
+        if !Path::new(&musl_src).exists() {
+            fetch_musl(os_config);
+        }
+
+        // create <output_dir>/ruxmusl
+        fs::create_dir_all(&ruxmusl_dir).unwrap_or_else(|why| {
             log(
                 LogLevel::Error,
                 &format!("Couldn't create build dir: {}", why),
@@ -512,7 +953,7 @@ fn build_ruxmusl(build_config: &BuildConfig, os_config: &OSConfig) {
 
         // config ruxmusl to generate makefile
         let current_dir = std::env::current_dir().expect("Failed to get current directory");
-        let ruxmusl_abs_path = current_dir.join(ULIB_RUXMUSL_SRC.as_str());
+        let ruxmusl_abs_path = current_dir.join(&musl_src);
         let ruxmusl_abs_path_str = ruxmusl_abs_path
             .to_str()
             .expect("Failed to convert path to string");
@@ -523,7 +964,7 @@ fn build_ruxmusl(build_config: &BuildConfig, os_config: &OSConfig) {
         let configure_output = Command::new("sh")
             .arg("-c")
             .arg(cmd)
-            .current_dir(RUXMUSL_DIR)
+            .current_dir(&ruxmusl_dir)
             .stderr(Stdio::inherit())
             .output()
             .expect("Failed to execute configure command");
@@ -540,38 +981,45 @@ fn build_ruxmusl(build_config: &BuildConfig, os_config: &OSConfig) {
 
         // compile and install ruxmusl
         log(LogLevel::Log, "Compiling and installing Musl...");
-        let make_output = Command::new("make")
-            .args(["-j"])
-            .current_dir(RUXMUSL_DIR)
-            .output()
-            .expect("Failed to run make command");
-        if !make_output.status.success() {
-            log(
-                LogLevel::Error,
-                &format!(
-                    "\"make -j\" command execution failed: {:?}",
-                    make_output.status.code()
-                ),
-            );
+        let made = make_ruxmusl(|_, args| {
+            Command::new("make")
+                .args(args)
+                .current_dir(&ruxmusl_dir)
+                .output()
+        });
+        if let Err(why) = made {
+            log(LogLevel::Error, &why);
             std::process::exit(1);
         }
-        let make_install_output = Command::new("make")
-            .args(["install"])
-            .current_dir(RUXMUSL_DIR)
-            .stderr(Stdio::inherit())
-            .output()
-            .expect("Failed to run make install command");
-        if !make_install_output.status.success() {
-            log(
-                LogLevel::Error,
-                &format!(
-                    "\"make install\" command execution failed: {:?}",
-                    make_install_output.status.code()
-                ),
-            );
-            std::process::exit(1);
+        let _ = fs::write(&version_file, musl_version);
+    }
+}
+
+/// Compiles and installs musl with `make -j` then `make install`, under a spinner that is
+/// cleared when they finish or fail
+/// # Arguments
+/// * `run` - Runs make with the given args in the musl build dir, given the spinner shown meanwhile
+fn make_ruxmusl(
+    mut run: impl FnMut(&ProgressBar, &[&str]) -> std::io::Result<Output>,
+) -> Result<(), String> {
+    let spinner = tools::spinner("Building musl...");
+    for args in [&["-j"][..], &["install"][..]] {
+        let make = format!("\"make {}\"", args.join(" "));
+        let result = run(&spinner, args);
+        match result {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                spinner.finish_and_clear();
+                return Err(format!("{}: {}", make, tools::failure_message(&output)));
+            }
+            Err(why) => {
+                spinner.finish_and_clear();
+                return Err(format!("Failed to run {}: {}", make, why));
+            }
         }
     }
+    spinner.finish_and_clear();
+    Ok(())
 }
 
 /// Runs the exe target
@@ -580,24 +1028,46 @@ fn build_ruxmusl(build_config: &BuildConfig, os_config: &OSConfig) {
 /// * `build_config` - The local build configuration
 /// * `exe_target` - The exe target to run
 /// * `targets` - A vector of targets
+/// * `print_cmd` - Print the command that would run the exe, e.g. the QEMU command line, instead of running it
 pub fn run(
     bin_args: Option<Vec<&str>>,
     build_config: &BuildConfig,
     os_config: &OSConfig,
     exe_target: &TargetConfig,
     targets: &Vec<TargetConfig>,
+    print_cmd: bool,
 ) {
     let trgt = Target::new(build_config, os_config, exe_target, targets);
-    if !Path::new(&trgt.bin_path).exists() {
-        log(
-            LogLevel::Error,
-            &format!("Could not find binary: {}", &trgt.bin_path),
-        );
-        std::process::exit(1);
+    if !print_cmd {
+        tools::require(&run_tools(os_config));
+        if !Path::new(&trgt.bin_path).exists() {
+            log(
+                LogLevel::Error,
+                &format!("Could not find binary: {}", &trgt.bin_path),
+            );
+            std::process::exit(1);
+        }
     }
     if os_config.platform.qemu != QemuConfig::default() {
         let (qemu_args, qemu_args_debug) =
-            QemuConfig::config_qemu(&os_config.platform.qemu, &os_config.platform, &trgt);
+            QemuConfig::config_qemu(&os_config.platform.qemu, &os_config.platform, &trgt)
+                .unwrap_or_else(|e| {
+                    log(LogLevel::Error, &e.to_string());
+                    std::process::exit(1);
+                });
+        let debug = match os_config.platform.qemu.debug.as_str() {
+            "y" => true,
+            "n" => false,
+            _ => {
+                log(LogLevel::Error, "Debug field must be one of 'y' or 'n'");
+                std::process::exit(1);
+            }
+        };
+        if print_cmd {
+            let qemu_args = if debug { qemu_args_debug } else { qemu_args };
+            println!("{}", tools::display(&qemu_argv(qemu_args, bin_args)));
+            return;
+        }
         // enable virtual disk image if need
         if os_config.platform.qemu.blk == "y" {
             let path = Path::new(&os_config.platform.qemu.disk_img);
@@ -614,14 +1084,15 @@ pub fn run(
             }
         }
         // enable qemu gdb guest if needed
-        if &os_config.platform.qemu.debug == "y" {
+        if debug {
             run_qemu_debug(qemu_args_debug, bin_args);
-        } else if &os_config.platform.qemu.debug == "n" {
-            run_qemu(qemu_args, bin_args);
         } else {
-            log(LogLevel::Error, "Debug field must be one of 'y' or 'n'");
-            std::process::exit(1);
+            run_qemu(qemu_args, bin_args);
         }
+    } else if print_cmd {
+        let mut argv = vec![trgt.bin_path.clone()];
+        argv.extend(bin_args.into_iter().flatten().map(String::from));
+        println!("{}", tools::display(&argv));
     } else {
         log(LogLevel::Log, &format!("Running: {}", &trgt.bin_path));
         let mut cmd = Command::new(&trgt.bin_path);
@@ -644,6 +1115,180 @@ pub fn run(
     }
 }
 
+/// Builds the test targets and runs each of them, natively or on QEMU like `run`,
+/// then prints how many passed and exits with 1 if any failed
+/// # Arguments
+/// * `filter` - Only the tests whose name contains it are built and run
+/// * `output_dir` - Overrides `output_dir` of the config file
+pub fn test(filter: Option<&str>, output_dir: Option<&str>) {
+    let (mut build_config, os_config, targets) =
+        parser::try_parse_config(config_file(), cfg!(target_os = "windows")).unwrap_or_else(|e| {
+            log(LogLevel::Error, &e.to_string());
+            std::process::exit(1);
+        });
+    if let Some(output_dir) = output_dir {
+        build_config.output_dir = output_dir.to_string();
+        check_output_dir(output_dir, &targets);
+    }
+    env::config_env(&os_config);
+
+    let tests: Vec<&TargetConfig> = targets
+        .iter()
+        .filter(|target| target.typ == TargetType::Test)
+        .filter(|target| filter.is_none_or(|filter| target.name.contains(filter)))
+        .collect();
+    if tests.is_empty() {
+        match filter {
+            Some(filter) => log(
+                LogLevel::Warn,
+                &format!("No test target matches \"{}\"", filter),
+            ),
+            None => log(
+                LogLevel::Warn,
+                "No test targets found, add a target with type \"test\"",
+            ),
+        }
+        return;
+    }
+
+    log(LogLevel::Log, "Building tests...");
+    let names: Vec<&str> = tests.iter().map(|test| test.name.as_str()).collect();
+    build(
+        &build_config,
+        &targets,
+        &os_config,
+        None,
+        false,
+        false,
+        false,
+        &names,
+    );
+
+    tools::require(&run_tools(&os_config));
+    let mut failed = Vec::new();
+    for test in &tests {
+        let trgt = Target::new(&build_config, &os_config, test, &targets);
+        log(LogLevel::Log, &format!("Running test: {}", test.name));
+        let argv = if os_config.platform.qemu != QemuConfig::default() {
+            let qemu = &os_config.platform.qemu;
+            if qemu.blk == "y" && !Path::new(&qemu.disk_img).exists() {
+                make_disk_image_fat32(&qemu.disk_img);
+            }
+            let (qemu_args, _) = QemuConfig::config_qemu(qemu, &os_config.platform, &trgt)
+                .unwrap_or_else(|e| {
+                    log(LogLevel::Error, &e.to_string());
+                    std::process::exit(1);
+                });
+            qemu_args
+        } else {
+            vec![trgt.bin_path.clone()]
+        };
+        log(
+            LogLevel::Info,
+            &format!("Command: {}", tools::display(&argv)),
+        );
+        match tools::command(&argv).status() {
+            Ok(status) if status.success() => {
+                log(LogLevel::Log, &format!("Test {} passed", test.name))
+            }
+            Ok(status) => {
+                log(
+                    LogLevel::Error,
+                    &format!("Test {} failed ({})", test.name, status),
+                );
+                failed.push(test.name.as_str());
+            }
+            Err(why) => {
+                log(
+                    LogLevel::Error,
+                    &format!("Could not run test {}: {}", test.name, why),
+                );
+                failed.push(test.name.as_str());
+            }
+        }
+    }
+
+    let summary = format!(
+        "Test result: {} passed, {} failed",
+        tests.len() - failed.len(),
+        failed.len()
+    );
+    if failed.is_empty() {
+        log(LogLevel::Log, &summary);
+    } else {
+        log(LogLevel::Error, &summary);
+        log(
+            LogLevel::Error,
+            &format!("Failed tests: {}", failed.join(", ")),
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Returns the external tools needed to build the project
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `os_config` - The local os configuration
+/// * `targets` - A vector of targets
+fn build_tools(
+    build_config: &BuildConfig,
+    os_config: &OSConfig,
+    targets: &[TargetConfig],
+) -> Vec<String> {
+    // Commands may carry arguments, e.g. `rust-lld -flavor gnu`, only the program is checked
+    let program = |cmd: &str| cmd.split_whitespace().next().map(String::from);
+    let mut tools: Vec<String> = program(&build_config.compiler.read().unwrap())
+        .into_iter()
+        .collect();
+    for target in targets {
+        tools.extend(program(&target.linker));
+        if target.typ == TargetType::Static {
+            tools.extend(program(&target.archive));
+        }
+    }
+    if os_config.has_os() {
+        tools.push("cargo".to_string());
+        if targets.iter().any(|target| target.typ.is_exe()) {
+            tools.push("rust-objcopy".to_string());
+        }
+        if os_config.ulib == "ruxlibc" {
+            tools.push(format!("{}ar", os_config.platform.cross_compile));
+        } else if os_config.ulib == "ruxmusl" {
+            tools.push("make".to_string());
+        }
+    } else if targets
+        .iter()
+        .any(|target| target.typ.is_exe() && target.strip == "always")
+    {
+        tools.push("strip".to_string());
+    }
+    if targets
+        .iter()
+        .any(|target| target.typ.is_exe() && target.emit_asm)
+    {
+        tools.push(objdump(os_config).to_string());
+    }
+    tools.sort();
+    tools.dedup();
+    tools
+}
+
+/// Returns the external tools needed to run the project
+/// # Arguments
+/// * `os_config` - The local os configuration
+fn run_tools(os_config: &OSConfig) -> Vec<String> {
+    let qemu = &os_config.platform.qemu;
+    let mut tools = Vec::new();
+    if qemu != &QemuConfig::default() {
+        tools.push(qemu.qemu_bin(&os_config.platform));
+        if qemu.blk == "y" && !Path::new(&qemu.disk_img).exists() {
+            tools.push("dd".to_string());
+            tools.push("mkfs.fat".to_string());
+        }
+    }
+    tools
+}
+
 /// Makes the disk_img of fat32
 fn make_disk_image_fat32(file_name: &str) {
     log(
@@ -652,7 +1297,7 @@ fn make_disk_image_fat32(file_name: &str) {
     );
     let output = Command::new("dd")
         .arg("if=/dev/zero")
-        .arg(&format!("of={}", file_name))
+        .arg(format!("of={}", file_name))
         .arg("bs=1M")
         .arg("count=64")
         .output()
@@ -685,34 +1330,29 @@ fn make_disk_image_fat32(file_name: &str) {
     }
 }
 
+/// Returns the argv running qemu with its args followed by the args of the bin
+fn qemu_argv(qemu_args: Vec<String>, bin_args: Option<Vec<&str>>) -> Vec<String> {
+    let mut argv = qemu_args;
+    argv.extend(bin_args.into_iter().flatten().map(String::from));
+    argv
+}
+
 /// Runs the bin by qemu
 fn run_qemu(qemu_args: Vec<String>, bin_args: Option<Vec<&str>>) {
     log(LogLevel::Log, "Running on qemu...");
-    let mut cmd = String::new();
-    for qemu_arg in qemu_args {
-        cmd.push_str(&qemu_arg);
-        cmd.push(' ');
-    }
-    if let Some(bin_args) = bin_args {
-        for arg in bin_args {
-            cmd.push_str(arg);
-            cmd.push(' ');
-        }
-    }
-    log(LogLevel::Info, &format!("Command: {}", cmd));
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(cmd)
+    let argv = qemu_argv(qemu_args, bin_args);
+    log(
+        LogLevel::Info,
+        &format!("Command: {}", tools::display(&argv)),
+    );
+    let output = tools::command(&argv)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .output()
         .expect("Failed to start qemu");
     if !output.status.success() {
-        log(
-            LogLevel::Error,
-            &format!("Command execution failed: {:?}", output.stderr),
-        );
+        log(LogLevel::Error, &tools::failure_message(&output));
         std::process::exit(1);
     }
 }
@@ -720,343 +1360,811 @@ fn run_qemu(qemu_args: Vec<String>, bin_args: Option<Vec<&str>>) {
 /// Runs the bin by qemu and enable gdb guest
 fn run_qemu_debug(qemu_debug_args: Vec<String>, bin_args: Option<Vec<&str>>) {
     log(LogLevel::Log, "Debugging on qemu...");
-    let mut cmd = String::new();
-    for qemu_debug_arg in qemu_debug_args {
-        cmd.push_str(&qemu_debug_arg);
-        cmd.push(' ');
-    }
-    if let Some(bin_args) = bin_args {
-        for arg in bin_args {
-            cmd.push_str(arg);
-            cmd.push(' ');
-        }
-    }
-    log(LogLevel::Info, &format!("Command: {}", cmd));
+    let argv = qemu_argv(qemu_debug_args, bin_args);
+    log(
+        LogLevel::Info,
+        &format!("Command: {}", tools::display(&argv)),
+    );
     log(
         LogLevel::Log,
         "QEMU is listening for GDB connection on port 1234...",
     );
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(cmd)
+    let output = tools::command(&argv)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .output()
         .expect("Failed to start qemu");
     if !output.status.success() {
-        log(
-            LogLevel::Error,
-            &format!("Command execution failed: {:?}", output.stderr),
-        );
+        log(LogLevel::Error, &tools::failure_message(&output));
         std::process::exit(1);
     }
 }
 
-/// Initialises a new project in the current directory
-pub fn init_project(project_name: &str, is_c: Option<bool>, config: &GlobalConfig) {
+/// Initialises a new project
+/// # Arguments
+/// * `project_name` - The name of the project
+/// * `is_c` - Whether to create a C project, defaults to the configured language if None
+/// * `config` - The global configuration
+/// * `here` - If true, the project is created in the current directory instead of a new one
+/// * `force` - If true, an existing non-empty directory is populated with the missing files
+/// * `os_arch` - If given, the config targets Ruxos on QEMU for this architecture
+pub fn init_project(
+    project_name: &str,
+    is_c: Option<bool>,
+    config: &GlobalConfig,
+    here: bool,
+    force: bool,
+    os_arch: Option<&str>,
+) {
     log(LogLevel::Log, "Initializing project...");
 
-    if Path::new(project_name).exists() {
-        log(LogLevel::Error, &format!("{} already exists", project_name));
-        log(LogLevel::Error, "Cannot initialise project");
+    let project_dir = if here { "." } else { project_name };
+    let is_empty_dir = fs::read_dir(project_dir)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true);
+    if !force && !is_empty_dir {
+        if here {
+            log(LogLevel::Error, "The current directory is not empty");
+        } else {
+            log(LogLevel::Error, &format!("{} already exists", project_name));
+        }
+        log(
+            LogLevel::Error,
+            "Cannot initialise project, use --force to add the missing files",
+        );
         std::process::exit(1);
     }
 
     //Initialise git repo in project directory
-    let mut cmd = Command::new("git");
-    cmd.arg("init").arg(project_name);
-    let output = cmd.output();
-    if output.is_err() {
-        log(LogLevel::Error, "Could not initialise git repo");
-        log(LogLevel::Error, &format!("{}", output.err().unwrap()));
-        std::process::exit(1);
+    if !Path::new(project_dir).join(".git").exists() {
+        let mut cmd = Command::new("git");
+        cmd.arg("init").arg(project_dir);
+        let output = cmd.output();
+        if output.is_err() {
+            log(LogLevel::Error, "Could not initialise git repo");
+            log(LogLevel::Error, &format!("{}", output.err().unwrap()));
+            std::process::exit(1);
+        }
     }
 
+    let is_c = match is_c {
+        Some(is_c) => is_c,
+        None => match config.get_default_language().as_str() {
+            "c" => true,
+            "cpp" => false,
+            _ => {
+                log(LogLevel::Error, "Invalid default language");
+                std::process::exit(1);
+            }
+        },
+    };
+    let compiler = match (config.get_default_compiler().as_str(), is_c) {
+        ("gcc", true) => "gcc",
+        ("gcc", false) => "g++",
+        ("clang", true) => "clang",
+        ("clang", false) => "clang++",
+        _ => {
+            log(LogLevel::Error, "Invalid default compiler");
+            std::process::exit(1);
+        }
+    };
+
     //Initialise config_linux.toml
     #[cfg(target_os = "windows")]
-    let config_file = project_name.to_owned() + "/config_win32.toml";
+    let config_file = project_dir.to_owned() + "/config_win32.toml";
     #[cfg(target_os = "linux")]
-    let config_file = project_name.to_owned() + "/config_linux.toml";
-    if Path::new(&config_file).exists() {
-        log(LogLevel::Error, &format!("{} already exists", config_file));
-        log(LogLevel::Error, "Cannot initialise project");
+    let config_file = project_dir.to_owned() + "/config_linux.toml";
+    #[cfg(target_os = "macos")]
+    let config_file = project_dir.to_owned() + "/config_macos.toml";
+    let sample_config = match os_arch {
+        Some(arch) => {
+            let machine = if arch == "x86_64" { "q35" } else { "virt" };
+            let ulib = if is_c { "ruxlibc" } else { "ruxmusl" };
+            format!("[build]\ncompiler = \"{}\"\n\n[os]\nname = \"ruxos\"\nservices = [\"alloc\"]\nulib = \"{}\"\n\n[os.platform]\nname = \"{}-qemu-{}\"\nsmp = \"1\"\nmode = \"release\"\nlog = \"warn\"\n\n[os.platform.qemu]\ngraphic = \"n\"\n\n[[targets]]\nname = \"main\"\nsrc = \"./src/\"\ninclude_dir = \"./src/include/\"\ntype = \"exe\"\ncflags = \"-g -Wall -Wextra\"\nlinker = \"rust-lld -flavor gnu\"\nldflags = \"\"\ndeps = []\n", compiler, ulib, arch, machine)
+        }
+        None => format!("[build]\ncompiler = \"{}\"\n\n[[targets]]\nname = \"main\"\nsrc = \"./src/\"\ninclude_dir = \"./src/include/\"\ntype = \"exe\"\ncflags = \"-g -Wall -Wextra\"\nldflags = \"\"\ndeps = []\n", compiler),
+    };
+    create_new_file(&config_file, sample_config.as_bytes());
+
+    //Create src and src/include directories
+    let src_dir = project_dir.to_owned() + "/src";
+    let include_dir = project_dir.to_owned() + "/src/include";
+    fs::create_dir_all(&include_dir).unwrap_or_else(|why| {
+        log(LogLevel::Warn, &format!("Project name {}", project_name));
+        log(
+            LogLevel::Error,
+            &format!("Could not create src/include directory: {}", why),
+        );
         std::process::exit(1);
+    });
+
+    //Create main.c or main.cpp
+    if is_c {
+        create_new_file(
+            &(src_dir + "/main.c"),
+            b"#include <stdio.h>\n\nint main() {\n\tprintf(\"Here is a Ruxgo example!\\n\");\n\treturn 0;\n}",
+        );
+    } else {
+        create_new_file(
+            &(src_dir + "/main.cpp"),
+            b"#include <iostream>\n\nint main() {\n\tstd::cout << \"Here is a Ruxgo example!\" << std::endl;\n\treturn 0;\n}",
+        );
     }
-    let mut config_file = fs::OpenOptions::new()
+
+    //Create .gitignore
+    create_new_file(
+        &(project_dir.to_owned() + "/.gitignore"),
+        format!(
+            "{}\ncompile_commands.json\n.cache\n",
+            parser::DEFAULT_OUTPUT_DIR
+        )
+        .as_bytes(),
+    );
+
+    //Create README.md
+    create_new_file(
+        &(project_dir.to_owned() + "/README.md"),
+        format!("# {}", project_name).as_bytes(),
+    );
+
+    //Create LICENSE
+    let license_text = license::license_text(
+        &config.get_license(),
+        license::current_year(),
+        &license::author(),
+    );
+    create_new_file(
+        &(project_dir.to_owned() + "/LICENSE"),
+        license_text.as_bytes(),
+    );
+
+    log(
+        LogLevel::Log,
+        &format!("Project {} initialised", project_name),
+    );
+}
+
+/// Creates a file with the given contents, leaving it untouched if it already exists
+/// # Arguments
+/// * `path` - The path of the file
+/// * `contents` - The contents to write
+fn create_new_file(path: &str, contents: &[u8]) {
+    let file_name = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path);
+    let mut file = match fs::OpenOptions::new()
         .write(true)
-        .create(true)
-        .open(config_file)
-        .unwrap_or_else(|why| {
+        .create_new(true)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(why) if why.kind() == std::io::ErrorKind::AlreadyExists => {
+            log(LogLevel::Info, &format!("Keeping existing {}", path));
+            return;
+        }
+        Err(why) => {
             log(
                 LogLevel::Error,
-                &format!("Could not create config file: {}", why),
+                &format!("Could not create {}: {}", file_name, why),
             );
             std::process::exit(1);
-        });
-
-    let c_compiler = match config.get_default_compiler().as_str() {
-        "gcc" => "gcc",
-        "clang" => "clang",
-        _ => {
-            log(LogLevel::Error, "Invalid default compiler");
-            std::process::exit(1);
         }
     };
-    let cpp_compiler = match config.get_default_compiler().as_str() {
-        "gcc" => "g++",
-        "clang" => "clang++",
-        _ => {
-            log(LogLevel::Error, "Invalid default compiler");
+    file.write_all(contents).unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not write to {}: {}", file_name, why),
+        );
+        std::process::exit(1);
+    });
+}
+
+/// The config file set by `--config`, overriding the default one
+static CONFIG_FILE: OnceLock<String> = OnceLock::new();
+
+/// Overrides the config file of local project, `-` reads it from stdin
+/// # Arguments
+/// * `path` - The path to the config file
+pub fn set_config_file(path: &str) {
+    let _ = CONFIG_FILE.set(path.to_string());
+}
+
+/// Returns the config file of local project, unless overridden by `--config`
+/// On macOS `config_macos.toml` is used if it exists, otherwise `config_linux.toml`
+fn config_file() -> &'static str {
+    if let Some(path) = CONFIG_FILE.get() {
+        return path;
+    }
+    #[cfg(target_os = "windows")]
+    return "./config_win32.toml";
+    #[cfg(target_os = "macos")]
+    if Path::new("./config_macos.toml").exists() {
+        return "./config_macos.toml";
+    }
+    #[cfg(not(target_os = "windows"))]
+    return "./config_linux.toml";
+}
+
+/// Returns the member directories if the config file is a workspace, relative to the config file
+pub fn workspace_members() -> Option<Vec<PathBuf>> {
+    match parser::try_parse_workspace(config_file()) {
+        Ok(members) => {
+            let root = Path::new(config_file())
+                .parent()
+                .filter(|root| *root != Path::new("."))
+                .unwrap_or(Path::new(""));
+            members.map(|members| members.iter().map(|member| root.join(member)).collect())
+        }
+        // A missing config file is reported when parsing the project config
+        Err(ConfigError::Io { .. }) => None,
+        Err(e) => {
+            log(LogLevel::Error, &e.to_string());
             std::process::exit(1);
         }
-    };
-    let sample_cpp_config = format!("[build]\ncompiler = \"{}\"\n\n[[targets]]\nname = \"main\"\nsrc = \"./src/\"\ninclude_dir = \"./src/include/\"\ntype = \"exe\"\ncflags = \"-g -Wall -Wextra\"\nldflags = \"\"\ndeps = []\n", cpp_compiler);
+    }
+}
 
-    let sample_c_config = format!("[build]\ncompiler = \"{}\"\n\n[[targets]]\nname = \"main\"\nsrc = \"./src/\"\ninclude_dir = \"./src/include/\"\ntype = \"exe\"\ncflags = \"-g -Wall -Wextra\"\nldflags = \"\"\ndeps = []\n", c_compiler);
+/// Runs ruxgo with the same arguments in the directory of each workspace member,
+/// then prints the result of each member and exits with 1 if any failed
+/// # Arguments
+/// * `members` - The directories of the workspace members
+/// * `args` - The arguments to run ruxgo with in each member, e.g. `["-b"]`
+pub fn workspace(members: &[PathBuf], args: &[String]) -> ! {
+    let exe = std::env::current_exe().unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not find the ruxgo executable: {}", why),
+        );
+        std::process::exit(1);
+    });
+    let mut results = Vec::new();
+    for member in members {
+        let name = member.display().to_string();
+        log(LogLevel::Log, &format!("Workspace member: {}", name));
+        let result = if member.is_dir() {
+            let mut cmd = Command::new(&exe);
+            cmd.arg("--path").arg(member).args(args);
+            log(LogLevel::Info, &format!("Command: {:?}", cmd));
+            match cmd.status() {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => Err(status.to_string()),
+                Err(why) => Err(format!("could not run ruxgo: {}", why)),
+            }
+        } else {
+            Err("directory not found".to_string())
+        };
+        results.push((name, result));
+    }
 
-    let sample_config = match is_c {
-        Some(true) => sample_c_config,
-        Some(false) => sample_cpp_config,
-        None => match config.get_default_language().as_str() {
-            "c" => sample_c_config,
-            "cpp" => sample_cpp_config,
-            _ => {
-                log(LogLevel::Error, "Invalid default language");
-                std::process::exit(1);
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    for (name, result) in &results {
+        match result {
+            Ok(()) => log(LogLevel::Log, &format!("  {}: ok", name)),
+            Err(why) => log(LogLevel::Error, &format!("  {}: failed ({})", name, why)),
+        }
+    }
+    let summary = format!(
+        "Workspace result: {} succeeded, {} failed",
+        results.len() - failed.len(),
+        failed.len()
+    );
+    if failed.is_empty() {
+        log(LogLevel::Log, &summary);
+        std::process::exit(0);
+    }
+    log(LogLevel::Error, &summary);
+    std::process::exit(1);
+}
+
+/// Parses the config file of local project
+/// # Arguments
+/// * `selected` - The target selected by `--target`, if any
+pub fn parse_config(selected: Option<&str>) -> (BuildConfig, OSConfig, Vec<TargetConfig>) {
+    let (build_config, os_config, targets) =
+        parser::parse_config(config_file(), cfg!(target_os = "windows"));
+
+    // An explicitly selected target lifts the "exactly one exe" constraint
+    if let Some(name) = selected {
+        find_target(&targets, name);
+    } else {
+        check_exe_targets(&targets);
+    }
+
+    // Add environment config
+    env::config_env(&os_config);
+
+    (build_config, os_config, targets)
+}
+
+/// Returns the target with the given name, suggesting a near match if it does not exist
+pub fn find_target<'a>(targets: &'a [TargetConfig], name: &str) -> &'a TargetConfig {
+    targets
+        .iter()
+        .find(|target| target.name == name)
+        .unwrap_or_else(|| {
+            let mut msg = format!("Target \"{}\" not found", name);
+            if let Some(suggestion) =
+                closest_match(name, targets.iter().map(|target| target.name.as_str()))
+            {
+                msg.push_str(&format!(", did you mean \"{}\"?", suggestion));
             }
-        },
-    };
-    config_file
-        .write_all(sample_config.as_bytes())
-        .unwrap_or_else(|why| {
-            log(
-                LogLevel::Error,
-                &format!("Could not write to config file: {}", why),
-            );
+            log(LogLevel::Error, &msg);
             std::process::exit(1);
-        });
+        })
+}
 
-    //Create src and src/include directories
-    let src_dir = project_name.to_owned() + "/src";
-    let include_dir = project_name.to_owned() + "/src/include";
-    if !Path::new(&src_dir).exists() {
-        fs::create_dir(&src_dir).unwrap_or_else(|why| {
-            log(LogLevel::Warn, &format!("Project name {}", project_name));
-            log(
-                LogLevel::Error,
-                &format!("Could not create src directory: {}", why),
-            );
-            std::process::exit(1);
-        });
+/// Returns the exe target to run, which is either the selected target or the only exe target
+pub fn find_exe_target<'a>(
+    targets: &'a [TargetConfig],
+    selected: Option<&str>,
+) -> &'a TargetConfig {
+    let exe_target = match selected {
+        Some(name) => find_target(targets, name),
+        None => targets
+            .iter()
+            .find(|target| target.typ == TargetType::Exe)
+            .unwrap(),
+    };
+    if exe_target.typ != TargetType::Exe {
+        log(
+            LogLevel::Error,
+            &format!(
+                "Target \"{}\" is a {} target and cannot be run",
+                exe_target.name, exe_target.typ
+            ),
+        );
+        std::process::exit(1);
+    }
+    exe_target
+}
+
+/// Returns the names of the given target and all of its transitive dependencies
+fn target_with_deps(targets: &[TargetConfig], name: &str) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut to_visit = vec![name.to_string()];
+    while let Some(name) = to_visit.pop() {
+        if names.insert(name.clone()) {
+            if let Some(target) = targets.iter().find(|target| target.name == name) {
+                to_visit.extend(target.deps.iter().cloned());
+            }
+        }
     }
-    if !Path::new(&include_dir).exists() {
-        fs::create_dir(&include_dir).unwrap_or_else(|why| {
+    names
+}
+
+/// Checks that exactly one exe target is specified, naming the offending targets otherwise
+fn check_exe_targets(targets: &[TargetConfig]) {
+    if let Some(msg) = exe_targets_error(targets) {
+        log(LogLevel::Error, &msg);
+        if msg.starts_with("Multiple") {
             log(
                 LogLevel::Error,
-                &format!("Could not create src/include directory: {}", why),
+                "Use `--target <name>` to select which one to build or run",
             );
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Returns an error message unless exactly one target is an exe
+fn exe_targets_error(targets: &[TargetConfig]) -> Option<String> {
+    let mut exe_names: Vec<&str> = targets
+        .iter()
+        .filter(|target| target.typ == TargetType::Exe)
+        .map(|target| target.name.as_str())
+        .collect();
+    // Targets come out of the topological sort in no fixed order
+    exe_names.sort_unstable();
+    match exe_names.len() {
+        1 => None,
+        0 => Some(
+            "No executable target found, exactly one target must have type \"exe\"".to_string(),
+        ),
+        _ => Some(format!(
+            "Multiple executable targets found: {}",
+            exe_names.join(", ")
+        )),
+    }
+}
+
+/// Checks an `--output-dir` given on the command line like `output_dir` in the config file
+/// # Arguments
+/// * `output_dir` - The output dir given on the command line
+/// * `targets` - The targets of the project
+pub fn check_output_dir(output_dir: &str, targets: &[TargetConfig]) {
+    if let Err(e) = parser::check_output_dir(output_dir, targets) {
+        log(LogLevel::Error, &e.to_string());
+        std::process::exit(1);
+    }
+}
+
+/// Validates the config file of local project without building anything
+/// Prints a summary of the targets and exits with 1 if any problem is found
+pub fn check() {
+    let (_, os_config, targets) =
+        parser::try_parse_config(config_file(), false).unwrap_or_else(|e| {
+            log(LogLevel::Error, &e.to_string());
             std::process::exit(1);
         });
-    }
 
-    //Create main.c or main.cpp
-    let main_path: String;
-    match is_c {
-        Some(true) => main_path = src_dir.to_owned() + "/main.c",
-        Some(false) => main_path = src_dir.to_owned() + "/main.cpp",
-        None => match config.get_default_language().as_str() {
-            "c" => main_path = src_dir.to_owned() + "/main.c",
-            "cpp" => main_path = src_dir.to_owned() + "/main.cpp",
-            _ => {
-                log(LogLevel::Error, "Invalid default language");
-                std::process::exit(1);
+    let mut problems = features::unknown_services(&os_config);
+    if let Some(msg) = exe_targets_error(&targets) {
+        problems.push(msg);
+    }
+    if !os_config.ruxos_dir.is_empty() && !Path::new(&os_config.ruxos_dir).is_dir() {
+        problems.push(format!(
+            "ruxos_dir \"{}\" in [os] does not exist",
+            os_config.ruxos_dir
+        ));
+    }
+    for target in &targets {
+        // Header-only targets have no sources
+        if target.typ != TargetType::HeaderOnly {
+            if let Some(src) = target.src.iter().find(|src| !Path::new(src).is_dir()) {
+                problems.push(format!(
+                    "Target \"{}\": src directory \"{}\" does not exist",
+                    target.name, src
+                ));
+            } else if let Err(e) = target.check_duplicate_srcs() {
+                problems.push(e.to_string());
             }
-        },
+        }
+        for include_dir in &target.include_dir {
+            if !Path::new(include_dir).exists() {
+                problems.push(format!(
+                    "Target \"{}\": include_dir \"{}\" does not exist",
+                    target.name, include_dir
+                ));
+            }
+        }
+        if !target.pch.is_empty() && !Path::new(&target.pch).is_file() {
+            problems.push(format!(
+                "Target \"{}\": pch \"{}\" does not exist",
+                target.name, target.pch
+            ));
+        }
+        for dep in &target.deps {
+            if let Some(dep_target) = targets.iter().find(|t| t.name == *dep) {
+                if !dep_target.typ.is_lib() {
+                    problems.push(format!(
+                        "Target \"{}\": dependency \"{}\" is a {} target, only libs can be dependencies",
+                        target.name, dep, dep_target.typ
+                    ));
+                }
+            }
+        }
+        problems.extend(
+            target
+                .unresolved_deps(&targets)
+                .iter()
+                .map(|e| e.to_string()),
+        );
     }
-    if !Path::new(&main_path).exists() {
-        let mut main_file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(&main_path)
-            .unwrap_or_else(|why| {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not create main.cpp: {}", why),
-                );
-                std::process::exit(1);
-            });
 
-        let c_sample_program =
-            b"#include <stdio.h>\n\nint main() {\n\tprintf(\"Here is a Ruxgo example!\\n\");\n\treturn 0;\n}";
-        let cpp_sample_program =
-            b"#include <iostream>\n\nint main() {\n\tstd::cout << \"Here is a Ruxgo example!\" << std::endl;\n\treturn 0;\n}";
-        match is_c {
-            Some(true) => main_file.write_all(c_sample_program).unwrap_or_else(|why| {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not write to main.c: {}", why),
-                );
-                std::process::exit(1);
-            }),
-            Some(false) => main_file
-                .write_all(cpp_sample_program)
-                .unwrap_or_else(|why| {
-                    log(
-                        LogLevel::Error,
-                        &format!("Could not write to main.cpp: {}", why),
-                    );
-                    std::process::exit(1);
-                }),
-            None => match config.get_default_language().as_str() {
-                "c" => main_file.write_all(c_sample_program).unwrap_or_else(|why| {
-                    log(
-                        LogLevel::Error,
-                        &format!("Could not write to main.c: {}", why),
-                    );
-                    std::process::exit(1);
-                }),
-                "cpp" => main_file
-                    .write_all(cpp_sample_program)
-                    .unwrap_or_else(|why| {
-                        log(
-                            LogLevel::Error,
-                            &format!("Could not write to main.cpp: {}", why),
-                        );
-                        std::process::exit(1);
-                    }),
-                _ => {
-                    log(LogLevel::Error, "Invalid default language");
-                    std::process::exit(1);
-                }
-            },
+    for target in &targets {
+        log(
+            LogLevel::Log,
+            &format!(
+                "{} ({}): {} source files",
+                target.name,
+                target.typ,
+                target.count_srcs()
+            ),
+        );
+    }
+    if problems.is_empty() {
+        log(LogLevel::Log, &format!("{} is valid", config_file()));
+    } else {
+        for problem in &problems {
+            log(LogLevel::Error, problem);
         }
+        log(
+            LogLevel::Error,
+            &format!("Found {} problem(s) in {}", problems.len(), config_file()),
+        );
+        std::process::exit(1);
     }
+}
 
-    //Create .gitignore
-    let gitignore_path = project_name.to_owned() + "/.gitignore";
-    if !Path::new(&gitignore_path).exists() {
-        let mut gitignore_file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(&gitignore_path)
-            .unwrap_or_else(|why| {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not create .gitignore: {}", why),
-                );
-                std::process::exit(1);
-            });
-        gitignore_file
-            .write_all(b"ruxgo_bld\ncompile_commands.json\n.cache\n")
+/// Formats the source files of all targets with clang-format, which picks up
+/// the `.clang-format` file of the project if there is one
+/// # Arguments
+/// * `check` - Only report the files that are not formatted and exit with an error if any
+pub fn fmt(check: bool) {
+    let (_, _, targets) = parser::try_parse_config(config_file(), false).unwrap_or_else(|e| {
+        log(LogLevel::Error, &e.to_string());
+        std::process::exit(1);
+    });
+    let mut files: Vec<String> = targets.iter().flat_map(|t| t.src_files()).collect();
+    files.sort();
+    files.dedup();
+    if files.is_empty() {
+        log(LogLevel::Log, "No source files to format");
+        return;
+    }
+
+    let run_clang_format = |args: &[&str]| {
+        Command::new("clang-format")
+            .args(args)
+            .output()
             .unwrap_or_else(|why| {
                 log(
                     LogLevel::Error,
-                    &format!("Could not write to .gitignore: {}", why),
+                    &format!("Could not run clang-format: {}", why),
                 );
                 std::process::exit(1);
-            });
-    }
-
-    //Create README.md
-    let mut readme_file = fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(project_name.to_owned() + "/README.md")
-        .unwrap_or_else(|why| {
+            })
+    };
+    if check {
+        let mut unformatted = 0;
+        for file in &files {
+            let output = run_clang_format(&["--dry-run", "--Werror", file]);
+            if !output.status.success() {
+                log(LogLevel::Warn, &format!("Not formatted: {}", file));
+                unformatted += 1;
+            }
+        }
+        if unformatted > 0 {
             log(
                 LogLevel::Error,
-                &format!("Could not create README.md: {}", why),
+                &format!(
+                    "{} of {} file(s) are not formatted",
+                    unformatted,
+                    files.len()
+                ),
             );
             std::process::exit(1);
-        });
-    readme_file
-        .write_all(format!("# {}", project_name).as_bytes())
-        .unwrap_or_else(|why| {
+        }
+        log(
+            LogLevel::Log,
+            &format!("All {} file(s) are formatted", files.len()),
+        );
+    } else {
+        let mut args = vec!["-i"];
+        args.extend(files.iter().map(|file| file.as_str()));
+        log(
+            LogLevel::Info,
+            &format!("Command: clang-format {}", args.join(" ")),
+        );
+        let output = run_clang_format(&args);
+        if !output.status.success() {
             log(
                 LogLevel::Error,
-                &format!("Could not write to README.md: {}", why),
+                &format!(
+                    "clang-format failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ),
             );
             std::process::exit(1);
-        });
+        }
+        log(LogLevel::Log, &format!("Formatted {} file(s)", files.len()));
+    }
+}
 
-    //Create LICENSE
-    let mut license_file = fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(project_name.to_owned() + "/LICENSE")
-        .unwrap_or_else(|why| {
-            log(
-                LogLevel::Error,
-                &format!("Could not create LICENSE: {}", why),
-            );
-            std::process::exit(1);
-        });
+/// Runs clang-tidy over the source files of all targets, using the compile_commands.json
+/// generated by `ruxgo -b --gen-cc`, and logs its diagnostics as warnings
+/// # Arguments
+/// * `checks` - Passed to clang-tidy as `--checks`
+/// * `tidy_error` - Exit with an error if clang-tidy reports any error
+pub fn lint(checks: Option<&str>, tidy_error: bool) {
+    if !Path::new("./compile_commands.json").exists() {
+        log(
+            LogLevel::Error,
+            "compile_commands.json not found, run `ruxgo -b --gen-cc` first",
+        );
+        std::process::exit(1);
+    }
+    // Only the source paths are needed, so neither pre_build nor the compiler is run
+    let (_, _, targets) = parser::parse_config(config_file(), cfg!(target_os = "windows"));
+    let mut srcs = Vec::new();
+    for target in targets
+        .iter()
+        .filter(|target| target.typ != TargetType::HeaderOnly)
+    {
+        srcs.extend(target.src_files());
+    }
+    srcs.sort();
+    srcs.dedup();
 
-    let license = config.get_license();
-    if license.as_str() == "NONE" {
-        license_file.write_all(b"No license").unwrap_or_else(|why| {
+    let (mut warnings, mut errors) = (0, 0);
+    for src in &srcs {
+        let mut cmd = Command::new("clang-tidy");
+        cmd.arg("-p").arg(".").arg("--quiet");
+        if let Some(checks) = checks {
+            cmd.arg(format!("--checks={}", checks));
+        }
+        cmd.arg(src);
+        log(LogLevel::Info, &format!("Command: {:?}", cmd));
+        let output = cmd.output().unwrap_or_else(|why| {
             log(
                 LogLevel::Error,
-                &format!("Could not write to LICENSE: {}", why),
+                &format!("Could not run clang-tidy: {}", why),
             );
             std::process::exit(1);
         });
-    } else {
-        license_file
-            .write_all(license.as_bytes())
-            .unwrap_or_else(|why| {
-                log(
-                    LogLevel::Error,
-                    &format!("Could not write to LICENSE: {}", why),
-                );
-                std::process::exit(1);
-            });
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if line.contains(": warning: ") {
+                warnings += 1;
+            } else if line.contains(": error: ") {
+                errors += 1;
+            } else {
+                continue;
+            }
+            log(LogLevel::Warn, line);
+        }
     }
 
     log(
         LogLevel::Log,
-        &format!("Project {} initialised", project_name),
+        &format!(
+            "Linted {} file(s): {} warning(s), {} error(s)",
+            srcs.len(),
+            warnings,
+            errors
+        ),
     );
+    if tidy_error && errors > 0 {
+        std::process::exit(1);
+    }
 }
 
-/// Parses the config file of local project
-pub fn parse_config() -> (BuildConfig, OSConfig, Vec<TargetConfig>) {
-    #[cfg(target_os = "linux")]
-    let (build_config, os_config, targets) = parser::parse_config("./config_linux.toml", false);
-    #[cfg(target_os = "windows")]
-    let (build_config, os_config, targets) = utils::parse_config("./config_win32.toml", true);
+/// Flags that disable floating point registers, under which clang rejects the floating
+/// point code of the headers it parses, so clangd must not use them
+static CLANGD_REMOVE_FLAGS: [&str; 2] = ["-mno-sse", "-mgeneral-regs-only"];
+
+/// Writes a .clangd file that points clangd at compile_commands.json and adds the
+/// include paths and defines of the build, which also apply to headers missing from it
+/// # Arguments
+/// * `build_config` - The local build configuration
+/// * `os_config` - The local os configuration
+/// * `targets` - A vector of targets
+/// * `gen_cc` - The compile_commands.json generated with it, if any, whose directory clangd reads
+fn gen_clangd_file(
+    build_config: &BuildConfig,
+    os_config: &OSConfig,
+    targets: &[TargetConfig],
+    gen_cc: Option<&Path>,
+) {
+    let current_dir = std::env::current_dir().unwrap();
+    // clangd applies the flags to files in any directory, so include paths are made absolute
+    let include = |dir: &str| {
+        format!(
+            "-I{}",
+            current_dir.join(dir.trim_start_matches("./")).display()
+        )
+    };
+    let os_cflags = os_cflags(build_config, os_config);
+    let mut add = Vec::new();
+    for flag in os_cflags.split_whitespace() {
+        if let Some(dir) = flag.strip_prefix("-I") {
+            add.push(include(dir));
+        } else if flag.starts_with("-D") || flag == "-nostdinc" {
+            add.push(flag.to_string());
+        }
+    }
+    for target in targets {
+        add.extend(target.include_dir.iter().map(|dir| include(dir)));
+    }
+    let mut seen = HashSet::new();
+    add.retain(|flag| seen.insert(flag.clone()));
+    let remove: Vec<&str> = CLANGD_REMOVE_FLAGS
+        .into_iter()
+        .filter(|flag| {
+            os_cflags.split_whitespace().any(|f| f == *flag)
+                || targets
+                    .iter()
+                    .any(|t| t.cflags.split_whitespace().any(|f| f == *flag))
+        })
+        .collect();
 
-    let mut num_exe = 0;
-    let mut exe_target: Option<&TargetConfig> = None;
+    let database = gen_cc
+        .and_then(Path::parent)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or_else(|| ".".to_string(), |dir| dir.display().to_string());
 
-    for target in &targets {
-        if target.typ == "exe" {
-            num_exe += 1;
-            exe_target = Some(target);
+    // JSON strings are valid YAML scalars and take care of the quoting
+    let mut clangd = format!(
+        "# Generated by ruxgo\nCompileFlags:\n  CompilationDatabase: {}\n",
+        serde_json::json!(database)
+    );
+    if !add.is_empty() {
+        clangd.push_str("  Add:\n");
+        for flag in &add {
+            clangd.push_str(&format!("    - {}\n", serde_json::json!(flag)));
+        }
+    }
+    if !remove.is_empty() {
+        clangd.push_str("  Remove:\n");
+        for flag in &remove {
+            clangd.push_str(&format!("    - {}\n", serde_json::json!(flag)));
         }
     }
-    if num_exe != 1 || exe_target.is_none() {
+    fs::write(".clangd", clangd).unwrap_or_else(|why| {
         log(
             LogLevel::Error,
-            "Exactly one executable target must be specified",
+            &format!("Could not write .clangd file: {}", why),
         );
         std::process::exit(1);
-    }
+    });
+}
 
-    // Add environment config
-    env::config_env(&os_config);
+/// The entries of compile_commands.json before the build, merged with the new ones after it
+static PREVIOUS_CC: OnceLock<Vec<serde_json::Value>> = OnceLock::new();
 
-    (build_config, os_config, targets)
+/// Keeps the entries of the existing compile_commands.json, then empties it for the build
+/// # Arguments
+/// * `cc_path` - The path of the compile_commands.json, its directory is created if missing
+pub fn pre_gen_cc(cc_path: &Path) {
+    let previous = fs::read_to_string(cc_path)
+        .map(|contents| parse_cc_entries(&contents))
+        .unwrap_or_default();
+    let _ = PREVIOUS_CC.set(previous);
+    let created = match cc_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::File::create(cc_path)),
+        None => fs::File::create(cc_path),
+    };
+    if let Err(why) = created {
+        log(
+            LogLevel::Error,
+            &format!("Could not create {}: {}", cc_path.display(), why),
+        );
+        std::process::exit(1);
+    }
 }
 
-pub fn pre_gen_cc() {
-    if !Path::new("./compile_commands.json").exists() {
-        fs::File::create(Path::new("./compile_commands.json")).unwrap();
-    } else {
-        fs::remove_file(Path::new("./compile_commands.json")).unwrap();
-        fs::File::create(Path::new("./compile_commands.json")).unwrap();
+/// Parses the entries of a compile_commands.json, also accepting the trailing comma
+/// left by the entries written during a build
+/// # Arguments
+/// * `contents` - The contents of the compile_commands.json
+fn parse_cc_entries(contents: &str) -> Vec<serde_json::Value> {
+    let entries = contents.trim();
+    let entries = entries.strip_prefix('[').unwrap_or(entries);
+    let entries = entries.strip_suffix(']').unwrap_or(entries).trim_end();
+    let entries = entries.strip_suffix(',').unwrap_or(entries);
+    serde_json::from_str(&format!("[{}]", entries)).unwrap_or_else(|why| {
+        log(
+            LogLevel::Warn,
+            &format!("Ignoring the invalid compile_commands.json: {}", why),
+        );
+        Vec::new()
+    })
+}
+
+/// Merges the entries written during the build into the ones of the previous compile_commands.json
+/// by `file`, so that building targets separately keeps the entries of all of them
+/// # Arguments
+/// * `cc_path` - The path of the compile_commands.json
+fn finish_gen_cc(cc_path: &Path) {
+    let contents = fs::read_to_string(cc_path).unwrap_or_else(|why| {
+        log(LogLevel::Error, &format!("Could not read cc file: {}", why));
+        std::process::exit(1);
+    });
+    let current = parse_cc_entries(&contents);
+    let file = |entry: &serde_json::Value| entry.get("file").cloned();
+    let mut merged = PREVIOUS_CC.get().cloned().unwrap_or_default();
+    for entry in current {
+        match merged
+            .iter_mut()
+            .find(|previous| file(previous) == file(&entry))
+        {
+            Some(previous) => *previous = entry,
+            None => merged.push(entry),
+        }
     }
+    let merged = serde_json::to_string_pretty(&merged).unwrap();
+    fs::write(cc_path, merged).unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not write to cc file: {}", why),
+        );
+        std::process::exit(1);
+    });
 }
 
 pub fn pre_gen_vsc() {
@@ -1071,3 +2179,318 @@ pub fn pre_gen_vsc() {
         fs::File::create(Path::new("./.vscode/c_cpp_properties.json")).unwrap();
     }
 }
+
+/// Header file extensions installed from the include dirs of library targets
+const HEADER_EXTS: [&str; 4] = ["h", "hh", "hpp", "hxx"];
+
+/// Copies the outputs listed in the build manifest to a prefix: exes into `<prefix>/bin`,
+/// libraries into `<prefix>/lib` and the headers of the libraries' include dirs into `<prefix>/include`
+/// # Arguments
+/// * `prefix` - The directory to install into
+/// * `dry_run` - Only list the files that would be copied
+/// * `output_dir` - Overrides `output_dir` of the config file, where the build manifest is read from
+pub fn install(prefix: &Path, dry_run: bool, output_dir: Option<&str>) {
+    let (mut build_config, _, targets) = parser::try_parse_config(config_file(), false)
+        .unwrap_or_else(|e| {
+            log(LogLevel::Error, &e.to_string());
+            std::process::exit(1);
+        });
+    if let Some(output_dir) = output_dir {
+        build_config.output_dir = output_dir.to_string();
+        check_output_dir(output_dir, &targets);
+    }
+    let manifest_path = build_config.build_manifest_path();
+    let manifest: serde_json::Value = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|manifest| serde_json::from_str(&manifest).ok())
+        .unwrap_or_else(|| {
+            log(
+                LogLevel::Error,
+                &format!("Could not read {}, run `ruxgo -b` first", manifest_path),
+            );
+            std::process::exit(1);
+        });
+
+    // (source, destination) of each file to install
+    let mut copies = Vec::new();
+    let out_dir = fs::canonicalize(&build_config.output_dir).ok();
+    let built = manifest["targets"].as_array().cloned().unwrap_or_default();
+    for entry in &built {
+        let name = entry["name"].as_str().unwrap_or_default();
+        // Tests are only run by `ruxgo test`, not installed
+        if entry["type"].as_str() == Some(TargetType::Test.as_str()) {
+            continue;
+        }
+        let is_exe = entry["type"].as_str() == Some(TargetType::Exe.as_str());
+        let dest_dir = prefix.join(if is_exe { "bin" } else { "lib" });
+        for output in entry["outputs"].as_array().into_iter().flatten() {
+            let output = Path::new(output.as_str().unwrap_or_default());
+            copies.push((
+                output.to_path_buf(),
+                dest_dir.join(output.file_name().unwrap()),
+            ));
+        }
+        if is_exe {
+            continue;
+        }
+        let Some(target) = targets.iter().find(|target| target.name == name) else {
+            continue;
+        };
+        for include_dir in &target.include_dir {
+            for entry in WalkDir::new(include_dir)
+                .into_iter()
+                .filter_entry(|e| {
+                    // Skip the build outputs when the include dir is the project root
+                    out_dir.is_none() || fs::canonicalize(e.path()).ok() != out_dir
+                })
+                .filter_map(|e| e.ok())
+            {
+                let path = entry.path();
+                let is_header = path
+                    .extension()
+                    .is_some_and(|ext| HEADER_EXTS.contains(&ext.to_string_lossy().as_ref()));
+                if entry.file_type().is_file() && is_header {
+                    let relative = path.strip_prefix(include_dir).unwrap_or(path);
+                    copies.push((path.to_path_buf(), prefix.join("include").join(relative)));
+                }
+            }
+        }
+    }
+    copies.sort();
+    copies.dedup();
+    if copies.is_empty() {
+        log(LogLevel::Log, "Nothing to install");
+        return;
+    }
+
+    for (src, dest) in &copies {
+        if dry_run {
+            log(
+                LogLevel::Log,
+                &format!("Would install {} -> {}", src.display(), dest.display()),
+            );
+            continue;
+        }
+        log(
+            LogLevel::Info,
+            &format!("Installing {} -> {}", src.display(), dest.display()),
+        );
+        // fs::copy also copies the permission bits
+        let copied = fs::create_dir_all(dest.parent().unwrap()).and_then(|_| fs::copy(src, dest));
+        if let Err(why) = copied {
+            log(
+                LogLevel::Error,
+                &format!(
+                    "Could not install {} to {}: {}",
+                    src.display(),
+                    dest.display(),
+                    why
+                ),
+            );
+            std::process::exit(1);
+        }
+    }
+    if !dry_run {
+        log(
+            LogLevel::Log,
+            &format!("Installed {} file(s) to {}", copies.len(), prefix.display()),
+        );
+    }
+}
+
+/// Quiet time after a change before rebuilding, so that a burst of saves triggers one build
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Builds the project, then rebuilds it each time a file in the src or include dirs of
+/// the targets or the config file changes, until interrupted with Ctrl-C
+/// Each build runs ruxgo again in a child process, so that a failed build doesn't end the watch
+/// # Arguments
+/// * `build_config` - The local build configuration, for the output dir that is not watched
+/// * `targets` - The targets whose dirs are watched
+/// * `build_args` - The arguments of the child ruxgo, e.g. `["-b", "--keep-going"]`
+pub fn watch(build_config: &BuildConfig, targets: &[TargetConfig], build_args: &[String]) {
+    let exe = std::env::current_exe().unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not find the ruxgo executable: {}", why),
+        );
+        std::process::exit(1);
+    });
+    let rebuild = || match Command::new(&exe).args(build_args).status() {
+        Ok(status) if !status.success() => {
+            log(LogLevel::Warn, "Build failed, waiting for changes...")
+        }
+        Ok(_) => log(LogLevel::Log, "Waiting for changes..."),
+        Err(why) => {
+            log(LogLevel::Error, &format!("Could not run ruxgo: {}", why));
+            std::process::exit(1);
+        }
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).unwrap_or_else(|why| {
+        log(
+            LogLevel::Error,
+            &format!("Could not watch for changes: {}", why),
+        );
+        std::process::exit(1);
+    });
+    let dirs: HashSet<&str> = targets
+        .iter()
+        .flat_map(|target| {
+            target
+                .src
+                .iter()
+                .filter(|_| target.typ != TargetType::HeaderOnly)
+                .chain(target.include_dir.iter())
+                .map(String::as_str)
+        })
+        .collect();
+    let watched = dirs
+        .iter()
+        .map(|dir| (*dir, RecursiveMode::Recursive))
+        .chain(
+            // The config read from stdin cannot change
+            Some((config_file(), RecursiveMode::NonRecursive)).filter(|(path, _)| *path != "-"),
+        );
+    // Watched by absolute path, so that the events can be matched against the output dir
+    let cwd = std::env::current_dir().unwrap_or_default();
+    for (path, mode) in watched {
+        if let Err(why) = watcher.watch(&cwd.join(path), mode) {
+            log(
+                LogLevel::Warn,
+                &format!("Could not watch {}: {}", path, why),
+            );
+        }
+    }
+
+    // Builds write into the output dir, which may be inside a watched dir and may not exist yet
+    let output_dir = cwd.join(&build_config.output_dir);
+    let is_relevant = |event: &notify::Event| {
+        !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|path| !path.starts_with(&output_dir))
+    };
+
+    rebuild();
+    loop {
+        match rx.recv() {
+            Ok(Ok(event)) if is_relevant(&event) => {}
+            Ok(Ok(_)) => continue,
+            Ok(Err(why)) => {
+                log(LogLevel::Warn, &format!("Watch error: {}", why));
+                continue;
+            }
+            Err(_) => return,
+        }
+        // Waits for the burst of changes to end
+        while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+        log(LogLevel::Log, "Change detected, rebuilding...");
+        rebuild();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an OS config on ruxmusl built from the given musl version
+    fn ruxmusl(version: &str) -> OSConfig {
+        OSConfig {
+            ulib: "ruxmusl".to_string(),
+            musl_version: version.to_string(),
+            ruxos_dir: "/src/ruxos".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn musl_version_drives_the_url_and_source_dir() {
+        assert_eq!(
+            musl_url("1.2.3"),
+            "https://musl.libc.org/releases/musl-1.2.3.tar.gz"
+        );
+        assert_eq!(
+            musl_url("1.2.5"),
+            "https://musl.libc.org/releases/musl-1.2.5.tar.gz"
+        );
+        assert_eq!(
+            musl_src_dir(&ruxmusl("1.2.5")),
+            "/src/ruxos/ulib/ruxmusl/musl-1.2.5"
+        );
+        // The tarball extracts to musl-<version>, the source dir is where it lands
+        let url = musl_url("1.2.5");
+        let tarball = url.rsplit('/').next().unwrap();
+        assert!(musl_src_dir(&ruxmusl("1.2.5")).ends_with(tarball.trim_end_matches(".tar.gz")));
+    }
+
+    #[test]
+    fn musl_tarball_is_extracted_into_the_dest_dir() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let configure = b"#!/bin/sh\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(configure.len() as u64);
+        header.set_mode(0o755);
+        builder
+            .append_data(&mut header, "musl-1.2.5/configure", &configure[..])
+            .unwrap();
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = std::env::temp_dir().join(format!("ruxgo-musl-{}", std::process::id()));
+        let dest_str = dest.join("ulib/ruxmusl").display().to_string();
+        extract_tar_gz(&tarball, &dest_str).unwrap();
+        let extracted = fs::read(dest.join("ulib/ruxmusl/musl-1.2.5/configure"));
+        // Not a tarball
+        let error = extract_tar_gz(b"not a tarball", &dest_str);
+        fs::remove_dir_all(&dest).unwrap();
+        assert_eq!(extracted.unwrap(), configure);
+        assert!(error.is_err());
+    }
+
+    /// Returns the output of `sh -c script`, standing in for a make run
+    fn sh(script: &str) -> std::io::Result<Output> {
+        Command::new("sh").args(["-c", script]).output()
+    }
+
+    #[test]
+    fn musl_make_runs_under_a_spinner() {
+        let mut runs = Vec::new();
+        let mut shown = None;
+        let made = make_ruxmusl(|spinner, args| {
+            assert!(!spinner.is_finished());
+            assert_eq!(spinner.message(), "Building musl...");
+            shown = Some(spinner.clone());
+            runs.push(args.join(" "));
+            sh("exit 0")
+        });
+        assert_eq!(made, Ok(()));
+        assert_eq!(runs, ["-j", "install"]);
+        // Cleared once make is done
+        assert!(shown.unwrap().is_finished());
+    }
+
+    #[test]
+    fn musl_make_failure_clears_the_spinner() {
+        let mut shown = None;
+        let mut runs = 0;
+        let made = make_ruxmusl(|spinner, _| {
+            shown = Some(spinner.clone());
+            runs += 1;
+            sh("echo 'cc: not found' >&2; exit 2")
+        });
+        assert_eq!(
+            made,
+            Err(
+                "\"make -j\": Command execution failed (exit status: 2): cc: not found".to_string()
+            )
+        );
+        // make install is not run after a failed make
+        assert_eq!(runs, 1);
+        assert!(shown.unwrap().is_finished());
+    }
+}
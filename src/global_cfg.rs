@@ -1,7 +1,11 @@
+use crate::utils::license;
 use crate::utils::log::{log, LogLevel};
+use crate::utils::suggest::closest_match;
+use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+#[derive(Debug, PartialEq)]
 enum ConfigParam {
     DefaultCompiler(String),
     DefaultLanguage(String),
@@ -32,49 +36,114 @@ pub struct GlobalConfig {
     license: String,
 }
 
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        GlobalConfig {
+            default_compiler: "gcc".to_string(),
+            default_language: "cpp".to_string(),
+            license: "NONE".to_string(),
+        }
+    }
+}
+
 impl GlobalConfig {
+    /// Returns the path of the global config file, in the config dir of the user's home
+    /// The home is looked up when ruxgo runs, an unset `HOME` is an error rather than a
+    /// fallback to another directory
+    pub fn path() -> Result<PathBuf, String> {
+        #[cfg(unix)]
+        if std::env::var_os("HOME").is_none_or(|home| home.is_empty()) {
+            return Err(
+                "HOME is not set, it is needed to find the global config of ruxgo".to_string(),
+            );
+        }
+        let project_dirs = ProjectDirs::from("com", "RuxosApps", "ruxos-c")
+            .ok_or("Could not find the home directory for the global config of ruxgo")?;
+        Ok(project_dirs.config_dir().join("config.toml"))
+    }
+
+    /// The parameters that can be read and set with `ruxgo config`
+    pub const PARAMETERS: [&'static str; 3] = ["default_compiler", "default_language", "license"];
+
+    /// Returns the stored value of a parameter, or None if the parameter does not exist
+    /// # Arguments
+    /// * `parameter` - One of `GlobalConfig::PARAMETERS`
+    pub fn get(&self, parameter: &str) -> Option<String> {
+        match parameter {
+            "default_compiler" => Some(self.get_default_compiler()),
+            "default_language" => Some(self.get_default_language()),
+            "license" => Some(self.get_license()),
+            _ => None,
+        }
+    }
+
+    /// Validates and stores a parameter, leaving the config file unchanged if the value is invalid
+    /// # Arguments
+    /// * `config` - The path to the global config file
+    /// * `parameter` - One of `GlobalConfig::PARAMETERS`
+    /// * `value` - The value to store, for `license` a path to a license file, an SPDX id or `NONE`
     pub fn set_defaults(config: &PathBuf, parameter: &str, value: &str) {
+        match GlobalConfig::validate(parameter, value) {
+            Ok(param) => set_config_param(param, config),
+            Err(msg) => {
+                log(LogLevel::Error, &msg);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Returns the parameter to store, or the error message if the parameter or value is invalid
+    /// # Arguments
+    /// * `parameter` - One of `GlobalConfig::PARAMETERS`
+    /// * `value` - The value to validate
+    fn validate(parameter: &str, value: &str) -> Result<ConfigParam, String> {
+        let invalid = |what: &str, valid: &str| {
+            Err(format!(
+                "Invalid {} \"{}\", expected {}. See `ruxgo config --help` for more info",
+                what, value, valid
+            ))
+        };
         match parameter {
             "default_compiler" => {
                 if value == "gcc" || value == "clang" {
-                    set_config_param(ConfigParam::DefaultCompiler(value.to_string()), config);
+                    Ok(ConfigParam::DefaultCompiler(value.to_string()))
                 } else {
-                    log(
-                        LogLevel::Error,
-                        "Invalid compiler. See `ruxgo config --help` for more info",
-                    );
-                    std::process::exit(1);
+                    invalid("compiler", "one of: gcc, clang")
                 }
             }
             "default_language" => {
                 if value == "c" || value == "cpp" {
-                    set_config_param(ConfigParam::DefaultLanguage(value.to_string()), config);
+                    Ok(ConfigParam::DefaultLanguage(value.to_string()))
                 } else {
-                    log(
-                        LogLevel::Error,
-                        "Invalid language. See `ruxgo config --help` for more info",
-                    );
-                    std::process::exit(1);
+                    invalid("language", "one of: c, cpp")
                 }
             }
             "license" => {
-                if std::path::Path::new(value).exists() {
-                    let value = std::fs::read_to_string(value).unwrap();
-                    set_config_param(ConfigParam::License(value), config);
+                if value == "NONE" {
+                    Ok(ConfigParam::License(value.to_string()))
+                } else if let Some(id) = license::spdx_id(value) {
+                    Ok(ConfigParam::License(id.to_string()))
+                } else if std::path::Path::new(value).is_file() {
+                    std::fs::read_to_string(value)
+                        .map(ConfigParam::License)
+                        .map_err(|why| format!("Could not read license file: {}", why))
                 } else {
-                    log(
-                        LogLevel::Error,
-                        "Invalid license file. See `ruxgo config --help` for more info",
-                    );
-                    std::process::exit(1);
+                    invalid(
+                        "license",
+                        &format!(
+                            "an existing file, NONE or one of: {}",
+                            license::spdx_ids().join(", ")
+                        ),
+                    )
                 }
             }
             _ => {
-                log(
-                    LogLevel::Error,
-                    "Invalid parameter. See `ruxgo config --help` for more info",
-                );
-                std::process::exit(1);
+                let mut msg = format!("Invalid parameter \"{}\"", parameter);
+                if let Some(suggestion) = closest_match(parameter, GlobalConfig::PARAMETERS) {
+                    msg.push_str(&format!(", did you mean \"{}\"?", suggestion));
+                }
+                msg.push_str(" See `ruxgo config --help` for more info");
+                Err(msg)
             }
         }
     }
@@ -112,3 +181,67 @@ impl GlobalConfig {
         self.license.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_compiler_is_gcc_or_clang() {
+        assert_eq!(
+            GlobalConfig::validate("default_compiler", "clang"),
+            Ok(ConfigParam::DefaultCompiler("clang".to_string()))
+        );
+        assert_eq!(
+            GlobalConfig::validate("default_compiler", "gcc"),
+            Ok(ConfigParam::DefaultCompiler("gcc".to_string()))
+        );
+        let err = GlobalConfig::validate("default_compiler", "msvc").unwrap_err();
+        assert!(err.starts_with("Invalid compiler \"msvc\", expected one of: gcc, clang"));
+    }
+
+    #[test]
+    fn default_language_is_c_or_cpp() {
+        assert_eq!(
+            GlobalConfig::validate("default_language", "c"),
+            Ok(ConfigParam::DefaultLanguage("c".to_string()))
+        );
+        assert_eq!(
+            GlobalConfig::validate("default_language", "cpp"),
+            Ok(ConfigParam::DefaultLanguage("cpp".to_string()))
+        );
+        let err = GlobalConfig::validate("default_language", "rust").unwrap_err();
+        assert!(err.starts_with("Invalid language \"rust\", expected one of: c, cpp"));
+    }
+
+    #[test]
+    fn license_is_none_an_spdx_id_or_a_file() {
+        assert_eq!(
+            GlobalConfig::validate("license", "NONE"),
+            Ok(ConfigParam::License("NONE".to_string()))
+        );
+        assert_eq!(
+            GlobalConfig::validate("license", "MIT"),
+            Ok(ConfigParam::License("MIT".to_string()))
+        );
+
+        let file = std::env::temp_dir().join(format!("ruxgo-license-{}", std::process::id()));
+        std::fs::write(&file, "My license\n").unwrap();
+        let param = GlobalConfig::validate("license", file.to_str().unwrap());
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(param, Ok(ConfigParam::License("My license\n".to_string())));
+
+        let err = GlobalConfig::validate("license", "/no/such/license").unwrap_err();
+        assert!(err.starts_with("Invalid license \"/no/such/license\", expected an existing file"));
+    }
+
+    #[test]
+    fn unknown_parameter_suggests_the_closest() {
+        let err = GlobalConfig::validate("default_compilr", "gcc").unwrap_err();
+        assert!(
+            err.contains("did you mean \"default_compiler\"?"),
+            "{}",
+            err
+        );
+    }
+}
@@ -20,6 +20,3 @@ pub mod packages;
 pub mod parser;
 /// Contains logger, config parser and environment config
 pub mod utils;
-
-#[macro_use]
-extern crate lazy_static;
@@ -1,13 +1,15 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use dialoguer::MultiSelect;
-use directories::ProjectDirs;
+use ruxgo::builder;
 use ruxgo::commands;
 use ruxgo::global_cfg::GlobalConfig;
 use ruxgo::packages;
-use ruxgo::parser::OSConfig;
-use ruxgo::utils::log::{log, LogLevel};
+use ruxgo::utils::log::{
+    log, set_log_format, set_log_level, set_log_timestamps, LogFormat, LogLevel,
+};
 use std::env;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -15,11 +17,11 @@ struct CLIArgs {
     /// Build your project
     #[arg(short, long)]
     build: bool,
-    /// Clean the obj and bin intermediates
-    #[arg(short, long)]
-    clean: bool,
-    /// Choose which parts to delete
-    #[arg(conflicts_with("clean"))]
+    /// Clean the obj and bin intermediates, prompting for the parts unless given (e.g. `obj,bins,os`)
+    #[arg(short, long, num_args(0..), value_delimiter(','), value_name = "PARTS")]
+    clean: Option<Vec<String>>,
+    /// Deprecated, the parts to clean given without `--clean`
+    #[arg(hide = true, conflicts_with("clean"), value_delimiter(','))]
     choices: Vec<String>,
     /// Run the executable
     #[arg(short, long)]
@@ -30,15 +32,57 @@ struct CLIArgs {
     /// Path argument to pass to switch to the specified directory
     #[arg(long, num_args(1))]
     path: Option<PathBuf>,
+    /// Print the command that would run the executable, e.g. the full QEMU command line,
+    /// instead of running it
+    #[arg(long, requires = "run")]
+    print_qemu_cmd: bool,
     /// Arguments to pass to the executable when running
     #[arg(long, num_args(1..), require_equals(true), value_delimiter(','))]
     bin_args: Option<Vec<String>>,
-    /// Generate compile_commands.json
-    #[arg(long)]
-    gen_cc: bool,
+    /// Generate compile_commands.json, in the project directory unless PATH is given
+    #[arg(long, value_name = "PATH", num_args(0..=1), require_equals(true))]
+    gen_cc: Option<Option<PathBuf>>,
     /// Generate .vscode/c_cpp_properties.json
     #[arg(long)]
     gen_vsc: bool,
+    /// Generate a .clangd file using compile_commands.json
+    #[arg(long)]
+    gen_clangd: bool,
+    /// Keep linking the other targets after one fails to link, and list the failed ones at the end
+    #[arg(long)]
+    keep_going: bool,
+    /// Log why each source is compiled and each target relinked
+    #[arg(long)]
+    explain: bool,
+    /// Keep rebuilding when the sources, headers or config file change, until Ctrl-C
+    #[arg(long, requires = "build", conflicts_with_all = ["run", "gen_cc", "gen_vsc", "gen_clangd"])]
+    watch: bool,
+    /// Config file to use instead of `config_linux.toml` or `config_win32.toml`,
+    /// `-` reads it from stdin
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Build or run only the specified target
+    #[arg(long, value_name = "TARGET")]
+    target: Option<String>,
+    /// Directory for all build outputs, overrides `output_dir` in the config file
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<String>,
+    /// Write the sources of each target and the headers they include as JSON, without compiling
+    #[arg(long, value_name = "FILE")]
+    emit_deps: Option<PathBuf>,
+    /// Print the config as resolved from the config file as JSON, e.g. the cross compiler
+    /// and the final OS features
+    #[arg(long)]
+    print_config: bool,
+    /// Output format of the log, overrides `RUXGO_LOG_FORMAT`
+    #[arg(long, global = true, value_parser = ["text", "json"])]
+    format: Option<String>,
+    /// Only print warnings and errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Prefix each log line with the seconds elapsed since the first one
+    #[arg(long, global = true)]
+    log_timestamps: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -54,6 +98,18 @@ enum Commands {
         #[clap(long, action)]
         /// Initialize a C++ project
         cpp: bool,
+        #[clap(long, action)]
+        /// Initialize the project in the current directory
+        here: bool,
+        #[clap(long, action)]
+        /// Populate an existing non-empty directory, only creating missing files
+        force: bool,
+        #[clap(long, action)]
+        /// Generate a config that builds the project for Ruxos and runs it on QEMU
+        os: bool,
+        #[clap(long, requires = "os", value_parser = ["x86_64", "riscv64", "aarch64"], default_value = "x86_64")]
+        /// Architecture of the QEMU platform used with --os
+        arch: String,
     },
     /// Package management
     #[clap(name = "pkg", arg_required_else_help = true)]
@@ -64,6 +120,15 @@ enum Commands {
         /// Pull a specific package from the remote repository
         #[clap(short, long, value_name = "PKG_NAME")]
         pull: Option<String>,
+        /// Directory to pull the package into with `--pull`, instead of `ruxgo_pkg`
+        #[arg(long, value_name = "DIR", requires = "pull")]
+        dest: Option<PathBuf>,
+        /// List the packages that have been pulled
+        #[arg(long)]
+        installed: bool,
+        /// Show the full information of a specific package
+        #[clap(short, long, value_name = "PKG_NAME")]
+        info: Option<String>,
         /// Run a specific app-bin
         #[clap(short, long, value_name = "APP_BIN")]
         run: Option<String>,
@@ -73,51 +138,208 @@ enum Commands {
         /// Clean a specific package
         #[clap(short, long, value_name = "PKG_NAME")]
         clean: Option<String>,
-        /// Clean all packages
+        /// Clean all packages, prompting for the parts unless given (e.g. `all` or `app-bin,cache`)
+        #[arg(long, num_args(0..), value_delimiter(','), value_name = "PARTS")]
+        clean_all: Option<Vec<String>>,
+        /// Fetch the package list for `--list` and `--update` even if the cache has not expired
         #[arg(long)]
-        clean_all: bool,
+        refresh: bool,
+        /// Only use the cached package list and fail instead of downloading anything,
+        /// also set by `RUXGO_PKG_FROZEN=1`
+        #[arg(long, conflicts_with = "refresh")]
+        frozen: bool,
+        /// Don't ask for confirmation when running a new or changed app-bin script with `--run`,
+        /// or removing everything with `--clean-all`
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Configuration settings
+    #[clap(arg_required_else_help = true)]
     Config {
+        /// Print the stored value of a parameter
+        #[arg(long, value_name = "PARAMETER", conflicts_with_all = ["list", "parameter"])]
+        get: Option<String>,
+        /// Print all parameters and their stored values
+        #[arg(long, conflicts_with = "parameter")]
+        list: bool,
         /// Parameter to set currently supported parameters:
         ///     - `default_compiler`: Sets the default compiler to use
         ///     - `default_language`: Sets the default language to use
-        ///     - `license`: Sets the license to use. Give the path to the license file
-        #[clap(verbatim_doc_comment)]
-        parameter: String,
+        ///     - `license`: Sets the license to use. Give the path to the license file or an SPDX id
+        #[clap(verbatim_doc_comment, requires = "value")]
+        parameter: Option<String>,
         /// Value to set the parameter to currently supported values:
         ///     - `compiler`: `gcc`, `clang` Uses g++ or clang++ respectively
         ///     - `language`: `c`, `cpp`
-        ///     - `license`: `path/to/license/file`
+        ///     - `license`: `path/to/license/file`, `MIT`, `Apache-2.0`, `GPL-3.0`, `BSD-3-Clause` or `NONE`
         #[clap(verbatim_doc_comment)]
-        value: String,
+        value: Option<String>,
+    },
+    /// Check the config file for problems without building
+    Check,
+    /// Format the source files of all targets with clang-format
+    Fmt {
+        /// Report the files that are not formatted without modifying them
+        #[arg(long)]
+        check: bool,
     },
+    /// Copy the built exes, libraries and headers to a prefix, using the build manifest
+    Install {
+        /// Directory to install into, exes go to `<PREFIX>/bin`, libraries to `<PREFIX>/lib`
+        /// and headers to `<PREFIX>/include`
+        #[arg(long, default_value = "/usr/local")]
+        prefix: PathBuf,
+        /// List the files that would be copied without copying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Build the test targets and run them, reporting which ones pass
+    Test {
+        /// Only run the tests whose name contains this
+        filter: Option<String>,
+    },
+    /// Run clang-tidy over the source files, using compile_commands.json
+    Lint {
+        /// Checks to enable or disable, passed to clang-tidy as `--checks`
+        #[arg(long, value_name = "CHECKS")]
+        checks: Option<String>,
+        /// Exit with an error if clang-tidy reports an error
+        #[arg(long)]
+        tidy_error: bool,
+    },
+    /// Generate the man pages of ruxgo and its subcommands
+    #[command(hide = true)]
+    Man {
+        /// Directory to write `ruxgo.1` and one `ruxgo-<subcommand>.1` page per subcommand into,
+        /// instead of printing all of them to stdout
+        dir: Option<PathBuf>,
+    },
+}
+
+/// Returns the build options given on the command line as arguments for another ruxgo run
+/// # Arguments
+/// * `args` - The command line arguments
+/// * `config` - The absolute path of the config file to pass on, if the run builds the same project
+fn build_options(args: &CLIArgs, config: Option<&Path>) -> Vec<String> {
+    let mut options = Vec::new();
+    if let Some(config) = config {
+        options.extend(["--config".to_string(), config.display().to_string()]);
+    }
+    if args.keep_going {
+        options.push("--keep-going".to_string());
+    }
+    if args.explain {
+        options.push("--explain".to_string());
+    }
+    if let Some(ref target) = args.target {
+        options.extend(["--target".to_string(), target.clone()]);
+    }
+    if let Some(ref output_dir) = args.output_dir {
+        options.extend(["--output-dir".to_string(), output_dir.clone()]);
+    }
+    if let Some(ref format) = args.format {
+        options.extend(["--format".to_string(), format.clone()]);
+    }
+    if args.quiet {
+        options.push("--quiet".to_string());
+    }
+    if args.log_timestamps {
+        options.push("--log-timestamps".to_string());
+    }
+    options
+}
+
+/// Renders the man page of ruxgo followed by the page of each subcommand
+/// # Arguments
+/// * `w` - The writer to render the roff output into
+fn render_man(w: &mut dyn Write) -> std::io::Result<()> {
+    let mut cmd = CLIArgs::command().disable_help_subcommand(true);
+    cmd.build();
+    clap_mangen::Man::new(cmd.clone()).render(w)?;
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        clap_mangen::Man::new(sub.clone()).render(w)?;
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    // Add global config
-    let project_dirs = ProjectDirs::from("com", "RuxosApps", "ruxos-c").unwrap();
-    let config_dir = project_dirs.config_dir();
-    if !config_dir.exists() {
-        std::fs::create_dir_all(config_dir).unwrap();
+    // Add global config, found from the home directory of the user running ruxgo
+    let config = GlobalConfig::path();
+    let global_config = match &config {
+        Ok(config) => {
+            if !config.exists() {
+                std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+                std::fs::write(config, toml::to_string(&GlobalConfig::default()).unwrap()).unwrap();
+            }
+            GlobalConfig::from_file(config)
+        }
+        // Only `ruxgo config` needs the file, the other commands use the default values
+        Err(_) => GlobalConfig::default(),
+    };
+
+    // Parse args
+    let mut args = CLIArgs::parse();
+
+    match args.format.as_deref() {
+        Some("json") => set_log_format(LogFormat::Json),
+        Some(_) => set_log_format(LogFormat::Text),
+        None => (),
     }
-    let config = config_dir.join("config.toml");
-    if !config.exists() {
-        std::fs::write(
-            &config,
-            r#"
-default_compiler = "gcc"
-default_language = "cpp"
-license = "NONE"
-"#,
-        )
-        .unwrap();
+    if args.quiet {
+        set_log_level(LogLevel::Warn);
+    }
+    if args.log_timestamps {
+        set_log_timestamps(true);
+    }
+    if args.explain {
+        builder::set_explain(true);
+    }
+    // The parts to clean used to be positional arguments
+    if !args.choices.is_empty() {
+        log(
+            LogLevel::Warn,
+            &format!(
+                "Giving the parts to clean without --clean is deprecated, use `--clean {}`",
+                args.choices.join(",")
+            ),
+        );
+        args.clean = Some(std::mem::take(&mut args.choices));
     }
-    let global_config = GlobalConfig::from_file(&config);
 
-    // Parse args
-    let args = CLIArgs::parse();
+    // Each build of --watch reads the config file again, stdin can only be read once
+    if args.watch
+        && args
+            .config
+            .as_ref()
+            .is_some_and(|config| config.as_os_str() == "-")
+    {
+        log(
+            LogLevel::Error,
+            "--watch can't read the config from stdin, give the path of a config file to --config",
+        );
+        std::process::exit(1);
+    }
+
+    // The config path is relative to where ruxgo was started, not to `--path`
+    let project_config = args.config.as_ref().map(|config| {
+        if config.as_os_str() == "-" {
+            config.clone()
+        } else {
+            std::path::absolute(config).unwrap_or_else(|_| config.clone())
+        }
+    });
+
+    // So is the path of compile_commands.json, which defaults to the project directory
+    let cc_path = args.gen_cc.as_ref().map(|path| match path {
+        Some(path) => std::path::absolute(path).unwrap_or_else(|_| path.clone()),
+        None => PathBuf::from("compile_commands.json"),
+    });
+
+    // And so is the path given to --emit-deps
+    if let Some(ref mut path) = args.emit_deps {
+        *path = std::path::absolute(&*path).unwrap_or_else(|_| path.clone());
+    }
 
     if let Some(ref path_buf) = args.path {
         if let Err(e) = env::set_current_dir(path_buf) {
@@ -125,82 +347,232 @@ license = "NONE"
         }
     }
 
+    // The paths in a config file are relative to its directory, which becomes the working directory
+    if let Some(ref config) = project_config {
+        if let Some(dir) = config.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if let Err(e) = env::set_current_dir(dir) {
+                log(
+                    LogLevel::Error,
+                    &format!(
+                        "Could not enter the directory of {}: {}",
+                        config.display(),
+                        e
+                    ),
+                );
+                std::process::exit(1);
+            }
+        }
+        commands::set_config_file(&config.to_string_lossy());
+    }
+
     if args.commands.is_some() {
-        match args.commands {
-            Some(Commands::Init { name, c, cpp }) => {
+        match args.commands.take() {
+            Some(Commands::Init {
+                name,
+                c,
+                cpp,
+                here,
+                force,
+                os,
+                arch,
+            }) => {
                 if c && cpp {
                     log(LogLevel::Error, "Only one of --c or --cpp can be specified");
                     std::process::exit(1);
                 }
-                if !c && !cpp {
-                    commands::init_project(&name, None, &global_config);
-                    std::process::exit(0);
-                }
-
-                if c {
-                    commands::init_project(&name, Some(true), &global_config);
-                } else {
-                    commands::init_project(&name, Some(false), &global_config);
-                }
+                let is_c = match (c, cpp) {
+                    (true, _) => Some(true),
+                    (_, true) => Some(false),
+                    _ => None,
+                };
+                let os_arch = if os { Some(arch.as_str()) } else { None };
+                commands::init_project(&name, is_c, &global_config, here, force, os_arch);
             }
             Some(Commands::Pkg {
                 list,
                 pull,
+                dest,
+                installed,
+                info,
                 run,
                 update,
                 clean,
                 clean_all,
+                refresh,
+                frozen,
+                yes,
             }) => {
+                if frozen {
+                    packages::set_frozen(true);
+                }
                 if list {
-                    packages::list_packages()
+                    packages::list_packages(refresh)
                         .await
-                        .expect("Failed to list packages");
+                        .unwrap_or_else(|err| {
+                            log(
+                                LogLevel::Error,
+                                &format!("Failed to list packages: {}", err),
+                            );
+                            std::process::exit(1);
+                        });
+                }
+                if installed {
+                    packages::list_installed().expect("Failed to list installed packages");
+                }
+                if let Some(pkg_name) = info {
+                    packages::package_info(&pkg_name)
+                        .await
+                        .unwrap_or_else(|err| {
+                            log(LogLevel::Error, &err.to_string());
+                            std::process::exit(1);
+                        });
                 }
                 if let Some(pkg_name) = pull {
-                    packages::pull_packages(&pkg_name)
+                    packages::pull_packages(&pkg_name, dest.as_deref())
                         .await
-                        .expect("Failed to pull package");
+                        .unwrap_or_else(|err| {
+                            log(LogLevel::Error, &format!("Failed to pull package: {}", err));
+                            std::process::exit(1);
+                        });
                 }
                 if let Some(app_name) = run {
-                    packages::run_app(&app_name).expect("Failed to run app-bin");
+                    packages::run_app(&app_name, yes).unwrap_or_else(|err| {
+                        log(LogLevel::Error, &format!("Failed to run app-bin: {}", err));
+                        std::process::exit(1);
+                    });
                 }
                 if let Some(pkg_name) = update {
-                    packages::update_package(&pkg_name)
+                    packages::update_package(&pkg_name, refresh)
                         .await
-                        .expect("Failed to update package");
+                        .unwrap_or_else(|err| {
+                            log(
+                                LogLevel::Error,
+                                &format!("Failed to update package: {}", err),
+                            );
+                            std::process::exit(1);
+                        });
                 }
                 if let Some(pkg_name) = clean {
                     packages::clean_package(&pkg_name)
                         .await
                         .expect("Failed to clean package");
                 }
-                if clean_all {
-                    let items = vec!["All", "App-bin", "App-src", "Kernel", "Cache"];
-                    let defaults = vec![false; items.len()];
-                    let choices = MultiSelect::new()
-                        .with_prompt("What parts do you want to clean?")
-                        .items(&items)
-                        .defaults(&defaults)
-                        .interact_opt()
-                        .unwrap_or(None)
-                        .unwrap_or_default()
-                        .iter()
-                        .map(|&index| String::from(items[index]))
-                        .collect();
+                if let Some(parts) = clean_all {
+                    let items = packages::CLEAN_ALL_ITEMS;
+                    let choices = if !parts.is_empty() {
+                        packages::parse_clean_all_choices(&parts).unwrap_or_else(|err| {
+                            log(LogLevel::Error, &err.to_string());
+                            std::process::exit(1);
+                        })
+                    } else if std::io::stdin().is_terminal() {
+                        let defaults = vec![false; items.len()];
+                        MultiSelect::new()
+                            .with_prompt("What parts do you want to clean?")
+                            .items(&items)
+                            .defaults(&defaults)
+                            .interact_opt()
+                            .unwrap_or(None)
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|&index| String::from(items[index]))
+                            .collect()
+                    } else {
+                        log(
+                            LogLevel::Error,
+                            "No parts to clean given and stdin is not a terminal, use e.g. `--clean-all all`",
+                        );
+                        std::process::exit(1);
+                    };
                     log(LogLevel::Log, "Cleaning packages...");
-                    packages::clean_all_packages(choices)
+                    packages::clean_all_packages(choices, yes)
                         .await
-                        .expect("Failed to clean choice packages");
+                        .unwrap_or_else(|err| {
+                            log(
+                                LogLevel::Error,
+                                &format!("Failed to clean packages: {}", err),
+                            );
+                            std::process::exit(1);
+                        });
                 }
             }
-            Some(Commands::Config { parameter, value }) => {
-                let parameter = parameter.as_str();
-                let value = value.as_str();
-                GlobalConfig::set_defaults(&config, parameter, value);
-                log(
-                    LogLevel::Log,
-                    format!("Setting {} to {}", parameter, value).as_str(),
-                );
+            Some(Commands::Config {
+                get,
+                list,
+                parameter,
+                value,
+            }) => {
+                let config = config.unwrap_or_else(|why| {
+                    log(LogLevel::Error, &why);
+                    std::process::exit(1);
+                });
+                if list {
+                    for parameter in GlobalConfig::PARAMETERS {
+                        let value = global_config.get(parameter).unwrap_or_default();
+                        // A license holds the whole license text, only show its first line
+                        let value = value.lines().next().unwrap_or_default();
+                        println!("{} = {}", parameter, value);
+                    }
+                } else if let Some(parameter) = get {
+                    match global_config.get(&parameter) {
+                        Some(value) => println!("{}", value),
+                        None => {
+                            log(
+                                LogLevel::Error,
+                                "Invalid parameter. See `ruxgo config --help` for more info",
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                } else if let (Some(parameter), Some(value)) = (parameter, value) {
+                    let parameter = parameter.as_str();
+                    let value = value.as_str();
+                    GlobalConfig::set_defaults(&config, parameter, value);
+                    log(
+                        LogLevel::Log,
+                        format!("Setting {} to {}", parameter, value).as_str(),
+                    );
+                }
+                std::process::exit(0);
+            }
+            Some(Commands::Check) => {
+                commands::check();
+                std::process::exit(0);
+            }
+            Some(Commands::Fmt { check }) => {
+                commands::fmt(check);
+                std::process::exit(0);
+            }
+            Some(Commands::Install { prefix, dry_run }) => {
+                commands::install(&prefix, dry_run, args.output_dir.as_deref());
+                std::process::exit(0);
+            }
+            Some(Commands::Test { filter }) => {
+                commands::test(filter.as_deref(), args.output_dir.as_deref());
+                std::process::exit(0);
+            }
+            Some(Commands::Lint { checks, tidy_error }) => {
+                commands::lint(checks.as_deref(), tidy_error);
+                std::process::exit(0);
+            }
+            Some(Commands::Man { dir }) => {
+                let result = match dir {
+                    Some(dir) => std::fs::create_dir_all(&dir).and_then(|_| {
+                        clap_mangen::generate_to(CLIArgs::command(), &dir)?;
+                        log(
+                            LogLevel::Info,
+                            &format!("Man pages written to {}", dir.display()),
+                        );
+                        Ok(())
+                    }),
+                    None => render_man(&mut std::io::stdout().lock()),
+                };
+                if let Err(e) = result {
+                    log(
+                        LogLevel::Error,
+                        &format!("Could not generate the man pages: {}", e),
+                    );
+                    std::process::exit(1);
+                }
                 std::process::exit(0);
             }
             None => {
@@ -210,10 +582,65 @@ license = "NONE"
         }
     }
 
-    let mut gen_cc = false;
-    if args.gen_cc {
-        gen_cc = true;
-        commands::pre_gen_cc();
+    // Parses the config file, applying the command line overrides
+    let parse_config = || {
+        let (mut build_config, os_config, targets) = commands::parse_config(args.target.as_deref());
+        if let Some(ref output_dir) = args.output_dir {
+            build_config.output_dir = output_dir.clone();
+            commands::check_output_dir(output_dir, &targets);
+        }
+        (build_config, os_config, targets)
+    };
+
+    // A workspace config lists member projects, each built or cleaned by its own ruxgo run
+    if let Some(members) = commands::workspace_members() {
+        if args.run || args.watch || args.print_config || args.emit_deps.is_some() {
+            log(
+                LogLevel::Error,
+                "The config is a workspace, only --build and --clean can be run in its members",
+            );
+            std::process::exit(1);
+        }
+        let mut member_args = Vec::new();
+        match args.clean {
+            Some(ref parts) if !parts.is_empty() => {
+                member_args.push(format!("--clean={}", parts.join(",")))
+            }
+            Some(_) => member_args.push("--clean".to_string()),
+            None => (),
+        }
+        if args.build {
+            member_args.push("-b".to_string());
+        }
+        // Members given a path share one compile_commands.json, merged by file
+        if let Some(ref cc_path) = cc_path {
+            member_args.push(match args.gen_cc {
+                Some(Some(_)) => format!("--gen-cc={}", cc_path.display()),
+                _ => "--gen-cc".to_string(),
+            });
+        }
+        for (enabled, flag) in [
+            (args.gen_vsc, "--gen-vsc"),
+            (args.gen_clangd, "--gen-clangd"),
+        ] {
+            if enabled {
+                member_args.push(flag.to_string());
+            }
+        }
+        // Members build with their own config files
+        member_args.extend(build_options(&args, None));
+        commands::workspace(&members, &member_args);
+    }
+
+    if args.print_config {
+        let (build_config, os_config, targets) = parse_config();
+        commands::print_config(&build_config, &os_config, &targets);
+        std::process::exit(0);
+    }
+
+    let gen_cc = cc_path.as_deref();
+    if let Some(cc_path) = gen_cc {
+        commands::pre_gen_cc(cc_path);
     }
 
     let mut gen_vsc = false;
@@ -222,47 +649,132 @@ license = "NONE"
         commands::pre_gen_vsc();
     }
 
-    // If clean flag is provided, prompt user for choices
-    if args.clean {
-        let (_, os_config, targets) = commands::parse_config();
-        let mut items = vec!["All", "App_bins", "Obj"];
-        if os_config != OSConfig::default() {
-            items.push("OS");
-            if !os_config.ulib.is_empty() {
-                items.push("Ulib");
+    // If clean flag is provided, clean the given parts or prompt user for choices
+    if let Some(ref parts) = args.clean {
+        let (build_config, os_config, targets) = parse_config();
+        // A selected target is cleaned on its own, instead of the parts of all targets
+        if let Some(ref name) = args.target {
+            if !parts.is_empty() {
+                log(
+                    LogLevel::Error,
+                    "--target cleans all the outputs of the target, it cannot be combined with parts to clean",
+                );
+                std::process::exit(1);
             }
+            log(LogLevel::Log, &format!("Cleaning target: {}", name));
+            commands::clean_target(&build_config, commands::find_target(&targets, name));
+        } else {
+            let items = commands::clean_items(&os_config);
+            let choices = if !parts.is_empty() {
+                commands::parse_clean_choices(parts, &items)
+            } else if std::io::stdin().is_terminal() {
+                let defaults = vec![false; items.len()];
+                MultiSelect::new()
+                    .with_prompt("What parts do you want to clean?")
+                    .items(&items)
+                    .defaults(&defaults)
+                    .interact_opt()
+                    .unwrap_or(None)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|&index| String::from(items[index]))
+                    .collect()
+            } else {
+                log(
+                    LogLevel::Error,
+                    "No parts to clean given and stdin is not a terminal, use e.g. `--clean obj,bins`",
+                );
+                std::process::exit(1);
+            };
+
+            log(LogLevel::Log, "Cleaning...");
+            commands::clean(&build_config, &targets, &os_config, choices);
         }
-        let defaults = vec![false; items.len()];
-        let choices = MultiSelect::new()
-            .with_prompt("What parts do you want to clean?")
-            .items(&items)
-            .defaults(&defaults)
-            .interact_opt()
-            .unwrap_or(None)
-            .unwrap_or_default()
-            .iter()
-            .map(|&index| String::from(items[index]))
-            .collect();
+    }
+
+    if let Some(ref path) = args.emit_deps {
+        let (_, _, targets) = parse_config();
+        commands::emit_deps(&targets, path);
+    }
 
-        log(LogLevel::Log, "Cleaning...");
-        commands::clean(&targets, &os_config, choices);
+    if args.build && args.watch {
+        let (build_config, _, targets) = parse_config();
+        // The build options are passed on to the ruxgo run for each build
+        let mut build_args = vec!["-b".to_string()];
+        build_args.extend(build_options(&args, project_config.as_deref()));
+        log(LogLevel::Log, "Watching for changes, press Ctrl-C to stop");
+        commands::watch(&build_config, &targets, &build_args);
+        return;
     }
 
     if args.build {
-        let (build_config, os_config, targets) = commands::parse_config();
+        let (build_config, os_config, targets) = parse_config();
         log(LogLevel::Log, "Building...");
-        commands::build(&build_config, &targets, &os_config, gen_cc, gen_vsc);
+        commands::build(
+            &build_config,
+            &targets,
+            &os_config,
+            gen_cc,
+            gen_vsc,
+            args.gen_clangd,
+            args.keep_going,
+            &args.target.as_deref().into_iter().collect::<Vec<_>>(),
+        );
     }
 
     if args.run {
-        let (build_config, os_config, targets) = commands::parse_config();
+        let (build_config, os_config, targets) = parse_config();
         let bin_args: Option<Vec<&str>> = args
             .bin_args
             .as_ref()
             .map(|x| x.iter().map(|x| x.as_str()).collect());
 
-        log(LogLevel::Log, "Running...");
-        let exe_target = targets.iter().find(|x| x.typ == "exe").unwrap();
-        commands::run(bin_args, &build_config, &os_config, exe_target, &targets);
+        let exe_target = commands::find_exe_target(&targets, args.target.as_deref());
+        if !args.print_qemu_cmd {
+            log(LogLevel::Log, "Running...");
+        }
+        commands::run(
+            bin_args,
+            &build_config,
+            &os_config,
+            exe_target,
+            &targets,
+            args.print_qemu_cmd,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn man_page_covers_flags_and_subcommands() {
+        let mut buf = Vec::new();
+        render_man(&mut buf).unwrap();
+        let man = String::from_utf8(buf).unwrap();
+        assert!(man.contains("ruxgo"));
+        assert!(man.contains("Build your project"));
+        assert!(man.contains(".TH ruxgo-pkg"));
+    }
+
+    #[test]
+    fn config_from_custom_path() {
+        let dir = env::temp_dir().join(format!("ruxgo-config-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("configs")).unwrap();
+        let config = dir.join("configs/custom.toml");
+        std::fs::write(
+            &config,
+            "[build]\ncompiler = \"gcc\"\n\n[[targets]]\nname = \"custom\"\nsrc = \"src\"\ninclude_dir = \"src\"\ntype = \"exe\"\ncflags = \"\"\nlinker_flags = \"\"\ndeps = []\n",
+        )
+        .unwrap();
+
+        let args =
+            CLIArgs::try_parse_from(["ruxgo", "-b", "--config", config.to_str().unwrap()]).unwrap();
+        commands::set_config_file(&args.config.unwrap().to_string_lossy());
+        let (_, _, targets) = commands::parse_config(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "custom");
     }
 }
@@ -1,23 +1,41 @@
 //! This module contains code related to package management.
 
+use crate::hasher::Hasher;
 use crate::utils::log::{log, LogLevel};
+use crate::utils::suggest::closest_match;
 use bytes::Bytes;
 use colored::Colorize;
+use dialoguer::Confirm;
 use reqwest;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::error::Error;
+use std::io::IsTerminal;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
 use std::{fmt, fs};
 use toml;
 
+/// Where the package list and the app-bins are downloaded from, unless `RUXGO_PKG_URL` is set
 static PACKAGES_URL: &str =
-    "https://mirror.ghproxy.com/https://raw.githubusercontent.com/Ybeichen/ruxos-pkgs/master/";
+    "https://mirror.ghproxy.com/https://raw.githubusercontent.com/Ybeichen/ruxos-pkgs/master";
 static SYSWONDER_URL: &str = "https://mirror.ghproxy.com/https://github.com/syswonder";
 static PKG_DIR: &str = "ruxgo_pkg";
 static BIN_DIR: &str = "ruxgo_pkg/app-bin";
 static CACHE_DIR: &str = "ruxgo_pkg/cache";
+/// Record of the pulled packages
+static INSTALLED_FILE: &str = "ruxgo_pkg/installed.toml";
+/// Versions, commits and checksums the packages are pinned to
+static LOCK_FILE: &str = "ruxgo.lock";
+/// Hashes of the app-bin scripts the user agreed to run
+static APPROVED_SCRIPTS: &str = "ruxgo_pkg/app-bin/approved_scripts.hash";
+/// Seconds the package list cache is reused before refreshing, unless `RUXGO_PKG_CACHE_TTL` is set
+const DEFAULT_CACHE_TTL: u64 = 3600;
+/// Set by `--frozen`, see `frozen`
+static FROZEN: AtomicBool = AtomicBool::new(false);
 
 /// Enum describing the Package type
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -67,6 +85,137 @@ struct PackageList {
     packages: Vec<PackageInfo>,
 }
 
+/// Struct descibing a pulled package
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct InstalledPackage {
+    name: String,
+    typ: PackageType,
+    version: String,
+    /// RFC 3339 time of the pull, in UTC
+    installed_at: String,
+}
+
+/// Struct descibing a package pinned in the lockfile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct LockedPackage {
+    name: String,
+    typ: PackageType,
+    version: String,
+    branch: String,
+    /// Commit the git repository of an app-src or kernel was checked out at
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    commit: String,
+    /// sha256 of the binary of an app-bin
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    sha256: String,
+}
+
+/// Struct descibing the lockfile
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct LockFile {
+    packages: Vec<LockedPackage>,
+}
+
+impl LockFile {
+    /// Loads the lockfile, empty if nothing has been pulled yet
+    fn load() -> Result<Self, Box<dyn Error>> {
+        if !Path::new(LOCK_FILE).exists() {
+            return Ok(LockFile::default());
+        }
+        let contents = fs::read_to_string(LOCK_FILE)?;
+        toml::from_str(&contents).map_err(|err| {
+            log(
+                LogLevel::Error,
+                &format!("Failed to parse {}: {}", LOCK_FILE, err),
+            );
+            Box::new(err) as Box<dyn Error>
+        })
+    }
+
+    /// Pins a package, replacing a previous entry of the same name
+    fn add(locked: LockedPackage) -> Result<(), Box<dyn Error>> {
+        let mut lock = LockFile::load()?;
+        lock.packages.retain(|pkg| pkg.name != locked.name);
+        lock.packages.push(locked);
+        fs::write(LOCK_FILE, toml::to_string(&lock)?)?;
+        Ok(())
+    }
+
+    /// Unpins a package, if it is pinned
+    fn remove(pkg_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut lock = LockFile::load()?;
+        if lock.get(pkg_name).is_none() {
+            return Ok(());
+        }
+        lock.packages.retain(|pkg| pkg.name != pkg_name);
+        fs::write(LOCK_FILE, toml::to_string(&lock)?)?;
+        Ok(())
+    }
+
+    /// Returns the entry of the given package, if it is pinned
+    fn get(&self, pkg_name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|pkg| pkg.name == pkg_name)
+    }
+}
+
+/// Struct descibing the installed-manifest
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct InstalledList {
+    packages: Vec<InstalledPackage>,
+}
+
+impl InstalledList {
+    /// Loads the installed-manifest, empty if nothing has been pulled yet
+    fn load() -> Result<Self, Box<dyn Error>> {
+        if !Path::new(INSTALLED_FILE).exists() {
+            return Ok(InstalledList::default());
+        }
+        let contents = fs::read_to_string(INSTALLED_FILE)?;
+        toml::from_str(&contents).map_err(|err| {
+            log(
+                LogLevel::Error,
+                &format!("Failed to parse {}: {}", INSTALLED_FILE, err),
+            );
+            Box::new(err) as Box<dyn Error>
+        })
+    }
+
+    /// Writes the installed-manifest, creating the package directory if needed
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(PKG_DIR)?;
+        fs::write(INSTALLED_FILE, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Records a package as pulled now, replacing a previous entry of the same name
+    fn add(pkg_info: &PackageInfo) -> Result<(), Box<dyn Error>> {
+        let mut installed = InstalledList::load()?;
+        installed.packages.retain(|pkg| pkg.name != pkg_info.name);
+        installed.packages.push(InstalledPackage {
+            name: pkg_info.name.clone(),
+            typ: pkg_info.typ.clone(),
+            version: pkg_info.version.clone(),
+            installed_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        });
+        installed.save()
+    }
+
+    /// Removes the entries matching `pred` from the manifest, if it exists
+    fn remove(pred: impl Fn(&InstalledPackage) -> bool) -> Result<(), Box<dyn Error>> {
+        if !Path::new(INSTALLED_FILE).exists() {
+            return Ok(());
+        }
+        let mut installed = InstalledList::load()?;
+        installed.packages.retain(|pkg| !pred(pkg));
+        installed.save()
+    }
+
+    /// Returns the entry of the given package, if it is installed
+    fn get(&self, pkg_name: &str) -> Option<&InstalledPackage> {
+        self.packages.iter().find(|pkg| pkg.name == pkg_name)
+    }
+}
+
 /// Processes the HTTP GET request and read the response text
 async fn fetch_url(url: &str) -> Result<String, Box<dyn Error>> {
     let resp = reqwest::get(url).await.map_err(|err| {
@@ -104,8 +253,10 @@ async fn fetch_binary(url: &str) -> Result<Bytes, Box<dyn Error>> {
 }
 
 /// Lists the packages information in the hosting server
-pub async fn list_packages() -> Result<(), Box<dyn Error>> {
-    let pkgs = load_or_refresh_packages(true).await?;
+/// # Arguments
+/// * `refresh` - Fetch the package list even if the cache has not expired
+pub async fn list_packages(refresh: bool) -> Result<(), Box<dyn Error>> {
+    let pkgs = load_or_refresh_packages(true, refresh).await?;
 
     // print the information of each package
     println!("{:-<1$}", "", 97);
@@ -129,23 +280,117 @@ pub async fn list_packages() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Returns the package with the given name, suggesting a close name if there is none
+fn find_package<'a>(
+    pkgs: &'a [PackageInfo],
+    pkg_name: &str,
+) -> Result<&'a PackageInfo, Box<dyn Error>> {
+    pkgs.iter().find(|pkg| pkg.name == pkg_name).ok_or_else(|| {
+        let mut msg = format!("Package '{}' not found", pkg_name);
+        if let Some(suggestion) = closest_match(pkg_name, pkgs.iter().map(|pkg| pkg.name.as_str()))
+        {
+            msg.push_str(&format!(", did you mean '{}'?", suggestion));
+        }
+        msg.into()
+    })
+}
+
+/// Returns the URL the package is pulled from
+fn source_url(pkg_info: &PackageInfo) -> String {
+    match pkg_info.typ {
+        PackageType::AppBin => format!("{}/{}", packages_url(), pkg_info.name),
+        _ => format!("{}/{}", SYSWONDER_URL, pkg_info.name),
+    }
+}
+
+/// Prints the full information of the specified package
+/// # Arguments
+/// * `pkg_name` - The name of the package
+pub async fn package_info(pkg_name: &str) -> Result<(), Box<dyn Error>> {
+    let pkgs = load_or_refresh_packages(false, false).await?;
+    let pkg_info = find_package(&pkgs, pkg_name)?;
+
+    println!("{:<13} {}", "Name:".bold(), pkg_info.name);
+    println!(
+        "{:<13} {}",
+        "Type:".bold(),
+        pkg_info.typ.to_string().trim_end()
+    );
+    println!("{:<13} {}", "Branch:".bold(), pkg_info.branch);
+    println!("{:<13} {}", "Version:".bold(), pkg_info.version);
+    println!("{:<13} {}", "Description:".bold(), pkg_info.description);
+    println!("{:<13} {}", "Source:".bold(), source_url(pkg_info));
+    match InstalledList::load()?.get(pkg_name) {
+        Some(installed) => println!(
+            "{:<13} yes, version {} at {}",
+            "Installed:".bold(),
+            installed.version,
+            installed.installed_at
+        ),
+        None => println!("{:<13} no", "Installed:".bold()),
+    }
+
+    Ok(())
+}
+
+/// Creates the destination directory if needed and checks that files can be written to it
+fn prepare_dest(dest: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dest)
+        .map_err(|err| format!("Failed to create destination '{}': {}", dest.display(), err))?;
+    let probe = dest.join(".ruxgo_write_test");
+    fs::write(&probe, b"")
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|err| format!("Destination '{}' is not writable: {}", dest.display(), err))?;
+    Ok(())
+}
+
 /// Pulls the specified package
-pub async fn pull_packages(pkg_name: &str) -> Result<(), Box<dyn Error>> {
+/// # Arguments
+/// * `pkg_name` - The name of the package
+/// * `dest` - The directory to pull into instead of `ruxgo_pkg/app-bin` for app-bin
+///   and `ruxgo_pkg` for the other types, such packages are not recorded as installed
+pub async fn pull_packages(pkg_name: &str, dest: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    ensure_not_frozen(&format!("pull package '{}'", pkg_name))?;
     // load or refresh packages
-    let pkgs = load_or_refresh_packages(false).await?;
-
-    // find the specified package
-    let pkg_info = pkgs
-        .iter()
-        .find(|pkg| pkg.name == pkg_name)
-        .ok_or_else(|| format!("Package '{}' not found", pkg_name))?;
+    let pkgs = load_or_refresh_packages(false, false).await?;
+    let pkg_info = find_package(&pkgs, pkg_name)?;
+    let lock = LockFile::load()?;
+    let locked = lock.get(pkg_name);
+    if let Some(locked) = locked {
+        if locked.typ != pkg_info.typ || locked.version != pkg_info.version {
+            return Err(format!(
+                "Package '{}' is locked to version {} in {}, but the package list has version {}, \
+                 use `ruxgo pkg --update {}` to update it",
+                pkg_name, locked.version, LOCK_FILE, pkg_info.version, pkg_name
+            )
+            .into());
+        }
+    }
+    let mut pinned = LockedPackage {
+        name: pkg_info.name.clone(),
+        typ: pkg_info.typ.clone(),
+        version: pkg_info.version.clone(),
+        branch: pkg_info.branch.clone(),
+        commit: String::new(),
+        sha256: String::new(),
+    };
+    if let Some(dest) = dest {
+        prepare_dest(dest)?;
+    }
 
     // handle different types of packages
     match pkg_info.typ {
         PackageType::AppBin => {
-            let url = format!("{}/{}", PACKAGES_URL, pkg_name);
-            let bytes = fetch_binary(&url).await?;
-            let bin_dir = PathBuf::from(BIN_DIR);
+            let bytes = fetch_binary(&source_url(pkg_info)).await?;
+            pinned.sha256 = format!("{:x}", Sha256::digest(&bytes));
+            if let Some(locked) = locked.filter(|locked| locked.sha256 != pinned.sha256) {
+                return Err(format!(
+                    "Checksum of package '{}' does not match {}: expected {}, got {}",
+                    pkg_name, LOCK_FILE, locked.sha256, pinned.sha256
+                )
+                .into());
+            }
+            let bin_dir = dest.map_or_else(|| PathBuf::from(BIN_DIR), Path::to_path_buf);
             if !bin_dir.exists() {
                 fs::create_dir_all(&bin_dir)?;
             }
@@ -156,7 +401,7 @@ pub async fn pull_packages(pkg_name: &str) -> Result<(), Box<dyn Error>> {
                 &format!("Package '{}' pulled successfully!", pkg_name),
             );
             // pull its script
-            pull_script(pkg_name).await.map_err(|err| {
+            pull_script(pkg_name, &bin_dir).await.map_err(|err| {
                 log(
                     LogLevel::Error,
                     &format!("Failed to pull script for '{}': {}", pkg_name, err),
@@ -166,19 +411,21 @@ pub async fn pull_packages(pkg_name: &str) -> Result<(), Box<dyn Error>> {
         }
         PackageType::AppSrc | PackageType::Kernel => {
             // pull the package from github
-            let url = format!("{}/{}", SYSWONDER_URL, pkg_name);
-            let dir = PathBuf::from(PKG_DIR);
+            let url = source_url(pkg_info);
+            let dir = dest.map_or_else(|| PathBuf::from(PKG_DIR), Path::to_path_buf);
             if !dir.exists() {
                 fs::create_dir_all(&dir)?;
             }
+            let repo = dir.join(pkg_name);
             let status = Command::new("git")
                 .arg("clone")
                 .arg(&url)
-                .arg(&dir.join(pkg_name))
+                .arg(&repo)
                 .status();
 
             if let Ok(status) = status {
                 if status.success() {
+                    pinned.commit = checkout_locked(&repo, locked)?;
                     log(
                         LogLevel::Log,
                         &format!("Package '{}' pulled successfully!", pkg_name),
@@ -196,15 +443,92 @@ pub async fn pull_packages(pkg_name: &str) -> Result<(), Box<dyn Error>> {
             return Err(format!("Unknown package type: {}", pkg_info.typ).into())
         }
     }
+    if dest.is_none() {
+        InstalledList::add(pkg_info)?;
+    }
+    LockFile::add(pinned)?;
+
+    Ok(())
+}
+
+/// Checks out the commit a cloned package is locked to, returns the commit it is at
+/// # Arguments
+/// * `repo` - The cloned repository
+/// * `locked` - The lockfile entry of the package, if it is pinned
+fn checkout_locked(repo: &Path, locked: Option<&LockedPackage>) -> Result<String, Box<dyn Error>> {
+    if let Some(commit) = locked
+        .map(|locked| &locked.commit)
+        .filter(|c| !c.is_empty())
+    {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["checkout", "--quiet", commit])
+            .status()?;
+        if !status.success() {
+            // Don't leave a clone at another commit behind
+            let _ = fs::remove_dir_all(repo);
+            return Err(format!(
+                "Could not check out commit {} of {} locked in {}",
+                commit,
+                repo.display(),
+                LOCK_FILE
+            )
+            .into());
+        }
+        return Ok(commit.clone());
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "HEAD"])
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("Could not get the commit of {}", repo.display()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Lists the packages recorded as pulled
+pub fn list_installed() -> Result<(), Box<dyn Error>> {
+    let installed = InstalledList::load()?;
+    if installed.packages.is_empty() {
+        log(LogLevel::Log, "No packages installed");
+        return Ok(());
+    }
+
+    println!("{:-<1$}", "", 87);
+    println!(
+        "{:<10} {:<30} {:<22} {:<25}",
+        "TYPE".bold(),
+        "NAME".bold(),
+        "VERSION".bold(),
+        "INSTALLED".bold()
+    );
+    println!("{:-<1$}", "", 87);
+    for pkg in installed.packages {
+        println!(
+            "{:<10} {:<30} {:<22} {:<25}",
+            pkg.typ, pkg.name, pkg.version, pkg.installed_at
+        );
+    }
+    println!("{:-<1$}", "", 87);
 
     Ok(())
 }
 
 /// Updates the specified package
-pub async fn update_package(pkg_name: &str) -> Result<(), Box<dyn Error>> {
-    load_or_refresh_packages(true).await?;
+/// # Arguments
+/// * `pkg_name` - The name of the package
+/// * `refresh` - Fetch the package list even if the cache has not expired
+pub async fn update_package(pkg_name: &str, refresh: bool) -> Result<(), Box<dyn Error>> {
+    // Checked first, the package is removed before it is pulled again
+    ensure_not_frozen(&format!("update package '{}'", pkg_name))?;
+    load_or_refresh_packages(true, refresh).await?;
     clean_package(pkg_name).await?;
-    pull_packages(pkg_name).await?;
+    // The package is pinned again to what is pulled now
+    LockFile::remove(pkg_name)?;
+    pull_packages(pkg_name, None).await?;
     log(
         LogLevel::Log,
         &format!("Package '{}' updated successfully!", pkg_name),
@@ -215,11 +539,8 @@ pub async fn update_package(pkg_name: &str) -> Result<(), Box<dyn Error>> {
 
 /// Cleans the specified package
 pub async fn clean_package(pkg_name: &str) -> Result<(), Box<dyn Error>> {
-    let pkgs = load_or_refresh_packages(false).await?;
-    let pkg_info = pkgs
-        .iter()
-        .find(|pkg| pkg.name == pkg_name)
-        .ok_or_else(|| format!("Package '{}' not found", pkg_name))?;
+    let pkgs = load_or_refresh_packages(false, false).await?;
+    let pkg_info = find_package(&pkgs, pkg_name)?;
     match pkg_info.typ {
         PackageType::AppBin => {
             let bin_path = PathBuf::from(BIN_DIR).join(pkg_name);
@@ -263,32 +584,71 @@ pub async fn clean_package(pkg_name: &str) -> Result<(), Box<dyn Error>> {
             return Err(format!("Unknown package type: {}", pkg_info.typ).into())
         }
     }
+    InstalledList::remove(|pkg| pkg.name == pkg_name)?;
 
     Ok(())
 }
 
+/// The parts of the package directory `pkg --clean-all` can remove
+pub static CLEAN_ALL_ITEMS: [&str; 5] = ["All", "App-bin", "App-src", "Kernel", "Cache"];
+
+/// Maps the part names given on the command line to `CLEAN_ALL_ITEMS`, ignoring case
+/// # Arguments
+/// * `names` - The parts given to `--clean-all`, e.g. `all` or `app-bin,cache`
+pub fn parse_clean_all_choices(names: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    names
+        .iter()
+        .map(|name| {
+            if let Some(item) = CLEAN_ALL_ITEMS
+                .iter()
+                .find(|item| item.eq_ignore_ascii_case(name))
+            {
+                return Ok(item.to_string());
+            }
+            let valid: Vec<String> = CLEAN_ALL_ITEMS.iter().map(|i| i.to_lowercase()).collect();
+            let mut msg = format!("Unknown part to clean '{}'", name);
+            match closest_match(name, valid.iter().map(|v| v.as_str())) {
+                Some(suggestion) => msg.push_str(&format!(", did you mean '{}'?", suggestion)),
+                None => msg.push('.'),
+            }
+            msg.push_str(&format!(" Valid parts are: {}", valid.join(", ")));
+            Err(msg.into())
+        })
+        .collect()
+}
+
 /// Cleans all packages
 /// # Arguments
 /// * `choices` - A vector of choices to select which components to delete
-pub async fn clean_all_packages(choices: Vec<String>) -> Result<(), Box<dyn Error>> {
+/// * `yes` - Remove everything for the "All" choice without asking
+pub async fn clean_all_packages(choices: Vec<String>, yes: bool) -> Result<(), Box<dyn Error>> {
+    // "All" covers every other choice, the whole tree is removed once
+    if choices.iter().any(|choice| choice == "All") {
+        let root_dir_path = Path::new(PKG_DIR);
+        if !root_dir_path.exists() {
+            return Ok(());
+        }
+        let prompt = format!("Remove '{}' with all packages and the cache?", PKG_DIR);
+        if !confirm(&prompt, yes)? {
+            log(LogLevel::Warn, "Nothing removed");
+            return Ok(());
+        }
+        fs::remove_dir_all(root_dir_path)?;
+        log(LogLevel::Log, "All packages removed successfully!");
+        return Ok(());
+    }
     for choice in &choices {
         match choice.as_str() {
-            "All" => {
-                let root_dir_path = Path::new(PKG_DIR);
-                if root_dir_path.exists() {
-                    fs::remove_dir_all(root_dir_path)?;
-                    log(LogLevel::Log, "All packages removed successfully!");
-                }
-            }
             "App-bin" => {
                 let bin_dir_path = Path::new(BIN_DIR);
                 if bin_dir_path.exists() {
                     fs::remove_dir_all(bin_dir_path)?;
+                    InstalledList::remove(|pkg| pkg.typ == PackageType::AppBin)?;
                     log(LogLevel::Log, "App-bin packages removed successfully!");
                 }
             }
             "App-src" => {
-                let pkgs = load_or_refresh_packages(false).await?;
+                let pkgs = load_or_refresh_packages(false, false).await?;
                 let root_dir_path = Path::new(PKG_DIR);
                 if root_dir_path.exists() {
                     for pkg in &pkgs {
@@ -303,6 +663,7 @@ pub async fn clean_all_packages(choices: Vec<String>) -> Result<(), Box<dyn Erro
                             }
                         }
                     }
+                    InstalledList::remove(|pkg| pkg.typ == PackageType::AppSrc)?;
                     log(
                         LogLevel::Log,
                         "All 'App-src' packages removed successfully!",
@@ -310,7 +671,7 @@ pub async fn clean_all_packages(choices: Vec<String>) -> Result<(), Box<dyn Erro
                 }
             }
             "Kernel" => {
-                let pkgs = load_or_refresh_packages(false).await?;
+                let pkgs = load_or_refresh_packages(false, false).await?;
                 let root_dir_path = Path::new(PKG_DIR);
                 if root_dir_path.exists() {
                     for pkg in &pkgs {
@@ -325,6 +686,7 @@ pub async fn clean_all_packages(choices: Vec<String>) -> Result<(), Box<dyn Erro
                             }
                         }
                     }
+                    InstalledList::remove(|pkg| pkg.typ == PackageType::Kernel)?;
                     log(LogLevel::Log, "All 'Kernel' packages removed successfully!");
                 }
             }
@@ -345,14 +707,16 @@ pub async fn clean_all_packages(choices: Vec<String>) -> Result<(), Box<dyn Erro
 }
 
 /// Pulls the script of the specified app-bin
-async fn pull_script(pkg_name: &str) -> Result<(), Box<dyn Error>> {
-    let script_dir = PathBuf::from(BIN_DIR);
+/// # Arguments
+/// * `pkg_name` - The name of the app-bin
+/// * `script_dir` - The directory the app-bin was pulled into
+async fn pull_script(pkg_name: &str, script_dir: &Path) -> Result<(), Box<dyn Error>> {
     if !script_dir.exists() {
-        fs::create_dir_all(&script_dir)?;
+        fs::create_dir_all(script_dir)?;
     }
 
     // get the script code
-    let script_url = format!("{}/{}.sh", PACKAGES_URL, pkg_name);
+    let script_url = format!("{}/{}.sh", packages_url(), pkg_name);
     let bytes = match fetch_binary(&script_url).await {
         Ok(data) => data,
         Err(_) => {
@@ -363,7 +727,7 @@ async fn pull_script(pkg_name: &str) -> Result<(), Box<dyn Error>> {
                     pkg_name
                 ),
             );
-            let default_script_url = format!("{}/default.sh", PACKAGES_URL);
+            let default_script_url = format!("{}/default.sh", packages_url());
             fetch_binary(&default_script_url).await?
         }
     };
@@ -382,14 +746,86 @@ async fn pull_script(pkg_name: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Checks that a downloaded script is not empty and starts with a shebang
+fn validate_script(script_path: &Path) -> Result<(), Box<dyn Error>> {
+    let contents = fs::read(script_path)
+        .map_err(|err| format!("Failed to read script '{}': {}", script_path.display(), err))?;
+    if contents.iter().all(|byte| byte.is_ascii_whitespace()) {
+        return Err(format!("Script '{}' is empty", script_path.display()).into());
+    }
+    if !contents.starts_with(b"#!") {
+        return Err(format!(
+            "Script '{}' does not start with a shebang (#!)",
+            script_path.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Asks the user to confirm an action, returns true without asking if `yes` or `RUXGO_PKG_YES=1` is set
+/// # Arguments
+/// * `prompt` - The question to ask
+/// * `yes` - Confirm without asking
+fn confirm(prompt: &str, yes: bool) -> Result<bool, Box<dyn Error>> {
+    if yes || std::env::var("RUXGO_PKG_YES").is_ok_and(|v| v == "1" || v == "true") {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(format!(
+            "{} Stdin is not a terminal, use `--yes` or RUXGO_PKG_YES=1 to confirm",
+            prompt
+        )
+        .into());
+    }
+    Ok(Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+/// Asks before running a script that has not been approved in its current version,
+/// returns false if the user declines
+/// # Arguments
+/// * `script_path` - The path of the script to run
+/// * `yes` - Approve the script without asking, also set by `RUXGO_PKG_YES=1`
+fn approve_script(script_path: &Path, yes: bool) -> Result<bool, Box<dyn Error>> {
+    let script = script_path.to_str().unwrap();
+    let mut approved = Hasher::load_hashes_from_file(APPROVED_SCRIPTS);
+    if !Hasher::is_file_changed(script, &approved) {
+        return Ok(true);
+    }
+    let prompt = format!(
+        "Script '{}' is new or has changed since it was last run, run it?",
+        script
+    );
+    if !confirm(&prompt, yes)? {
+        return Ok(false);
+    }
+    Hasher::save_hash(script, &mut approved);
+    Hasher::save_hashes_to_file(APPROVED_SCRIPTS, &approved);
+    Ok(true)
+}
+
 /// Runs the specified app-bin
-pub fn run_app(pkg_name: &str) -> Result<(), Box<dyn Error>> {
+/// # Arguments
+/// * `pkg_name` - The name of the app-bin
+/// * `yes` - Run its script without asking if it is new or has changed
+pub fn run_app(pkg_name: &str, yes: bool) -> Result<(), Box<dyn Error>> {
     let script_dir = PathBuf::from(BIN_DIR);
     let mut script_path = script_dir.join(format!("{}.sh", pkg_name));
     // use the default script if the app-bin script does not exist
     if !script_path.exists() {
         script_path = script_dir.join("default.sh");
     }
+    validate_script(&script_path)?;
+    if !approve_script(&script_path, yes)? {
+        log(
+            LogLevel::Warn,
+            &format!("Not running application '{}'", pkg_name),
+        );
+        return Ok(());
+    }
     let output = Command::new("bash")
         .arg(&script_path)
         .arg(pkg_name)
@@ -422,13 +858,68 @@ pub fn run_app(pkg_name: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Forbids fetching anything or rewriting the package cache, also set by `RUXGO_PKG_FROZEN=1`
+pub fn set_frozen(frozen: bool) {
+    FROZEN.store(frozen, Ordering::Relaxed);
+}
+
+/// Returns true if the package cache must be used as is and nothing may be downloaded
+fn frozen() -> bool {
+    FROZEN.load(Ordering::Relaxed)
+        || std::env::var("RUXGO_PKG_FROZEN").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Returns an error if `--frozen` is set, as the action would download something
+/// # Arguments
+/// * `action` - What would be downloaded, e.g. `pull package 'foo'`
+fn ensure_not_frozen(action: &str) -> Result<(), Box<dyn Error>> {
+    if frozen() {
+        return Err(format!("Cannot {} with --frozen, it would download it", action).into());
+    }
+    Ok(())
+}
+
+/// Returns the URL the package list and the app-bins are downloaded from, `RUXGO_PKG_URL`
+/// if it is set, e.g. to use another mirror
+fn packages_url() -> String {
+    std::env::var("RUXGO_PKG_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .map_or_else(
+            || PACKAGES_URL.to_string(),
+            |url| url.trim_end_matches('/').to_string(),
+        )
+}
+
+/// Returns how long the package list cache is reused, from `RUXGO_PKG_CACHE_TTL` in seconds
+fn cache_ttl() -> Duration {
+    let ttl = std::env::var("RUXGO_PKG_CACHE_TTL")
+        .ok()
+        .and_then(|ttl| ttl.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_TTL);
+    Duration::from_secs(ttl)
+}
+
+/// Returns true if the cache file was modified longer than `ttl` ago, or its age is unknown
+fn cache_expired(pkg_cache: &Path, ttl: Duration) -> bool {
+    fs::metadata(pkg_cache)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_none_or(|age| age > ttl)
+}
+
 /// Checks and updates the package list cache as needed, then returns the packages
 /// # Arguments
-/// * `force_refresh` - Indicates whether to forcibly refresh the package list
-async fn load_or_refresh_packages(force_refresh: bool) -> Result<Vec<PackageInfo>, Box<dyn Error>> {
+/// * `force_refresh` - Refresh the package list if the cache is older than the TTL
+/// * `ignore_ttl` - Refresh the package list with `force_refresh` even if the cache has not expired
+async fn load_or_refresh_packages(
+    force_refresh: bool,
+    ignore_ttl: bool,
+) -> Result<Vec<PackageInfo>, Box<dyn Error>> {
     // create the cache directory if it doesn't exist
     let cache_dir = Path::new(CACHE_DIR);
-    if !cache_dir.exists() {
+    if !cache_dir.exists() && !frozen() {
         fs::create_dir_all(cache_dir)?
     }
 
@@ -450,8 +941,19 @@ async fn load_or_refresh_packages(force_refresh: bool) -> Result<Vec<PackageInfo
     };
 
     // If the cache is empty or forced to refresh, the data is updated and the cache is updated
-    if pkg_list.packages.is_empty() || force_refresh {
-        let contents = fetch_url(&format!("{}/{}", PACKAGES_URL, "packages.toml")).await?;
+    if frozen() {
+        // The cache is used whatever its age, only an explicit refresh or no cache is an error
+        if pkg_list.packages.is_empty() {
+            return Err("The package cache is empty and --frozen forbids fetching it".into());
+        }
+        if force_refresh && ignore_ttl {
+            return Err("Cannot refresh the package cache with --frozen".into());
+        }
+        return Ok(pkg_list.packages);
+    }
+    let refresh = force_refresh && (ignore_ttl || cache_expired(&pkg_cache, cache_ttl()));
+    if pkg_list.packages.is_empty() || refresh {
+        let contents = fetch_url(&format!("{}/{}", packages_url(), "packages.toml")).await?;
         pkg_list = toml::from_str::<PackageList>(&contents).map_err(|err| {
             log(LogLevel::Error, &format!("Failed to parse TOML: {}", err));
             Box::new(err) as Box<dyn Error>
@@ -2,20 +2,113 @@
 
 use crate::builder::Target;
 use crate::utils::log::{log, LogLevel};
-use serde::Serialize;
+use crate::utils::suggest::closest_match;
+use serde::{Serialize, Serializer};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::default::Default;
+use std::fmt;
 use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
-use std::sync::{Arc, RwLock};
-use std::{io::Read, path::Path};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
 use toml::{Table, Value};
 use walkdir::WalkDir;
 
+/// The default root directory of all build outputs
+pub static DEFAULT_OUTPUT_DIR: &str = "ruxgo_bld";
+
+/// The file extension of dynamic libraries on the host
+#[cfg(target_os = "windows")]
+pub static DLL_EXT: &str = ".dll";
+#[cfg(target_os = "macos")]
+pub static DLL_EXT: &str = ".dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub static DLL_EXT: &str = ".so";
+
 /// Struct descibing the build config of the local project
 #[derive(Debug, Clone)]
 pub struct BuildConfig {
     pub compiler: Arc<RwLock<String>>,
+    pub output_dir: String,
+    /// Whether compiled objects are kept in a content-addressed cache and reused
+    pub obj_cache: bool,
+}
+
+impl BuildConfig {
+    /// Returns the directory of the built binaries and libraries
+    pub fn bin_dir(&self) -> String {
+        format!("{}/bin", self.output_dir)
+    }
+
+    /// Returns the directory of the object files
+    pub fn obj_dir(&self) -> String {
+        #[cfg(target_os = "windows")]
+        return format!("{}/obj_win32", self.output_dir);
+        #[cfg(target_os = "linux")]
+        return format!("{}/obj_linux", self.output_dir);
+        #[cfg(target_os = "macos")]
+        return format!("{}/obj_macos", self.output_dir);
+    }
+
+    /// Returns the object file of a source of the given target
+    /// # Arguments
+    /// * `target_name` - The name of the target
+    /// * `src_name` - The file name of the source without its extensions
+    pub fn obj_path(&self, target_name: &str, src_name: &str) -> String {
+        format!("{}/{}-{}.o", self.obj_dir(), target_name, src_name)
+    }
+
+    /// Returns the directory of the precompiled header of the given target
+    pub fn pch_dir(&self, target_name: &str) -> String {
+        format!("{}/{}_pch", self.obj_dir(), target_name)
+    }
+
+    /// Returns the directory of the object cache, see `obj_cache`
+    pub fn obj_cache_dir(&self) -> String {
+        format!("{}/obj_cache", self.output_dir)
+    }
+
+    /// Returns the cargo target directory of the OS build
+    pub fn target_dir(&self) -> String {
+        format!("{}/target", self.output_dir)
+    }
+
+    /// Returns the directory of the ruxmusl build
+    pub fn ruxmusl_dir(&self) -> String {
+        format!("{}/ruxmusl", self.output_dir)
+    }
+
+    /// Returns the path of the OSConfig hash file
+    pub fn os_config_hash_path(&self) -> String {
+        format!("{}/os_config.hash", self.output_dir)
+    }
+
+    /// Returns the path of the linker map file of the given target
+    pub fn map_path(&self, target_name: &str) -> String {
+        format!("{}/{}.map", self.bin_dir(), target_name)
+    }
+
+    /// Returns the path of the disassembly listing of the given target
+    pub fn asm_path(&self, target_name: &str) -> String {
+        format!("{}/{}.asm", self.bin_dir(), target_name)
+    }
+
+    /// Returns the path of the manifest listing the built outputs
+    pub fn build_manifest_path(&self) -> String {
+        format!("{}/build-manifest.json", self.output_dir)
+    }
+
+    /// Returns the path of the hash file of the given target
+    pub fn target_hash_path(&self, target_name: &str) -> String {
+        #[cfg(target_os = "windows")]
+        return format!("{}/{}.win32.hash", self.output_dir, target_name);
+        #[cfg(target_os = "linux")]
+        return format!("{}/{}.linux.hash", self.output_dir, target_name);
+        #[cfg(target_os = "macos")]
+        return format!("{}/{}.macos.hash", self.output_dir, target_name);
+    }
 }
 
 /// Struct descibing the OS config of the local project
@@ -23,21 +116,75 @@ pub struct BuildConfig {
 pub struct OSConfig {
     pub name: String,
     pub features: Vec<String>,
+    /// Whether features implied by the config are added to `features`, `bus-pci` and `smp`
+    pub default_features: bool,
     pub ulib: String,
+    /// Version of the musl sources ruxmusl is built from
+    pub musl_version: String,
+    /// Expected sha256 of the musl tarball, empty to use the known one of the version
+    pub musl_sha256: String,
+    /// Directory of the Ruxos sources, empty to look for them next to or above the project
+    pub ruxos_dir: String,
     pub platform: PlatformConfig,
 }
 
+impl OSConfig {
+    /// Returns the config as JSON with sorted keys and features, the same for equal configs
+    /// whatever the order the features were listed in, for hashing
+    pub fn canonical_string(&self) -> String {
+        let mut os_config = self.clone();
+        os_config.features.sort();
+        os_config.features.dedup();
+        // serde_json::Value keeps the keys of objects sorted
+        serde_json::to_value(&os_config)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if Ruxos is built and linked into the exes, false without an [os] section
+    /// or with only a top level [platform] to run plain freestanding exes on
+    pub fn has_os(&self) -> bool {
+        !self.name.is_empty() || !self.ulib.is_empty()
+    }
+
+    /// Returns true if the exes run on a platform, i.e. with Ruxos or with a top level [platform]
+    pub fn has_platform(&self) -> bool {
+        self.has_os() || !self.platform.name.is_empty()
+    }
+
+    /// Returns the path of a file or directory in the Ruxos sources, e.g. `modules/ruxhal`
+    /// # Arguments
+    /// * `path` - The path relative to the root of the Ruxos sources
+    pub fn ruxos_path(&self, path: &str) -> String {
+        if !self.ruxos_dir.is_empty() {
+            format!("{}/{}", self.ruxos_dir.trim_end_matches('/'), path)
+        } else if Path::new("../ruxos").exists() {
+            format!("../ruxos/{}", path)
+        } else {
+            // The project is an app inside the Ruxos sources, e.g. `apps/c/<app>`
+            format!("../../../{}", path)
+        }
+    }
+}
+
 /// Struct descibing the platform config of the local project
 #[derive(Debug, Default, PartialEq, Clone, Serialize)]
 pub struct PlatformConfig {
     pub name: String,
     pub arch: String,
+    /// Prefix of the cross toolchain, prepended to the compiler and `ar`
     pub cross_compile: String,
     pub target: String,
     pub smp: String,
     pub mode: String,
     pub log: String,
     pub v: String,
+    /// Passed as `--sysroot=` when compiling and linking, if set
+    pub sysroot: String,
+    /// Extra include directories, passed as `-I` when compiling
+    pub extra_includes: Vec<String>,
+    /// Extra library directories, passed as `-L` when linking
+    pub extra_lib_dirs: Vec<String>,
     pub qemu: QemuConfig,
 }
 
@@ -52,35 +199,107 @@ pub struct QemuConfig {
     pub disk_img: String,
     pub v9p: String,
     pub v9p_path: String,
+    /// 9p shares of the host passed to the guest, a single `v9p_path` one tagged `rootfs` by default
+    pub v9p_shares: Vec<V9pShare>,
     pub accel: String,
     pub qemu_log: String,
     pub net_dump: String,
     pub net_dev: String,
+    /// Port forwards of the user netdev, e.g. `tcp::8080-:80`
+    pub hostfwd: Vec<String>,
+    /// Host interface of the tap netdev
+    pub tap_ifname: String,
+    /// Bridge the tap netdev is attached to by the QEMU bridge helper, instead of `tap_ifname`
+    pub bridge: String,
+    /// MAC address of the guest network device, empty for the QEMU default
+    pub mac_addr: String,
     pub ip: String,
     pub gw: String,
     pub args: String,
     pub envs: String,
+    /// Path or name of the qemu executable, empty for `qemu-system-<arch>`
+    pub qemu_bin: String,
+    /// Value of `-serial`, empty for the default of the graphic mode
+    pub serial: String,
+    /// Value of `-monitor`, empty for the qemu default
+    pub monitor: String,
+}
+
+/// Struct describing a host directory shared with the guest over virtio-9p
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct V9pShare {
+    pub path: String,
+    pub mount_tag: String,
+    pub security_model: String,
 }
 
 impl QemuConfig {
+    /// Returns the qemu executable to launch, `qemu_bin` if set or else `qemu-system-<arch>`
+    pub fn qemu_bin(&self, platform_config: &PlatformConfig) -> String {
+        if self.qemu_bin.is_empty() {
+            format!("qemu-system-{}", platform_config.arch)
+        } else {
+            self.qemu_bin.clone()
+        }
+    }
+
     /// This function is used to config qemu parameters when running on qemu
     pub fn config_qemu(
         &self,
         platform_config: &PlatformConfig,
         trgt: &Target,
-    ) -> (Vec<String>, Vec<String>) {
+    ) -> Result<(Vec<String>, Vec<String>), ConfigError> {
+        self.config_qemu_paths(platform_config, trgt.elf(), &trgt.bin_path)
+    }
+
+    /// Returns the qemu args and the qemu args waiting for gdb, booting the given kernel,
+    /// or an error if the bus, the architecture or the network device is not supported
+    /// # Arguments
+    /// * `platform_config` - The platform configuration
+    /// * `elf_path` - The path of the kernel elf, booted on x86_64
+    /// * `bin_path` - The path of the kernel binary, booted on riscv64 and aarch64
+    /// # Example
+    /// ```
+    /// # use ruxgo::parser::{PlatformConfig, QemuConfig};
+    /// let qemu = QemuConfig {
+    ///     bus: "mmio".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// for (arch, machine, kernel) in [
+    ///     ("x86_64", "q35", "app.elf"),
+    ///     ("riscv64", "virt", "app.bin"),
+    ///     ("aarch64", "virt", "app.bin"),
+    /// ] {
+    ///     let platform = PlatformConfig {
+    ///         arch: arch.to_string(),
+    ///         smp: "1".to_string(),
+    ///         ..Default::default()
+    ///     };
+    ///     let (qemu_args, _) = qemu.config_qemu_paths(&platform, "app.elf", "app.bin").unwrap();
+    ///     assert_eq!(qemu_args[0], format!("qemu-system-{}", arch));
+    ///     assert!(qemu_args.windows(2).any(|args| args == ["-machine", machine]));
+    ///     assert!(qemu_args.windows(2).any(|args| args == ["-kernel", kernel]));
+    /// }
+    /// ```
+    pub fn config_qemu_paths(
+        &self,
+        platform_config: &PlatformConfig,
+        elf_path: &str,
+        bin_path: &str,
+    ) -> Result<(Vec<String>, Vec<String>), ConfigError> {
         // vdev_suffix
         let vdev_suffix = match self.bus.as_str() {
             "mmio" => "device",
             "pci" => "pci",
-            _ => {
-                log(LogLevel::Error, "BUS must be one of 'mmio' or 'pci'");
-                std::process::exit(1);
+            bus => {
+                return Err(ConfigError::InvalidValue {
+                    field: "qemu.bus".to_string(),
+                    message: format!("must be one of \"mmio\" or \"pci\", found \"{}\"", bus),
+                })
             }
         };
         // config qemu
-        let mut qemu_args = Vec::new();
-        qemu_args.push(format!("qemu-system-{}", platform_config.arch));
+        let mut qemu_args = vec![self.qemu_bin(platform_config)];
         // init
         qemu_args.push("-m".to_string());
         qemu_args.push("128M".to_string());
@@ -90,23 +309,16 @@ impl QemuConfig {
         match platform_config.arch.as_str() {
             "x86_64" => {
                 qemu_args.extend(
-                    ["-machine", "q35", "-kernel", &trgt.elf_path]
+                    ["-machine", "q35", "-kernel", elf_path]
                         .iter()
                         .map(|&arg| arg.to_string()),
                 );
             }
-            "risc64" => {
+            "riscv64" => {
                 qemu_args.extend(
-                    [
-                        "-machine",
-                        "virt",
-                        "-bios",
-                        "default",
-                        "-kernel",
-                        &trgt.bin_path,
-                    ]
-                    .iter()
-                    .map(|&arg| arg.to_string()),
+                    ["-machine", "virt", "-bios", "default", "-kernel", bin_path]
+                        .iter()
+                        .map(|&arg| arg.to_string()),
                 );
             }
             "aarch64" => {
@@ -117,20 +329,17 @@ impl QemuConfig {
                         "-machine",
                         "virt",
                         "-kernel",
-                        &trgt.bin_path,
+                        bin_path,
                     ]
                     .iter()
                     .map(|&arg| arg.to_string()),
                 );
             }
-            _ => {
-                log(LogLevel::Error, "Unsupported architecture");
-                std::process::exit(1);
-            }
+            arch => return Err(ConfigError::UnsupportedArch(arch.to_string())),
         };
         // args and envs
         qemu_args.push("-append".to_string());
-        qemu_args.push(format!("\";{};{}\"", self.args, self.envs));
+        qemu_args.push(format!(";{};{}", self.args, self.envs));
         // blk
         if self.blk == "y" {
             qemu_args.push("-device".to_string());
@@ -143,33 +352,53 @@ impl QemuConfig {
         }
         // v9p
         if self.v9p == "y" {
-            qemu_args.push("-fsdev".to_string());
-            qemu_args.push(format!(
-                "local,id=myid,path={},security_model=none",
-                self.v9p_path
-            ));
-            qemu_args.push("-device".to_string());
-            qemu_args.push(format!(
-                "virtio-9p-{},fsdev=myid,mount_tag=rootfs",
-                vdev_suffix
-            ));
+            for (i, share) in self.v9p_shares.iter().enumerate() {
+                qemu_args.push("-fsdev".to_string());
+                qemu_args.push(format!(
+                    "local,id=fsdev{},path={},security_model={}",
+                    i, share.path, share.security_model
+                ));
+                qemu_args.push("-device".to_string());
+                qemu_args.push(format!(
+                    "virtio-9p-{},fsdev=fsdev{},mount_tag={}",
+                    vdev_suffix, i, share.mount_tag
+                ));
+            }
         }
         // net
         if self.net == "y" {
             qemu_args.push("-device".to_string());
-            qemu_args.push(format!("virtio-net-{},netdev=net0", vdev_suffix));
+            let mut device = format!("virtio-net-{},netdev=net0", vdev_suffix);
+            if !self.mac_addr.is_empty() {
+                device.push_str(&format!(",mac={}", self.mac_addr));
+            }
+            qemu_args.push(device);
             // net_dev
             if self.net_dev == "user" {
                 qemu_args.push("-netdev".to_string());
-                qemu_args.push(
-                    "user,id=net0,hostfwd=tcp::5555-:5555,hostfwd=udp::5555-:5555".to_string(),
-                );
+                let mut netdev = "user,id=net0".to_string();
+                for fwd in &self.hostfwd {
+                    netdev.push_str(&format!(",hostfwd={}", fwd));
+                }
+                qemu_args.push(netdev);
             } else if self.net_dev == "tap" {
                 qemu_args.push("-netdev".to_string());
-                qemu_args.push("tap,id=net0,ifname=tap0,script=no,downscript=no".to_string());
+                if !self.bridge.is_empty() {
+                    qemu_args.push(format!("tap,id=net0,br={}", self.bridge));
+                } else {
+                    qemu_args.push(format!(
+                        "tap,id=net0,ifname={},script=no,downscript=no",
+                        self.tap_ifname
+                    ));
+                }
             } else {
-                log(LogLevel::Error, "NET_DEV must be one of 'user' or 'tap'");
-                std::process::exit(1);
+                return Err(ConfigError::InvalidValue {
+                    field: "qemu.net_dev".to_string(),
+                    message: format!(
+                        "must be one of \"user\" or \"tap\", found \"{}\"",
+                        self.net_dev
+                    ),
+                });
             }
             // net_dump
             if self.net_dump == "y" {
@@ -184,9 +413,22 @@ impl QemuConfig {
             qemu_args.push("-vga".to_string());
             qemu_args.push("none".to_string());
             qemu_args.push("-serial".to_string());
-            qemu_args.push("mon:stdio".to_string());
+            if self.serial.is_empty() {
+                qemu_args.push("mon:stdio".to_string());
+            } else {
+                qemu_args.push(self.serial.clone());
+            }
         } else if self.graphic == "n" {
             qemu_args.push("-nographic".to_string());
+            // -nographic puts the serial and monitor on stdio unless told otherwise
+            if !self.serial.is_empty() {
+                qemu_args.push("-serial".to_string());
+                qemu_args.push(self.serial.clone());
+            }
+        }
+        if !self.monitor.is_empty() {
+            qemu_args.push("-monitor".to_string());
+            qemu_args.push(self.monitor.clone());
         }
         // qemu_log
         if self.qemu_log == "y" {
@@ -202,7 +444,7 @@ impl QemuConfig {
         qemu_args_debug.push("-S".to_string());
         // acceel
         if self.accel == "y" {
-            if cfg!(target_os = "darwin") {
+            if cfg!(target_os = "macos") {
                 qemu_args.push("-cpu".to_string());
                 qemu_args.push("host".to_string());
                 qemu_args.push("-accel".to_string());
@@ -215,49 +457,168 @@ impl QemuConfig {
             }
         }
 
-        (qemu_args, qemu_args_debug)
+        Ok((qemu_args, qemu_args_debug))
+    }
+}
+
+/// Enum describing the target type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetType {
+    Exe,
+    Dll,
+    Static,
+    Object,
+    /// Only contributes its `include_dir` to the targets depending on it, builds nothing
+    HeaderOnly,
+    /// An exe built and run by `ruxgo test`, passing if it exits with 0
+    Test,
+}
+
+impl TargetType {
+    /// All valid target types
+    pub const ALL: [TargetType; 6] = [
+        TargetType::Exe,
+        TargetType::Dll,
+        TargetType::Static,
+        TargetType::Object,
+        TargetType::HeaderOnly,
+        TargetType::Test,
+    ];
+
+    /// Names other build systems give to the target types, suggested when used as a type
+    const ALIASES: [(&'static str, TargetType); 8] = [
+        ("executable", TargetType::Exe),
+        ("bin", TargetType::Exe),
+        ("shared", TargetType::Dll),
+        ("so", TargetType::Dll),
+        ("dylib", TargetType::Dll),
+        ("lib", TargetType::Static),
+        ("archive", TargetType::Static),
+        ("header", TargetType::HeaderOnly),
+    ];
+
+    /// Returns the name of the target type as written in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetType::Exe => "exe",
+            TargetType::Dll => "dll",
+            TargetType::Static => "static",
+            TargetType::Object => "object",
+            TargetType::HeaderOnly => "header-only",
+            TargetType::Test => "test",
+        }
+    }
+
+    /// Returns true if the target is linked into an executable
+    pub fn is_exe(&self) -> bool {
+        matches!(self, TargetType::Exe | TargetType::Test)
+    }
+
+    /// Returns true if other targets can depend on this type of target
+    pub fn is_lib(&self) -> bool {
+        matches!(
+            self,
+            TargetType::Dll | TargetType::Static | TargetType::Object | TargetType::HeaderOnly
+        )
+    }
+}
+
+impl FromStr for TargetType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(typ) = TargetType::ALL.iter().find(|typ| typ.as_str() == s) {
+            return Ok(*typ);
+        }
+        let valid = TargetType::ALL.map(|typ| typ.as_str());
+        let mut msg = format!("Invalid target type \"{}\"", s);
+        let alias = TargetType::ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(s))
+            .map(|(_, typ)| typ.as_str());
+        match alias.or_else(|| closest_match(s, valid)) {
+            Some(suggestion) => msg.push_str(&format!(", did you mean \"{}\"?", suggestion)),
+            None => msg.push('.'),
+        }
+        msg.push_str(&format!(" Valid types are: {}", valid.join(", ")));
+        Err(msg)
+    }
+}
+
+impl Serialize for TargetType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl fmt::Display for TargetType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
 /// Struct describing the target config of the local project
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TargetConfig {
     pub name: String,
-    pub src: String,
+    /// Roots searched for source files
+    pub src: Vec<String>,
     pub src_only: Vec<String>,
     pub src_exclude: Vec<String>,
     pub include_dir: Vec<String>,
-    pub typ: String,
+    #[serde(rename = "type")]
+    pub typ: TargetType,
     pub cflags: String,
+    /// Preprocessor macros passed as `-D`, e.g. `FOO` or `BAR=1`
+    pub defines: Vec<String>,
+    pub cstd: String,
+    pub cppstd: String,
     pub archive: String,
     pub linker: String,
     pub ldflags: String,
+    /// System libraries linked as `-l` after the objects and deps of native exe and dll targets
+    pub libs: Vec<String>,
+    /// Library search paths passed as `-L` with `libs`
+    pub lib_dirs: Vec<String>,
     pub deps: Vec<String>,
+    /// Whether to write a linker map file next to the binary of exe and dll targets
+    pub emit_map: bool,
+    /// Whether to write the disassembly of the elf of exe targets next to their binary
+    pub emit_asm: bool,
+    /// `auto`, `always` or `never`: `always` strips native exes after linking,
+    /// `never` keeps the symbols in the bin of OS exes by stripping only the debug info
+    pub strip: String,
+    /// Shell command run after the target is linked
+    pub post_build: String,
+    /// Shell command run before the sources of the target are collected
+    pub pre_build: String,
+    /// Files whose changes make `pre_build` run again
+    pub pre_build_inputs: Vec<String>,
+    /// Header precompiled once and included in every source of its language
+    pub pch: String,
 }
 
 impl TargetConfig {
     /// Returns a vec of all filenames ending in .cpp or .c in the src directory
     /// # Arguments
     /// * `path` - The path to the src directory
+    ///
+    /// Files and directories matching `src_exclude` are skipped, directories as a whole,
+    /// and if `src_only` is set only the files matching it are kept
     fn get_src_names(&self, tgt_path: &str) -> Vec<String> {
         let mut src_names = Vec::new();
         let src_path = Path::new(tgt_path);
 
         let walker = WalkDir::new(src_path)
             .into_iter()
-            .filter_entry(|e| self.should_include(e.path()) && !self.should_exclude(e.path()));
+            .filter_entry(|e| !self.should_exclude(&Self::path_str(e.path())));
         for entry in walker.filter_map(|e| e.ok()) {
             let path = entry.path();
             if path.is_file() {
                 if let Some(ext) = path.extension() {
-                    if ext == "cpp" || ext == "c" {
-                        if let Some(file_path_str) = path.to_str() {
-                            #[cfg(target_os = "windows")]
-                            let formatted_path_str = file_path_str.replace('\\', "/");
-                            #[cfg(target_os = "linux")]
-                            let formatted_path_str = file_path_str.to_string();
-                            src_names.push(formatted_path_str);
-                        }
+                    let path_str = Self::path_str(path);
+                    if (ext == "cpp" || ext == "c") && self.should_include(&path_str) {
+                        src_names.push(path_str);
                     }
                 }
             }
@@ -266,55 +627,86 @@ impl TargetConfig {
         src_names
     }
 
+    /// Returns the path with `/` separators, as the patterns are written in the config
+    fn path_str(path: &Path) -> String {
+        let path_str = path.to_string_lossy().to_string();
+        #[cfg(target_os = "windows")]
+        let path_str = path_str.replace('\\', "/");
+        path_str
+    }
+
     /// Exclusion logic: Check if the path is in src_exclude
-    fn should_exclude(&self, path: &Path) -> bool {
+    fn should_exclude(&self, path: &str) -> bool {
         self.src_exclude
             .iter()
-            .any(|excluded| path.to_str().map_or(false, |p| p.contains(excluded)))
+            .any(|excluded| path.contains(excluded))
     }
 
     /// Inclusion logic: Apply src_only logic only to files
-    fn should_include(&self, path: &Path) -> bool {
+    fn should_include(&self, path: &str) -> bool {
         if self.src_only.is_empty() {
             return true;
         }
-        self.src_only
+        self.src_only.iter().any(|included| path.contains(included))
+    }
+
+    /// Returns the paths of the source files in the target, honoring `src_only` and `src_exclude`
+    pub fn src_files(&self) -> Vec<String> {
+        self.src
             .iter()
-            .any(|included| path.to_str().map_or(false, |p| p.contains(included)))
+            .flat_map(|root| self.get_src_names(root))
+            .collect()
     }
 
-    /// Checks for duplicate source files in the target
-    fn check_duplicate_srcs(&self) {
-        let mut src_file_names = self.get_src_names(&self.src);
+    /// Returns the number of source files in the target
+    pub fn count_srcs(&self) -> usize {
+        self.src_files().len()
+    }
+
+    /// Checks for source files in the target that would compile to the same object file
+    pub fn check_duplicate_srcs(&self) -> Result<(), ConfigError> {
+        // Objects are named after the file stem, so "a/foo.c" and "b/foo.cpp" clash
+        let mut src_file_names: Vec<String> = self
+            .src_files()
+            .iter()
+            .filter_map(|path| Path::new(path).file_name()?.to_str())
+            .map(|file_name| file_name.split('.').next().unwrap_or(file_name).to_string())
+            .collect();
         src_file_names.sort_unstable();
-        src_file_names.dedup();
-        let mut last_name: Option<String> = None;
         let mut duplicates = Vec::new();
-
-        for file_name in &src_file_names {
-            if let Some(ref last) = last_name {
-                if last == file_name {
-                    duplicates.push(file_name.clone());
-                }
+        for pair in src_file_names.windows(2) {
+            if pair[0] == pair[1] && !duplicates.contains(&pair[0]) {
+                duplicates.push(pair[0].clone());
             }
-            last_name = Some(file_name.clone());
         }
         if !duplicates.is_empty() {
-            log(
-                LogLevel::Error,
-                &format!("Duplicate source files found for target: {}", self.name),
-            );
-            log(LogLevel::Error, "Source files must be unique");
-            for duplicate in duplicates {
-                log(LogLevel::Error, &format!("Duplicate file: {}", duplicate));
-            }
-            std::process::exit(1);
+            return Err(ConfigError::DuplicateSources {
+                target: self.name.clone(),
+                files: duplicates,
+            });
         }
+        Ok(())
+    }
+
+    /// Returns an error for each dependency that does not name a known target
+    /// # Arguments
+    /// * `targets` - All the targets of the project
+    pub fn unresolved_deps(&self, targets: &[TargetConfig]) -> Vec<ConfigError> {
+        self.deps
+            .iter()
+            .filter(|dep| !targets.iter().any(|target| target.name == **dep))
+            .map(|dep| ConfigError::UnknownDependency {
+                target: self.name.clone(),
+                dep: dep.clone(),
+                suggestion: closest_match(dep, targets.iter().map(|target| target.name.as_str()))
+                    .map(|s| s.to_string()),
+            })
+            .collect()
     }
 
     /// Rearrange the input targets
     /// Using topological sorting to respect dependencies.
-    fn arrange_targets(targets: Vec<TargetConfig>) -> Vec<TargetConfig> {
+    fn arrange_targets(targets: Vec<TargetConfig>) -> Result<Vec<TargetConfig>, ConfigError> {
         // Create a mapping from the target name to the target configuration
         let mut target_map: HashMap<String, TargetConfig> = targets
             .into_iter()
@@ -362,190 +754,473 @@ impl TargetConfig {
 
         // Check for rings
         if sorted_names.len() != target_map.len() {
-            log(LogLevel::Error, "Circular dependency detected");
-            std::process::exit(1);
+            return Err(ConfigError::CircularDependency(Self::find_cycle(
+                &target_map,
+                &sorted_names,
+            )));
         }
 
         // Rebuild the target list based on the sorted names
-        sorted_names
+        Ok(sorted_names
             .into_iter()
             .map(|name| target_map.remove(&name).unwrap())
-            .collect()
+            .collect())
+    }
+
+    /// Returns a dependency cycle among the targets left over by the topological sort
+    /// The first name is repeated at the end, e.g. `["a", "b", "a"]`
+    /// # Arguments
+    /// * `target_map` - All targets by name
+    /// * `sorted_names` - The targets the topological sort could order
+    fn find_cycle(
+        target_map: &HashMap<String, TargetConfig>,
+        sorted_names: &[String],
+    ) -> Vec<String> {
+        let mut remaining: Vec<&String> = target_map
+            .keys()
+            .filter(|name| !sorted_names.contains(name))
+            .collect();
+        remaining.sort_unstable();
+
+        // Every remaining target depends on another remaining one, so following
+        // those deps from any of them must eventually revisit a target
+        let mut path: Vec<String> = Vec::new();
+        let mut current = remaining[0].clone();
+        while !path.contains(&current) {
+            path.push(current.clone());
+            current = target_map[&current]
+                .deps
+                .iter()
+                .find(|dep| remaining.contains(dep))
+                .unwrap()
+                .clone();
+        }
+        let start = path.iter().position(|name| *name == current).unwrap();
+        let mut cycle = path.split_off(start);
+        cycle.push(current);
+        cycle
+    }
+}
+
+/// Error returned when a config file cannot be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be opened or read
+    Io { path: String, source: io::Error },
+    /// The config file is not valid TOML
+    Toml {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// A required section or field is missing
+    Missing(String),
+    /// A field does not have the expected TOML type
+    WrongType {
+        field: String,
+        expected: &'static str,
+    },
+    /// A field has a value that is not allowed
+    InvalidValue { field: String, message: String },
+    /// The platform name does not start with a supported architecture
+    UnsupportedArch(String),
+    /// The config does not define any target
+    NoTargets,
+    /// Two targets share the same name
+    DuplicateTarget(String),
+    /// A target has several source files with the same name
+    DuplicateSources { target: String, files: Vec<String> },
+    /// A target depends on a name that matches no target
+    UnknownDependency {
+        target: String,
+        dep: String,
+        suggestion: Option<String>,
+    },
+    /// The dependencies between targets form a cycle, given as the path around it
+    CircularDependency(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "Could not read config file {}: {}", path, source)
+            }
+            ConfigError::Toml { path, source } => {
+                write!(f, "Could not parse config file {}: {}", path, source)
+            }
+            ConfigError::Missing(field) => write!(f, "Could not find {} in config file", field),
+            ConfigError::WrongType { field, expected } => {
+                write!(f, "{} is not {}", field, expected)
+            }
+            ConfigError::InvalidValue { field, message } => write!(f, "{}: {}", field, message),
+            ConfigError::UnsupportedArch(arch) => write!(
+                f,
+                "\"ARCH\" must be one of \"x86_64\", \"riscv64\", or \"aarch64\", found \"{}\"",
+                arch
+            ),
+            ConfigError::NoTargets => write!(f, "No targets found"),
+            ConfigError::DuplicateTarget(name) => {
+                write!(f, "Duplicate target names found: {}", name)
+            }
+            ConfigError::DuplicateSources { target, files } => write!(
+                f,
+                "Duplicate source files found for target {}, source files must be unique: {}",
+                target,
+                files.join(", ")
+            ),
+            ConfigError::UnknownDependency {
+                target,
+                dep,
+                suggestion,
+            } => {
+                write!(f, "Target \"{}\": dependency \"{}\" not found", target, dep)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean \"{}\"?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            ConfigError::CircularDependency(cycle) => {
+                write!(f, "Circular dependency detected: {}", cycle.join(" -> "))
+            }
+        }
     }
 }
 
-/// This function is used to parse the config file of local project
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Toml { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// This function is used to parse the config file of local project, logging and exiting on error
 /// # Arguments
 /// * `path` - The path to the config file
 /// * `check_dup_src` - If true, the function will check for duplicately named source files
 pub fn parse_config(path: &str, check_dup_src: bool) -> (BuildConfig, OSConfig, Vec<TargetConfig>) {
-    // Open toml file and parse it into a string
-    let mut file = File::open(path).unwrap_or_else(|_| {
-        log(
-            LogLevel::Error,
-            &format!("Could not open config file: {}", path),
-        );
-        std::process::exit(1);
-    });
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap_or_else(|_| {
-        log(
-            LogLevel::Error,
-            &format!("Could not read config file: {}", path),
-        );
-        std::process::exit(1);
-    });
-    let config = contents.parse::<Table>().unwrap_or_else(|e| {
-        log(
-            LogLevel::Error,
-            &format!("Could not parse config file: {}", path),
-        );
-        log(LogLevel::Error, &format!("Error: {}", e));
+    try_parse_config(path, check_dup_src).unwrap_or_else(|e| {
+        log(LogLevel::Error, &e.to_string());
         std::process::exit(1);
-    });
+    })
+}
+
+/// Parses the config file of local project, returning an error instead of exiting
+/// # Arguments
+/// * `path` - The path to the config file
+/// * `check_dup_src` - If true, the function will check for duplicately named source files
+pub fn try_parse_config(
+    path: &str,
+    check_dup_src: bool,
+) -> Result<(BuildConfig, OSConfig, Vec<TargetConfig>), ConfigError> {
+    let config = read_table(path)?;
+
+    let build_config = parse_build_config(&config)?;
+    let os_config = parse_os_config(&config, &build_config)?;
+    let targets = parse_targets(&config, check_dup_src)?;
+    check_output_dir(&build_config.output_dir, &targets)?;
+
+    Ok((build_config, os_config, targets))
+}
+
+/// Checks that cleaning the output dir can't remove the project or its sources,
+/// i.e. that it is not empty, the project dir, one of its parents or a directory with sources
+/// # Arguments
+/// * `output_dir` - The output dir, relative to the project dir
+/// * `targets` - The targets of the project
+pub fn check_output_dir(output_dir: &str, targets: &[TargetConfig]) -> Result<(), ConfigError> {
+    let invalid = |message: String| {
+        Err(ConfigError::InvalidValue {
+            field: "output_dir".to_string(),
+            message,
+        })
+    };
+    if output_dir.trim().is_empty() {
+        return invalid("must not be empty".to_string());
+    }
+    let project_dir = normalize(&std::path::absolute(".").unwrap_or_default());
+    let dir = normalize(&project_dir.join(output_dir));
+    if project_dir.starts_with(&dir) {
+        return invalid(format!(
+            "\"{}\" is the project directory or one of its parents, cleaning it would remove the project",
+            output_dir
+        ));
+    }
+    for target in targets {
+        if target
+            .src
+            .iter()
+            .any(|src| normalize(&project_dir.join(src)).starts_with(&dir))
+        {
+            return invalid(format!(
+                "\"{}\" contains the sources of target \"{}\", cleaning it would remove them",
+                output_dir, target.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the `.` and `..` components of an absolute path without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
 
-    let build_config = parse_build_config(&config);
-    let os_config = parse_os_config(&config, &build_config);
-    let targets = parse_targets(&config, check_dup_src);
+/// Parses the `[workspace]` table of the config file, returning its `members`,
+/// the directories of the projects in the workspace, or None if the config is not a workspace
+/// # Arguments
+/// * `path` - The path to the config file
+pub fn try_parse_workspace(path: &str) -> Result<Option<Vec<String>>, ConfigError> {
+    let config = read_table(path)?;
+    let Some(workspace) = config.get("workspace") else {
+        return Ok(None);
+    };
+    let workspace = workspace.as_table().ok_or_else(|| ConfigError::WrongType {
+        field: "workspace".to_string(),
+        expected: "a table",
+    })?;
+    if !workspace.contains_key("members") {
+        return Err(ConfigError::Missing("members".to_string()));
+    }
+    let members = parse_cfg_vector(workspace, "members")?;
+    if members.is_empty() {
+        return Err(ConfigError::InvalidValue {
+            field: "members".to_string(),
+            message: "must list at least one project directory".to_string(),
+        });
+    }
+    Ok(Some(members))
+}
 
-    (build_config, os_config, targets)
+/// Reads the config file and parses it into a TOML table
+/// # Arguments
+/// * `path` - The path to the config file
+fn read_table(path: &str) -> Result<Table, ConfigError> {
+    let contents = read_config(path).map_err(|source| ConfigError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    contents
+        .parse::<Table>()
+        .map_err(|source| ConfigError::Toml {
+            path: path.to_string(),
+            source,
+        })
+}
+
+/// The config read from stdin, kept since stdin can only be read once
+static STDIN_CONFIG: OnceLock<String> = OnceLock::new();
+
+/// Reads the config file into a string, `-` reads it from stdin
+/// # Arguments
+/// * `path` - The path to the config file
+fn read_config(path: &str) -> io::Result<String> {
+    if path == "-" {
+        if let Some(contents) = STDIN_CONFIG.get() {
+            return Ok(contents.clone());
+        }
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        return Ok(STDIN_CONFIG.get_or_init(|| contents).clone());
+    }
+    // Open toml file and parse it into a string
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
 }
 
 /// Parses the build configuration
-fn parse_build_config(config: &Table) -> BuildConfig {
-    let build = config["build"].as_table().unwrap_or_else(|| {
-        log(LogLevel::Error, "Could not find build in config file");
-        std::process::exit(1);
-    });
-    let compiler = Arc::new(RwLock::new(
-        build
-            .get("compiler")
-            .unwrap_or_else(|| {
-                log(LogLevel::Error, "Could not find compiler in config file");
-                std::process::exit(1);
-            })
-            .as_str()
-            .unwrap_or_else(|| {
-                log(LogLevel::Error, "Compiler is not a string");
-                std::process::exit(1);
-            })
-            .to_string(),
-    ));
+fn parse_build_config(config: &Table) -> Result<BuildConfig, ConfigError> {
+    let build = config
+        .get("build")
+        .ok_or_else(|| ConfigError::Missing("build".to_string()))?
+        .as_table()
+        .ok_or_else(|| ConfigError::WrongType {
+            field: "build".to_string(),
+            expected: "a table",
+        })?;
+    if !build.contains_key("compiler") {
+        return Err(ConfigError::Missing("compiler".to_string()));
+    }
+    let compiler = Arc::new(RwLock::new(parse_cfg_string(build, "compiler", "")?));
+    let output_dir = parse_cfg_string(build, "output_dir", DEFAULT_OUTPUT_DIR)?;
+    let obj_cache = parse_cfg_bool(build, "obj_cache", false)?;
 
-    BuildConfig { compiler }
+    Ok(BuildConfig {
+        compiler,
+        output_dir,
+        obj_cache,
+    })
 }
 
 /// Parses the OS configuration
-fn parse_os_config(config: &Table, build_config: &BuildConfig) -> OSConfig {
+fn parse_os_config(config: &Table, build_config: &BuildConfig) -> Result<OSConfig, ConfigError> {
     let empty_os = Value::Table(toml::map::Map::default());
     let os = config.get("os").unwrap_or(&empty_os);
-    let os_config: OSConfig;
-    if os != &empty_os {
-        if let Some(os_table) = os.as_table() {
-            let name = parse_cfg_string(os_table, "name", "");
-            let ulib = parse_cfg_string(os_table, "ulib", "");
-            let mut features = parse_cfg_vector(os_table, "services");
-            if features.iter().any(|feat| {
-                feat == "fs"
-                    || feat == "net"
-                    || feat == "pipe"
-                    || feat == "select"
-                    || feat == "poll"
-                    || feat == "epoll"
-            }) {
-                features.push("fd".to_string());
-            }
-            if ulib == "ruxmusl" {
-                features.push("musl".to_string());
-                features.push("fp_simd".to_string());
-                features.push("fd".to_string());
-                features.push("tls".to_string());
-            }
-            // Parse platform (if empty, it is the default value)
-            let platform = parse_platform(os_table);
-            let current_compiler = build_config.compiler.read().unwrap();
-            let new_compiler = format!("{}{}", platform.cross_compile, *current_compiler);
-            drop(current_compiler);
-            *build_config.compiler.write().unwrap() = new_compiler;
-            os_config = OSConfig {
-                name,
-                features,
-                ulib,
-                platform,
-            };
-        } else {
-            log(LogLevel::Error, "OS is not a table");
-            std::process::exit(1);
+    if os == &empty_os {
+        // A platform without an [os] runs the plainly linked exes, e.g. on QEMU
+        if !config.contains_key("platform") {
+            return Ok(OSConfig::default());
         }
-    } else {
-        os_config = OSConfig::default();
+        let platform = parse_platform(config)?;
+        add_cross_compile(build_config, &platform);
+        return Ok(OSConfig {
+            platform,
+            ..Default::default()
+        });
+    }
+    let os_table = os.as_table().ok_or_else(|| ConfigError::WrongType {
+        field: "os".to_string(),
+        expected: "a table",
+    })?;
+    let name = parse_cfg_string(os_table, "name", "")?;
+    let ulib = parse_cfg_string(os_table, "ulib", "")?;
+    let musl_version = parse_cfg_string(os_table, "musl_version", "1.2.3")?;
+    let musl_sha256 = parse_cfg_string(os_table, "musl_sha256", "")?;
+    let ruxos_dir = parse_cfg_string(os_table, "ruxos_dir", "")?;
+    let default_features = parse_cfg_bool(os_table, "default_features", true)?;
+    let mut features = parse_cfg_vector(os_table, "services")?;
+    if default_features
+        && features.iter().any(|feat| {
+            feat == "fs"
+                || feat == "net"
+                || feat == "pipe"
+                || feat == "select"
+                || feat == "poll"
+                || feat == "epoll"
+        })
+    {
+        features.push("fd".to_string());
+    }
+    if default_features && ulib == "ruxmusl" {
+        features.push("musl".to_string());
+        features.push("fp_simd".to_string());
+        features.push("fd".to_string());
+        features.push("tls".to_string());
     }
+    // Parse platform (if empty, it is the default value)
+    let platform = parse_platform(os_table)?;
+    add_cross_compile(build_config, &platform);
 
-    os_config
+    Ok(OSConfig {
+        name,
+        features,
+        default_features,
+        ulib,
+        musl_version,
+        musl_sha256,
+        ruxos_dir,
+        platform,
+    })
+}
+
+/// Prefixes the compiler with the cross compiler of the platform
+fn add_cross_compile(build_config: &BuildConfig, platform: &PlatformConfig) {
+    let current_compiler = build_config.compiler.read().unwrap();
+    let new_compiler = format!("{}{}", platform.cross_compile, *current_compiler);
+    drop(current_compiler);
+    *build_config.compiler.write().unwrap() = new_compiler;
 }
 
 /// Parses the targets configuration
-fn parse_targets(config: &Table, check_dup_src: bool) -> Vec<TargetConfig> {
+fn parse_targets(config: &Table, check_dup_src: bool) -> Result<Vec<TargetConfig>, ConfigError> {
     let mut tgts = Vec::new();
-    let targets = config["targets"].as_array().unwrap_or_else(|| {
-        log(LogLevel::Error, "Could not find targets in config file");
-        std::process::exit(1);
-    });
+    let targets = config
+        .get("targets")
+        .ok_or_else(|| ConfigError::Missing("targets".to_string()))?
+        .as_array()
+        .ok_or_else(|| ConfigError::WrongType {
+            field: "targets".to_string(),
+            expected: "an array",
+        })?;
     for target in targets {
-        let target_tb = target.as_table().unwrap_or_else(|| {
-            log(LogLevel::Error, "Target is not a table");
-            std::process::exit(1);
-        });
+        let target_tb = target.as_table().ok_or_else(|| ConfigError::WrongType {
+            field: "target".to_string(),
+            expected: "a table",
+        })?;
         // include_dir is compatible with both string and vector types
-        let include_dir = if let Some(value) = target_tb.get("include_dir") {
-            match value {
-                Value::String(_s) => vec![parse_cfg_string(target_tb, "include_dir", "./")],
-                Value::Array(_arr) => parse_cfg_vector(target_tb, "include_dir"),
-                _ => {
-                    log(LogLevel::Error, "Invalid include_dir field");
-                    std::process::exit(1);
-                }
+        let include_dir = match target_tb.get("include_dir") {
+            Some(Value::String(_)) => vec![parse_cfg_string(target_tb, "include_dir", "./")?],
+            Some(Value::Array(_)) => parse_cfg_vector(target_tb, "include_dir")?,
+            Some(_) => {
+                return Err(ConfigError::WrongType {
+                    field: "include_dir".to_string(),
+                    expected: "a string or an array",
+                })
             }
-        } else {
-            vec!["./".to_owned()]
+            None => vec!["./".to_owned()],
+        };
+        // src is a single root or an array of roots
+        let src = match target_tb.get("src") {
+            Some(Value::Array(_)) => parse_cfg_vector(target_tb, "src")?,
+            _ => vec![parse_cfg_string(target_tb, "src", "")?],
         };
+        let name = parse_cfg_string(target_tb, "name", "")?;
+        let typ = parse_cfg_string(target_tb, "type", "")?
+            .parse::<TargetType>()
+            .map_err(|message| ConfigError::InvalidValue {
+                field: format!("Target \"{}\"", name),
+                message,
+            })?;
+        let strip = parse_cfg_string(target_tb, "strip", "auto")?;
+        if !["auto", "always", "never"].contains(&strip.as_str()) {
+            return Err(ConfigError::InvalidValue {
+                field: format!("Target \"{}\"", name),
+                message: format!(
+                    "strip must be \"auto\", \"always\" or \"never\", found \"{}\"",
+                    strip
+                ),
+            });
+        }
         let target_config = TargetConfig {
-            name: parse_cfg_string(target_tb, "name", ""),
-            src: parse_cfg_string(target_tb, "src", ""),
-            src_only: parse_cfg_vector(target_tb, "src_only"),
-            src_exclude: parse_cfg_vector(target_tb, "src_exclude"),
+            name,
+            src,
+            src_only: parse_cfg_vector(target_tb, "src_only")?,
+            src_exclude: parse_cfg_vector(target_tb, "src_exclude")?,
             include_dir,
-            typ: parse_cfg_string(target_tb, "type", ""),
-            cflags: parse_cfg_string(target_tb, "cflags", ""),
-            archive: parse_cfg_string(target_tb, "archive", ""),
-            linker: parse_cfg_string(target_tb, "linker", ""),
-            ldflags: parse_cfg_string(target_tb, "ldflags", ""),
-            deps: parse_cfg_vector(target_tb, "deps"),
+            typ,
+            cflags: parse_cfg_string(target_tb, "cflags", "")?,
+            defines: parse_cfg_vector(target_tb, "defines")?,
+            cstd: parse_cfg_string(target_tb, "cstd", "")?,
+            cppstd: parse_cfg_string(target_tb, "cppstd", "")?,
+            archive: parse_cfg_string(target_tb, "archive", "")?,
+            linker: parse_cfg_string(target_tb, "linker", "")?,
+            ldflags: parse_cfg_string(target_tb, "ldflags", "")?,
+            libs: parse_cfg_vector(target_tb, "libs")?,
+            lib_dirs: parse_cfg_vector(target_tb, "lib_dirs")?,
+            deps: parse_cfg_vector(target_tb, "deps")?,
+            emit_map: parse_cfg_bool(target_tb, "emit_map", false)?,
+            emit_asm: parse_cfg_bool(target_tb, "emit_asm", false)?,
+            strip,
+            post_build: parse_cfg_string(target_tb, "post_build", "")?,
+            pre_build: parse_cfg_string(target_tb, "pre_build", "")?,
+            pre_build_inputs: parse_cfg_vector(target_tb, "pre_build_inputs")?,
+            pch: parse_cfg_string(target_tb, "pch", "")?,
         };
-        if target_config.typ != "exe"
-            && target_config.typ != "dll"
-            && target_config.typ != "static"
-            && target_config.typ != "object"
-        {
-            log(LogLevel::Error, "Type must be exe, dll, object or static");
-            std::process::exit(1);
-        }
         tgts.push(target_config);
     }
     if tgts.is_empty() {
-        log(LogLevel::Error, "No targets found");
-        std::process::exit(1);
+        return Err(ConfigError::NoTargets);
     }
 
     // Checks for duplicate target names
     let mut names_set = HashSet::new();
     for target in &tgts {
         if !names_set.insert(&target.name) {
-            log(
-                LogLevel::Error,
-                &format!("Duplicate target names found: {}", target.name),
-            );
-            std::process::exit(1);
+            return Err(ConfigError::DuplicateTarget(target.name.clone()));
         }
     }
 
@@ -556,7 +1231,7 @@ fn parse_targets(config: &Table, check_dup_src: bool) -> Vec<TargetConfig> {
             "Checking for duplicate srcs in all targets...",
         );
         for target in &tgts {
-            target.check_duplicate_srcs();
+            target.check_duplicate_srcs()?;
         }
     }
 
@@ -564,151 +1239,915 @@ fn parse_targets(config: &Table, check_dup_src: bool) -> Vec<TargetConfig> {
 }
 
 /// Parses the platform configuration
-fn parse_platform(config: &Table) -> PlatformConfig {
+fn parse_platform(config: &Table) -> Result<PlatformConfig, ConfigError> {
     let empty_platform = Value::Table(toml::map::Map::default());
     let platform = config.get("platform").unwrap_or(&empty_platform);
-    if let Some(platform_table) = platform.as_table() {
-        let name = parse_cfg_string(platform_table, "name", "x86_64-qemu-q35");
-        let arch = name.split('-').next().unwrap_or("x86_64").to_string();
-        let cross_compile = format!("{}-linux-musl-", arch);
-        let target = match &arch[..] {
-            "x86_64" => "x86_64-unknown-none".to_string(),
-            "riscv64" => "riscv64gc-unknown-none-elf".to_string(),
-            "aarch64" => "aarch64-unknown-none-softfloat".to_string(),
-            _ => {
-                log(
-                    LogLevel::Error,
-                    "\"ARCH\" must be one of \"x86_64\", \"riscv64\", or \"aarch64\"",
-                );
-                std::process::exit(1);
-            }
-        };
-        let smp = parse_cfg_string(platform_table, "smp", "1");
-        let mode = parse_cfg_string(platform_table, "mode", "");
-        let log = parse_cfg_string(platform_table, "log", "warn");
-        let v = parse_cfg_string(platform_table, "v", "");
-        // determine whether enable qemu
-        let qemu: QemuConfig = if name.split('-').any(|s| s == "qemu") {
-            parse_qemu(&arch, platform_table)
-        } else {
-            QemuConfig::default()
-        };
-        PlatformConfig {
-            name,
-            arch,
-            cross_compile,
-            target,
-            smp,
-            mode,
-            log,
-            v,
-            qemu,
-        }
-    } else {
-        log(LogLevel::Error, "Platform is not a table");
-        std::process::exit(1);
+    let platform_table = platform.as_table().ok_or_else(|| ConfigError::WrongType {
+        field: "platform".to_string(),
+        expected: "a table",
+    })?;
+    let name = parse_cfg_string(platform_table, "name", "x86_64-qemu-q35")?;
+    let arch = name.split('-').next().unwrap_or("x86_64").to_string();
+    let cross_compile = parse_cfg_string(
+        platform_table,
+        "cross_compile",
+        &format!("{}-linux-musl-", arch),
+    )?;
+    let target = match &arch[..] {
+        "x86_64" => "x86_64-unknown-none".to_string(),
+        "riscv64" => "riscv64gc-unknown-none-elf".to_string(),
+        "aarch64" => "aarch64-unknown-none-softfloat".to_string(),
+        _ => return Err(ConfigError::UnsupportedArch(arch)),
+    };
+    let smp = parse_cfg_string(platform_table, "smp", "1")?;
+    if !smp.parse::<u32>().is_ok_and(|cpus| cpus > 0) {
+        return Err(ConfigError::InvalidValue {
+            field: "platform.smp".to_string(),
+            message: format!(
+                "the number of CPUs must be a positive integer, found \"{}\"",
+                smp
+            ),
+        });
     }
+    let mode = parse_cfg_string(platform_table, "mode", "")?;
+    let log = parse_cfg_string(platform_table, "log", "warn")?;
+    let v = parse_cfg_string(platform_table, "v", "")?;
+    if !["", "1", "2"].contains(&v.as_str()) {
+        return Err(ConfigError::InvalidValue {
+            field: "platform.v".to_string(),
+            message: format!(
+                "cargo verbosity must be \"\", \"1\" or \"2\", found \"{}\"",
+                v
+            ),
+        });
+    }
+    let sysroot = parse_cfg_string(platform_table, "sysroot", "")?;
+    let extra_includes = parse_cfg_vector(platform_table, "extra_includes")?;
+    let extra_lib_dirs = parse_cfg_vector(platform_table, "extra_lib_dirs")?;
+    // determine whether enable qemu
+    let qemu: QemuConfig = if name.split('-').any(|s| s == "qemu") {
+        parse_qemu(&arch, platform_table)?
+    } else {
+        QemuConfig::default()
+    };
+
+    Ok(PlatformConfig {
+        name,
+        arch,
+        cross_compile,
+        target,
+        smp,
+        mode,
+        log,
+        v,
+        sysroot,
+        extra_includes,
+        extra_lib_dirs,
+        qemu,
+    })
 }
 
 /// Parses the qemu configuration
-fn parse_qemu(arch: &str, config: &Table) -> QemuConfig {
+fn parse_qemu(arch: &str, config: &Table) -> Result<QemuConfig, ConfigError> {
     let empty_qemu = Value::Table(toml::map::Map::default());
     let qemu = config.get("qemu").unwrap_or(&empty_qemu);
-    if let Some(qemu_table) = qemu.as_table() {
-        let debug = parse_cfg_string(qemu_table, "debug", "n");
-        let blk = parse_cfg_string(qemu_table, "blk", "n");
-        let net = parse_cfg_string(qemu_table, "net", "n");
-        let graphic = parse_cfg_string(qemu_table, "graphic", "n");
-        let bus = match arch {
-            "x86_64" => "pci".to_string(),
-            _ => "mmio".to_string(),
-        };
-        let disk_img = parse_cfg_string(qemu_table, "disk_img", "disk.img");
-        let v9p = parse_cfg_string(qemu_table, "v9p", "n");
-        let v9p_path = parse_cfg_string(qemu_table, "v9p_path", "./");
-        let accel_pre = match Command::new("uname").arg("-r").output() {
-            Ok(output) => {
-                let kernel_version = String::from_utf8_lossy(&output.stdout).to_lowercase();
-                if kernel_version.contains("-microsoft") {
-                    "n"
-                } else {
-                    "y"
-                }
-            }
-            Err(_) => {
-                log(LogLevel::Error, "Failed to execute command");
-                std::process::exit(1);
+    let qemu_table = qemu.as_table().ok_or_else(|| ConfigError::WrongType {
+        field: "qemu".to_string(),
+        expected: "a table",
+    })?;
+    let debug = parse_cfg_string(qemu_table, "debug", "n")?;
+    let blk = parse_cfg_string(qemu_table, "blk", "n")?;
+    let net = parse_cfg_string(qemu_table, "net", "n")?;
+    let graphic = parse_cfg_string(qemu_table, "graphic", "n")?;
+    let bus = match arch {
+        "x86_64" => "pci".to_string(),
+        _ => "mmio".to_string(),
+    };
+    let disk_img = parse_cfg_string(qemu_table, "disk_img", "disk.img")?;
+    let v9p = parse_cfg_string(qemu_table, "v9p", "n")?;
+    let v9p_path = parse_cfg_string(qemu_table, "v9p_path", "./")?;
+    let v9p_shares = parse_v9p_shares(qemu_table, &v9p_path)?;
+    let accel_pre = match Command::new("uname").arg("-r").output() {
+        Ok(output) => {
+            let kernel_version = String::from_utf8_lossy(&output.stdout).to_lowercase();
+            if kernel_version.contains("-microsoft") {
+                "n"
+            } else {
+                "y"
             }
-        };
-        let accel = match arch {
-            "x86_64" => accel_pre.to_string(),
-            _ => "n".to_string(),
-        };
-        let qemu_log = parse_cfg_string(qemu_table, "qemu_log", "n");
-        let net_dump = parse_cfg_string(qemu_table, "net_dump", "n");
-        let net_dev = parse_cfg_string(qemu_table, "net_dev", "user");
-        let ip = parse_cfg_string(qemu_table, "ip", "10.0.2.15");
-        let gw = parse_cfg_string(qemu_table, "gw", "10.0.2.2");
-        let args = parse_cfg_string(qemu_table, "args", "");
-        let envs = parse_cfg_string(qemu_table, "envs", "");
-        QemuConfig {
-            debug,
-            blk,
-            net,
-            graphic,
-            bus,
-            disk_img,
-            v9p,
-            v9p_path,
-            accel,
-            qemu_log,
-            net_dump,
-            net_dev,
-            ip,
-            gw,
-            args,
-            envs,
         }
+        Err(_) => {
+            log(
+                LogLevel::Warn,
+                "Failed to execute uname, disabling qemu acceleration",
+            );
+            "n"
+        }
+    };
+    let accel = match arch {
+        "x86_64" => accel_pre.to_string(),
+        _ => "n".to_string(),
+    };
+    let qemu_log = parse_cfg_string(qemu_table, "qemu_log", "n")?;
+    let net_dump = parse_cfg_string(qemu_table, "net_dump", "n")?;
+    let net_dev = parse_cfg_string(qemu_table, "net_dev", "user")?;
+    let hostfwd = if qemu_table.contains_key("hostfwd") {
+        parse_cfg_vector(qemu_table, "hostfwd")?
     } else {
-        log(LogLevel::Error, "Qemu is not a table");
-        std::process::exit(1);
+        vec!["tcp::5555-:5555".to_string(), "udp::5555-:5555".to_string()]
+    };
+    if let Some(fwd) = hostfwd.iter().find(|fwd| !is_hostfwd(fwd)) {
+        return Err(ConfigError::InvalidValue {
+            field: "qemu.hostfwd".to_string(),
+            message: format!(
+                "\"{}\" is not of the form [tcp|udp]:[hostaddr]:hostport-[guestaddr]:guestport",
+                fwd
+            ),
+        });
+    }
+    let tap_ifname = parse_cfg_string(qemu_table, "tap_ifname", "tap0")?;
+    let bridge = parse_cfg_string(qemu_table, "bridge", "")?;
+    let mac_addr = parse_cfg_string(qemu_table, "mac_addr", "")?;
+    let is_mac = mac_addr.split(':').count() == 6
+        && mac_addr
+            .split(':')
+            .all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()));
+    if !mac_addr.is_empty() && !is_mac {
+        return Err(ConfigError::InvalidValue {
+            field: "qemu.mac_addr".to_string(),
+            message: format!(
+                "\"{}\" is not a MAC address like 52:54:00:12:34:56",
+                mac_addr
+            ),
+        });
     }
+    let ip = parse_cfg_string(qemu_table, "ip", "10.0.2.15")?;
+    let gw = parse_cfg_string(qemu_table, "gw", "10.0.2.2")?;
+    let args = parse_cfg_string(qemu_table, "args", "")?;
+    let envs = parse_cfg_string(qemu_table, "envs", "")?;
+    let qemu_bin = parse_cfg_string(qemu_table, "qemu_bin", "")?;
+    let serial = parse_cfg_string(qemu_table, "serial", "")?;
+    let monitor = parse_cfg_string(qemu_table, "monitor", "")?;
+
+    Ok(QemuConfig {
+        debug,
+        blk,
+        net,
+        graphic,
+        bus,
+        disk_img,
+        v9p,
+        v9p_path,
+        v9p_shares,
+        accel,
+        qemu_log,
+        net_dump,
+        net_dev,
+        hostfwd,
+        tap_ifname,
+        bridge,
+        mac_addr,
+        ip,
+        gw,
+        args,
+        envs,
+        qemu_bin,
+        serial,
+        monitor,
+    })
 }
 
 /// Parses the configuration field of the string type
-fn parse_cfg_string(config: &Table, field: &str, default: &str) -> String {
-    let default_string = Value::String(default.to_string());
-    config
-        .get(field)
-        .unwrap_or(&default_string)
-        .as_str()
-        .unwrap_or_else(|| {
-            log(LogLevel::Error, &format!("{} is not a string", field));
-            std::process::exit(1);
-        })
-        .to_string()
+fn parse_cfg_string(config: &Table, field: &str, default: &str) -> Result<String, ConfigError> {
+    match config.get(field) {
+        None => Ok(default.to_string()),
+        Some(value) => {
+            value
+                .as_str()
+                .map(|s| s.to_string())
+                .ok_or_else(|| ConfigError::WrongType {
+                    field: field.to_string(),
+                    expected: "a string",
+                })
+        }
+    }
+}
+
+/// Parses the configuration field of the bool type
+fn parse_cfg_bool(config: &Table, field: &str, default: bool) -> Result<bool, ConfigError> {
+    match config.get(field) {
+        None => Ok(default),
+        Some(value) => value.as_bool().ok_or_else(|| ConfigError::WrongType {
+            field: field.to_string(),
+            expected: "a boolean",
+        }),
+    }
+}
+
+/// Parses the 9p shares, defaulting to a single `rootfs` share of `v9p_path`
+fn parse_v9p_shares(qemu_table: &Table, v9p_path: &str) -> Result<Vec<V9pShare>, ConfigError> {
+    let Some(value) = qemu_table.get("v9p_shares") else {
+        return Ok(vec![V9pShare {
+            path: v9p_path.to_string(),
+            mount_tag: "rootfs".to_string(),
+            security_model: "none".to_string(),
+        }]);
+    };
+    let array = value.as_array().ok_or_else(|| ConfigError::WrongType {
+        field: "v9p_shares".to_string(),
+        expected: "an array",
+    })?;
+    let mut shares: Vec<V9pShare> = Vec::new();
+    for share in array {
+        let share_tb = share.as_table().ok_or_else(|| ConfigError::WrongType {
+            field: "Each element of v9p_shares".to_string(),
+            expected: "a table",
+        })?;
+        let share = V9pShare {
+            path: parse_cfg_string(share_tb, "path", "")?,
+            mount_tag: parse_cfg_string(share_tb, "mount_tag", "")?,
+            security_model: parse_cfg_string(share_tb, "security_model", "none")?,
+        };
+        if share.path.is_empty() || share.mount_tag.is_empty() {
+            return Err(ConfigError::Missing(
+                "path or mount_tag of a v9p_shares entry".to_string(),
+            ));
+        }
+        let security_models = [
+            "none",
+            "passthrough",
+            "mapped",
+            "mapped-xattr",
+            "mapped-file",
+        ];
+        if !security_models.contains(&share.security_model.as_str()) {
+            return Err(ConfigError::InvalidValue {
+                field: "qemu.v9p_shares.security_model".to_string(),
+                message: format!(
+                    "must be one of {}, found \"{}\"",
+                    security_models.join(", "),
+                    share.security_model
+                ),
+            });
+        }
+        if shares.iter().any(|s| s.mount_tag == share.mount_tag) {
+            return Err(ConfigError::InvalidValue {
+                field: "qemu.v9p_shares".to_string(),
+                message: format!(
+                    "mount_tag \"{}\" is used by several shares",
+                    share.mount_tag
+                ),
+            });
+        }
+        shares.push(share);
+    }
+    Ok(shares)
+}
+
+/// Returns true if the port forward is of the form `[tcp|udp]:[hostaddr]:hostport-[guestaddr]:guestport`
+fn is_hostfwd(fwd: &str) -> bool {
+    let is_port = |addr: &str| {
+        addr.rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+    };
+    match fwd.split_once(':') {
+        Some((proto, rest)) if ["", "tcp", "udp"].contains(&proto) => rest
+            .split_once('-')
+            .is_some_and(|(host, guest)| is_port(host) && is_port(guest)),
+        _ => false,
+    }
 }
 
 /// Parses the configuration field of the vector type
-fn parse_cfg_vector(config: &Table, field: &str) -> Vec<String> {
-    let empty_vector = Value::Array(Vec::new());
-    config
-        .get(field)
-        .unwrap_or(&empty_vector)
-        .as_array()
-        .unwrap_or_else(|| {
-            log(LogLevel::Error, &format!("{} is not an array", field));
-            std::process::exit(1);
-        })
+fn parse_cfg_vector(config: &Table, field: &str) -> Result<Vec<String>, ConfigError> {
+    let Some(value) = config.get(field) else {
+        return Ok(Vec::new());
+    };
+    let array = value.as_array().ok_or_else(|| ConfigError::WrongType {
+        field: field.to_string(),
+        expected: "an array",
+    })?;
+    array
         .iter()
         .map(|value| {
             value
                 .as_str()
-                .unwrap_or_else(|| {
-                    log(LogLevel::Error, &format!("{} elements are strings", field));
-                    std::process::exit(1);
+                .map(|s| s.to_string())
+                .ok_or_else(|| ConfigError::WrongType {
+                    field: format!("Each element of {}", field),
+                    expected: "a string",
                 })
-                .to_string()
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_type_suggests_aliases() {
+        assert_eq!("dll".parse::<TargetType>(), Ok(TargetType::Dll));
+        for (typ, suggestion) in [
+            ("shared", "dll"),
+            ("SO", "dll"),
+            ("executable", "exe"),
+            ("lib", "static"),
+            ("statik", "static"),
+        ] {
+            let err = typ.parse::<TargetType>().unwrap_err();
+            assert!(
+                err.contains(&format!("did you mean \"{}\"?", suggestion)),
+                "{}",
+                err
+            );
+            assert!(err.contains("Valid types are: exe, dll"), "{}", err);
+        }
+        let err = "library-thing".parse::<TargetType>().unwrap_err();
+        assert!(!err.contains("did you mean"), "{}", err);
+    }
+
+    /// Returns a platform of the given architecture with one cpu
+    fn platform(arch: &str) -> PlatformConfig {
+        PlatformConfig {
+            arch: arch.to_string(),
+            smp: "1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a qemu config on the given bus with the given devices set to "y", the others "n"
+    fn qemu(bus: &str, devices: &[&str]) -> QemuConfig {
+        let mut qemu = QemuConfig {
+            bus: bus.to_string(),
+            blk: "n".to_string(),
+            net: "n".to_string(),
+            graphic: "n".to_string(),
+            net_dev: "user".to_string(),
+            ..Default::default()
+        };
+        for device in devices {
+            match *device {
+                "blk" => qemu.blk = "y".to_string(),
+                "net" => qemu.net = "y".to_string(),
+                "graphic" => qemu.graphic = "y".to_string(),
+                _ => unreachable!(),
+            }
+        }
+        qemu
+    }
+
+    /// Returns true if `args` contains `expected` as consecutive args
+    fn has_args(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len())
+            .any(|window| window == expected)
+    }
+
+    #[test]
+    fn qemu_paths_x86_64() {
+        let (args, debug_args) = qemu("pci", &["blk"])
+            .config_qemu_paths(&platform("x86_64"), "app.elf", "app.bin")
+            .unwrap();
+        assert_eq!(args[0], "qemu-system-x86_64");
+        assert!(has_args(&args, &["-machine", "q35", "-kernel", "app.elf"]));
+        assert!(has_args(&args, &["-device", "virtio-blk-pci,drive=disk0"]));
+        assert!(!args.contains(&"app.bin".to_string()));
+        assert_eq!(debug_args[..args.len()], args[..]);
+        assert_eq!(debug_args[args.len()..], ["-s", "-S"]);
+    }
+
+    #[test]
+    fn qemu_paths_riscv64() {
+        let (args, _) = qemu("mmio", &["net"])
+            .config_qemu_paths(&platform("riscv64"), "app.elf", "app.bin")
+            .unwrap();
+        assert_eq!(args[0], "qemu-system-riscv64");
+        assert!(has_args(
+            &args,
+            &["-machine", "virt", "-bios", "default", "-kernel", "app.bin"]
+        ));
+        assert!(has_args(
+            &args,
+            &["-device", "virtio-net-device,netdev=net0"]
+        ));
+        assert!(!args.contains(&"app.elf".to_string()));
+    }
+
+    #[test]
+    fn qemu_paths_aarch64() {
+        let (args, _) = qemu("mmio", &[])
+            .config_qemu_paths(&platform("aarch64"), "app.elf", "app.bin")
+            .unwrap();
+        assert_eq!(args[0], "qemu-system-aarch64");
+        assert!(has_args(
+            &args,
+            &[
+                "-cpu",
+                "cortex-a72",
+                "-machine",
+                "virt",
+                "-kernel",
+                "app.bin"
+            ]
+        ));
+        assert!(args.contains(&"-nographic".to_string()));
+    }
+
+    #[test]
+    fn qemu_paths_reject_unsupported_values() {
+        let result = qemu("isa", &[]).config_qemu_paths(&platform("x86_64"), "a.elf", "a.bin");
+        assert!(matches!(
+            result,
+            Err(ConfigError::InvalidValue { ref field, .. }) if field == "qemu.bus"
+        ));
+        let result = qemu("mmio", &[]).config_qemu_paths(&platform("mips"), "a.elf", "a.bin");
+        assert!(matches!(result, Err(ConfigError::UnsupportedArch(ref arch)) if arch == "mips"));
+        let mut net = qemu("mmio", &["net"]);
+        net.net_dev = "vde".to_string();
+        let result = net.config_qemu_paths(&platform("riscv64"), "a.elf", "a.bin");
+        assert!(matches!(
+            result,
+            Err(ConfigError::InvalidValue { ref field, .. }) if field == "qemu.net_dev"
+        ));
+    }
+
+    #[test]
+    fn qemu_bin_replaces_the_qemu_executable() {
+        let mut qemu = qemu("mmio", &[]);
+        qemu.qemu_bin = "/opt/qemu/bin/qemu-system-riscv64".to_string();
+        let (args, debug_args) = qemu
+            .config_qemu_paths(&platform("riscv64"), "app.elf", "app.bin")
+            .unwrap();
+        assert_eq!(args[0], "/opt/qemu/bin/qemu-system-riscv64");
+        assert_eq!(debug_args[0], "/opt/qemu/bin/qemu-system-riscv64");
+        assert_eq!(
+            args.iter()
+                .filter(|arg| arg.starts_with("qemu-system"))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn qemu_serial_and_monitor_replace_the_defaults() {
+        let paths = |qemu: &QemuConfig| {
+            qemu.config_qemu_paths(&platform("x86_64"), "app.elf", "app.bin")
+                .unwrap()
+                .0
+        };
+        let telnet = "telnet:127.0.0.1:4321,server,nowait";
+
+        let mut graphic = qemu("pci", &["graphic"]);
+        assert!(has_args(&paths(&graphic), &["-serial", "mon:stdio"]));
+        graphic.serial = telnet.to_string();
+        let args = paths(&graphic);
+        assert!(has_args(&args, &["-serial", telnet]));
+        assert!(!args.contains(&"mon:stdio".to_string()));
+
+        let mut nographic = qemu("pci", &[]);
+        let args = paths(&nographic);
+        assert!(args.contains(&"-nographic".to_string()));
+        assert!(!args.contains(&"-serial".to_string()) && !args.contains(&"-monitor".to_string()));
+        nographic.serial = telnet.to_string();
+        nographic.monitor = "unix:/tmp/mon.sock,server,nowait".to_string();
+        let args = paths(&nographic);
+        assert!(has_args(&args, &["-serial", telnet]));
+        assert!(has_args(
+            &args,
+            &["-monitor", "unix:/tmp/mon.sock,server,nowait"]
+        ));
+    }
+
+    /// Parses the `[platform]` table of an `[os]` section given as TOML
+    fn parse_platform_toml(os: &str) -> Result<PlatformConfig, ConfigError> {
+        parse_platform(&toml::from_str(os).unwrap())
+    }
+
+    #[test]
+    fn smp_must_be_a_positive_integer() {
+        let platform = parse_platform_toml("[platform]\nsmp = \"4\"\n").unwrap();
+        assert_eq!(platform.smp, "4");
+        assert_eq!(parse_platform_toml("").unwrap().smp, "1");
+        for smp in ["tow", "0", "-1", "", "2.5"] {
+            let result = parse_platform_toml(&format!("[platform]\nsmp = \"{}\"\n", smp));
+            assert!(
+                matches!(
+                    result,
+                    Err(ConfigError::InvalidValue { ref field, ref message })
+                        if field == "platform.smp" && message.contains(&format!("\"{}\"", smp))
+                ),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    /// Returns the QEMU args of an x86_64 platform with the given `[platform.qemu]` lines
+    fn qemu_args(qemu: &str) -> Result<Vec<String>, ConfigError> {
+        let platform = parse_platform_toml(&format!(
+            "[platform]\nname = \"x86_64-qemu-q35\"\n[platform.qemu]\n{}",
+            qemu
+        ))?;
+        let (args, _) = platform
+            .qemu
+            .config_qemu_paths(&platform, "app.elf", "app.bin")?;
+        Ok(args)
+    }
+
+    /// Returns the QEMU args like `qemu_args`, with networking enabled
+    fn net_args(qemu: &str) -> Result<Vec<String>, ConfigError> {
+        qemu_args(&format!("net = \"y\"\n{}", qemu))
+    }
+
+    #[test]
+    fn hostfwd_sets_the_user_netdev_forwards() {
+        let args = net_args("").unwrap();
+        assert!(has_args(
+            &args,
+            &[
+                "-netdev",
+                "user,id=net0,hostfwd=tcp::5555-:5555,hostfwd=udp::5555-:5555"
+            ]
+        ));
+        let args = net_args("hostfwd = [\"tcp::8080-:80\", \"udp:127.0.0.1:5353-10.0.2.15:53\"]\n")
+            .unwrap();
+        assert!(has_args(
+            &args,
+            &[
+                "-netdev",
+                "user,id=net0,hostfwd=tcp::8080-:80,hostfwd=udp:127.0.0.1:5353-10.0.2.15:53"
+            ]
+        ));
+        let args = net_args("hostfwd = []\n").unwrap();
+        assert!(has_args(&args, &["-netdev", "user,id=net0"]));
+
+        for fwd in ["tcp:8080:80", "sctp::1-:2", "tcp::99999-:80", "8080-80"] {
+            let result = net_args(&format!("hostfwd = [\"{}\"]\n", fwd));
+            assert!(
+                matches!(
+                    result,
+                    Err(ConfigError::InvalidValue { ref field, ref message })
+                        if field == "qemu.hostfwd" && message.contains(fwd)
+                ),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn tap_ifname_bridge_and_mac_reach_the_net_args() {
+        let args = net_args("net_dev = \"tap\"\n").unwrap();
+        assert!(has_args(
+            &args,
+            &["-netdev", "tap,id=net0,ifname=tap0,script=no,downscript=no"]
+        ));
+        assert!(has_args(&args, &["-device", "virtio-net-pci,netdev=net0"]));
+
+        let args = net_args(
+            "net_dev = \"tap\"\ntap_ifname = \"tap3\"\nmac_addr = \"52:54:00:ab:cd:0f\"\n",
+        )
+        .unwrap();
+        assert!(has_args(
+            &args,
+            &["-netdev", "tap,id=net0,ifname=tap3,script=no,downscript=no"]
+        ));
+        assert!(has_args(
+            &args,
+            &[
+                "-device",
+                "virtio-net-pci,netdev=net0,mac=52:54:00:ab:cd:0f"
+            ]
+        ));
+
+        let args = net_args("net_dev = \"tap\"\nbridge = \"br0\"\n").unwrap();
+        assert!(has_args(&args, &["-netdev", "tap,id=net0,br=br0"]));
+
+        for mac in ["52:54:00:ab:cd", "52-54-00-ab-cd-0f", "52:54:00:ab:cd:0g"] {
+            let result = net_args(&format!("mac_addr = \"{}\"\n", mac));
+            assert!(
+                matches!(
+                    result,
+                    Err(ConfigError::InvalidValue { ref field, ref message })
+                        if field == "qemu.mac_addr" && message.contains(mac)
+                ),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn v9p_shares_get_numbered_devices() {
+        let args = qemu_args("v9p = \"y\"\nv9p_path = \"./share\"\n").unwrap();
+        assert!(has_args(
+            &args,
+            &[
+                "-fsdev",
+                "local,id=fsdev0,path=./share,security_model=none",
+                "-device",
+                "virtio-9p-pci,fsdev=fsdev0,mount_tag=rootfs"
+            ]
+        ));
+        assert!(!args.iter().any(|arg| arg.contains("fsdev1")));
+
+        let shares = "v9p = \"y\"\nv9p_shares = [\n\
+                      { path = \"./root\", mount_tag = \"root\" },\n\
+                      { path = \"/srv/data\", mount_tag = \"data\", security_model = \"mapped-xattr\" },\n]\n";
+        let args = qemu_args(shares).unwrap();
+        assert!(has_args(
+            &args,
+            &[
+                "-fsdev",
+                "local,id=fsdev0,path=./root,security_model=none",
+                "-device",
+                "virtio-9p-pci,fsdev=fsdev0,mount_tag=root",
+                "-fsdev",
+                "local,id=fsdev1,path=/srv/data,security_model=mapped-xattr",
+                "-device",
+                "virtio-9p-pci,fsdev=fsdev1,mount_tag=data"
+            ]
+        ));
+        // Without v9p the shares are not passed
+        let args = qemu_args(&shares.replace("v9p = \"y\"", "v9p = \"n\"")).unwrap();
+        assert!(!args.contains(&"-fsdev".to_string()));
+
+        let duplicate = shares.replace("\"data\"", "\"root\"");
+        assert!(matches!(
+            qemu_args(&duplicate),
+            Err(ConfigError::InvalidValue { ref message, .. }) if message.contains("root")
+        ));
+        let unknown_model = shares.replace("mapped-xattr", "open");
+        assert!(matches!(
+            qemu_args(&unknown_model),
+            Err(ConfigError::InvalidValue { ref message, .. }) if message.contains("open")
+        ));
+    }
+
+    #[test]
+    fn canonical_os_config_ignores_the_feature_order() {
+        let with_features = |features: &[&str]| OSConfig {
+            name: "ruxos".to_string(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            ulib: "ruxlibc".to_string(),
+            platform: platform("x86_64"),
+            ..Default::default()
+        };
+        let hash = |features: &[&str]| {
+            crate::hasher::Hasher::hash_string(&with_features(features).canonical_string())
+        };
+        assert_eq!(
+            with_features(&["fs", "alloc", "fd"]).canonical_string(),
+            with_features(&["alloc", "fd", "fs", "fd"]).canonical_string()
+        );
+        assert_eq!(hash(&["net", "alloc"]), hash(&["alloc", "net"]));
+        assert_ne!(hash(&["alloc"]), hash(&["alloc", "net"]));
+    }
+
+    /// A `[build]` section with just the compiler
+    const BUILD: &str = "[build]\ncompiler = \"gcc\"\n";
+
+    /// Returns a `[[targets]]` entry with the given name, type and deps
+    fn target(name: &str, typ: &str, deps: &str) -> String {
+        format!(
+            "\n[[targets]]\nname = \"{}\"\nsrc = \"./src\"\ntype = \"{}\"\ndeps = [{}]\n",
+            name, typ, deps
+        )
+    }
+
+    /// Parses `config` from a file in a temporary directory holding the given empty `files`
+    /// # Arguments
+    /// * `name` - Names the temporary directory, unique per test
+    /// * `config` - The contents of the config file
+    /// * `files` - Files created next to the config file, e.g. sources
+    fn parse(
+        name: &str,
+        config: &str,
+        files: &[&str],
+    ) -> Result<(BuildConfig, OSConfig, Vec<TargetConfig>), ConfigError> {
+        let dir =
+            std::env::temp_dir().join(format!("ruxgo-parser-{}-{}", name, std::process::id()));
+        for file in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            config.replace("./src", &dir.join("src").to_string_lossy()),
+        )
+        .unwrap();
+        let result = try_parse_config(&path.to_string_lossy(), !files.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    #[test]
+    fn config_errors_for_unreadable_files() {
+        let result = try_parse_config("/nonexistent/config.toml", false);
+        assert!(matches!(result, Err(ConfigError::Io { .. })));
+        let result = parse("toml", "[build\n", &[]);
+        assert!(matches!(result, Err(ConfigError::Toml { .. })));
+    }
+
+    #[test]
+    fn config_errors_for_missing_sections() {
+        let result = parse("no-build", &target("app", "exe", ""), &[]);
+        assert!(matches!(result, Err(ConfigError::Missing(ref field)) if field == "build"));
+        let result = parse(
+            "no-compiler",
+            &format!("[build]\n{}", target("app", "exe", "")),
+            &[],
+        );
+        assert!(matches!(result, Err(ConfigError::Missing(ref field)) if field == "compiler"));
+        let result = parse("no-targets", BUILD, &[]);
+        assert!(matches!(result, Err(ConfigError::Missing(ref field)) if field == "targets"));
+        let result = parse("empty-targets", &format!("targets = []\n{}", BUILD), &[]);
+        assert!(matches!(result, Err(ConfigError::NoTargets)));
+    }
+
+    #[test]
+    fn config_errors_for_bad_values() {
+        let result = parse("build-type", "build = 1\n", &[]);
+        assert!(matches!(
+            result,
+            Err(ConfigError::WrongType { ref field, expected: "a table" }) if field == "build"
+        ));
+        let result = parse(
+            "target-type",
+            &format!("{}{}", BUILD, target("app", "binary", "")),
+            &[],
+        );
+        assert!(matches!(
+            result,
+            Err(ConfigError::InvalidValue { ref field, .. }) if field == "Target \"app\""
+        ));
+        let os = "[os]\nname = \"ruxos\"\nservices = []\nulib = \"ruxlibc\"\n\n\
+                  [os.platform]\nname = \"mips-qemu-virt\"\n";
+        let result = parse(
+            "arch",
+            &format!("{}{}{}", BUILD, os, target("app", "exe", "")),
+            &[],
+        );
+        assert!(matches!(result, Err(ConfigError::UnsupportedArch(ref arch)) if arch == "mips"));
+    }
+
+    #[test]
+    fn musl_version_defaults_to_1_2_3() {
+        let os = "[os]\nname = \"ruxos\"\nservices = []\nulib = \"ruxmusl\"\n\n\
+                  [os.platform]\nname = \"x86_64-qemu-q35\"\n";
+        let (_, os_config, _) = parse(
+            "musl-default",
+            &format!("{}{}{}", BUILD, os, target("app", "exe", "")),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(os_config.musl_version, "1.2.3");
+        assert_eq!(os_config.musl_sha256, "");
+
+        let os = os.replace("ulib", "musl_version = \"1.2.5\"\nulib");
+        let (_, os_config, _) = parse(
+            "musl-version",
+            &format!("{}{}{}", BUILD, os, target("app", "exe", "")),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(os_config.musl_version, "1.2.5");
+    }
+
+    #[test]
+    fn duplicate_check_skips_filtered_sources() {
+        // Only src/core/main.c is built, the other main.c files are filtered out
+        let filters = "src_only = [\"core/\"]\nsrc_exclude = [\"core/skip\"]\n";
+        let result = parse(
+            "filtered-duplicates",
+            &format!("{}{}{}", BUILD, target("app", "exe", ""), filters),
+            &[
+                "src/core/main.c",
+                "src/other/main.c",
+                "src/core/skip/main.c",
+            ],
+        );
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        let result = parse(
+            "unfiltered-duplicates",
+            &format!("{}{}{}", BUILD, target("app", "exe", ""), filters),
+            &["src/core/main.c", "src/core/util/main.c"],
+        );
+        assert!(matches!(result, Err(ConfigError::DuplicateSources { .. })));
+    }
+
+    #[test]
+    fn cross_compile_prefixes_the_compiler() {
+        let os = "[os]\nname = \"ruxos\"\nservices = []\nulib = \"ruxlibc\"\n\n\
+                  [os.platform]\nname = \"riscv64-qemu-virt\"\n";
+        let (build_config, os_config, _) = parse(
+            "cross-default",
+            &format!("{}{}{}", BUILD, os, target("app", "exe", "")),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(os_config.platform.cross_compile, "riscv64-linux-musl-");
+        assert_eq!(
+            *build_config.compiler.read().unwrap(),
+            "riscv64-linux-musl-gcc"
+        );
+
+        let os = format!("{}cross_compile = \"riscv64-unknown-linux-gnu-\"\n", os);
+        let (build_config, os_config, _) = parse(
+            "cross-custom",
+            &format!("{}{}{}", BUILD, os, target("app", "exe", "")),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            os_config.platform.cross_compile,
+            "riscv64-unknown-linux-gnu-"
+        );
+        assert_eq!(
+            *build_config.compiler.read().unwrap(),
+            "riscv64-unknown-linux-gnu-gcc"
+        );
+    }
+
+    #[test]
+    fn ruxos_paths_follow_ruxos_dir() {
+        let os_config = OSConfig {
+            ruxos_dir: "/src/ruxos/".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            os_config.ruxos_path("modules/ruxhal/linker_x86_64-qemu-q35.lds"),
+            "/src/ruxos/modules/ruxhal/linker_x86_64-qemu-q35.lds"
+        );
+        assert_eq!(
+            os_config.ruxos_path("ulib/ruxlibc/include"),
+            "/src/ruxos/ulib/ruxlibc/include"
+        );
+    }
+
+    #[test]
+    fn config_errors_for_bad_targets() {
+        let result = parse(
+            "duplicate-target",
+            &format!(
+                "{}{}{}",
+                BUILD,
+                target("app", "exe", ""),
+                target("app", "static", "")
+            ),
+            &[],
+        );
+        assert!(matches!(result, Err(ConfigError::DuplicateTarget(ref name)) if name == "app"));
+        let result = parse(
+            "duplicate-sources",
+            &format!("{}{}", BUILD, target("app", "exe", "")),
+            &["src/a/main.c", "src/b/main.c"],
+        );
+        assert!(matches!(
+            result,
+            Err(ConfigError::DuplicateSources { ref target, ref files })
+                if target == "app" && files == &["main"]
+        ));
+        // Deps may name packages, so unknown ones are reported when the targets are built
+        let (_, _, targets) = parse(
+            "unknown-dependency",
+            &format!(
+                "{}{}{}",
+                BUILD,
+                target("util", "static", ""),
+                target("app", "exe", "\"utl\"")
+            ),
+            &[],
+        )
+        .unwrap();
+        let app = targets.iter().find(|target| target.name == "app").unwrap();
+        assert!(matches!(
+            &app.unresolved_deps(&targets)[..],
+            [ConfigError::UnknownDependency { target, dep, suggestion }]
+                if target == "app" && dep == "utl" && suggestion.as_deref() == Some("util")
+        ));
+        let result = parse(
+            "circular-dependency",
+            &format!(
+                "{}{}{}{}",
+                BUILD,
+                target("a", "static", "\"b\""),
+                target("b", "static", "\"a\""),
+                target("app", "exe", "\"a\"")
+            ),
+            &[],
+        );
+        match result {
+            Err(ConfigError::CircularDependency(cycle)) => {
+                assert_eq!(cycle.len(), 3, "{:?}", cycle);
+                assert_eq!(cycle.first(), cycle.last());
+                assert!(cycle.contains(&"a".to_string()) && cycle.contains(&"b".to_string()));
+            }
+            result => panic!("{:?}", result.map(|_| ())),
+        }
+    }
+}
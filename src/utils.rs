@@ -1,6 +1,10 @@
 //! This module contains various logging, environment config and features config.
 //! used by the ruxgo library
 
+pub mod diagnostics;
 pub mod env;
 pub mod features;
+pub mod license;
 pub mod log;
+pub mod suggest;
+pub mod tools;
@@ -0,0 +1,159 @@
+//! Diagnostics Module
+
+use crate::utils::log::{log_fields, log_format, LogFormat, LogLevel};
+use serde::Serialize;
+
+/// The severities reported by GCC and Clang, the first match on a line wins
+const SEVERITIES: [&str; 4] = ["fatal error", "error", "warning", "note"];
+
+/// A diagnostic parsed from the output of the compiler
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    /// Missing if the compiler was run with `-fno-show-column`
+    pub column: Option<u32>,
+    /// `error`, `warning` or `note`, a fatal error is reported as `error`
+    pub severity: String,
+    pub message: String,
+}
+
+/// Parses the `file:line:column: severity: message` lines of GCC and Clang output,
+/// skipping the lines that are not about a source location, like the code snippets
+/// # Arguments
+/// * `output` - The stderr of the compiler
+/// # Example
+/// ```
+/// # use ruxgo::utils::diagnostics::parse;
+/// let stderr = "src/main.c:3:12: error: use of undeclared identifier 'x'\n    3 |     return x;\n";
+/// let diagnostics = parse(stderr);
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].file, "src/main.c");
+/// assert_eq!(diagnostics[0].line, 3);
+/// assert_eq!(diagnostics[0].column, Some(12));
+/// assert_eq!(diagnostics[0].severity, "error");
+/// assert_eq!(diagnostics[0].message, "use of undeclared identifier 'x'");
+/// ```
+pub fn parse(output: &str) -> Vec<Diagnostic> {
+    output.lines().filter_map(parse_line).collect()
+}
+
+/// Parses a single diagnostic line, or returns None if it is not one
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    let (pos, severity) = SEVERITIES
+        .iter()
+        .filter_map(|severity| {
+            line.find(&format!(": {}: ", severity))
+                .map(|pos| (pos, *severity))
+        })
+        .min_by_key(|(pos, _)| *pos)?;
+    let location = &line[..pos];
+    let message = &line[pos + severity.len() + 4..];
+
+    // The file may contain colons itself, e.g. a drive letter on Windows
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?.parse::<u32>().ok()?;
+    let (file, line, column) = match parts.next()?.parse::<u32>() {
+        Ok(line) => (parts.next()?, line, Some(last)),
+        Err(_) => (location.rsplit_once(':')?.0, last, None),
+    };
+    Some(Diagnostic {
+        file: file.to_string(),
+        line,
+        column,
+        severity: severity.trim_start_matches("fatal ").to_string(),
+        message: message.trim().to_string(),
+    })
+}
+
+/// Logs each diagnostic of the compiler output with a `diagnostic` field when the log format
+/// is JSON, so that tools don't have to parse the raw stderr
+/// # Arguments
+/// * `output` - The stderr of the compiler
+pub fn log_diagnostics(output: &str) {
+    if log_format() != LogFormat::Json {
+        return;
+    }
+    for diagnostic in parse(output) {
+        let level = match diagnostic.severity.as_str() {
+            "error" => LogLevel::Error,
+            "warning" => LogLevel::Warn,
+            _ => LogLevel::Info,
+        };
+        let location = match diagnostic.column {
+            Some(column) => format!("{}:{}:{}", diagnostic.file, diagnostic.line, column),
+            None => format!("{}:{}", diagnostic.file, diagnostic.line),
+        };
+        log_fields(
+            level,
+            &format!("{}: {}", location, diagnostic.message),
+            serde_json::json!({ "diagnostic": diagnostic }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_without_column() {
+        let diagnostics = parse("src/main.c:7: error: expected ';' before '}' token\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file, "src/main.c");
+        assert_eq!(diagnostics[0].line, 7);
+        assert_eq!(diagnostics[0].column, None);
+        assert_eq!(diagnostics[0].message, "expected ';' before '}' token");
+    }
+
+    #[test]
+    fn diagnostics_severities() {
+        let stderr = "a.c:1:2: warning: unused variable 'x' [-Wunused-variable]\n\
+                      a.c:3:4: note: declared here\n\
+                      a.c:5:6: fatal error: b.h: No such file or directory\n";
+        let severities: Vec<_> = parse(stderr)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.severity, diagnostic.message))
+            .collect();
+        assert_eq!(
+            severities,
+            [
+                (
+                    1,
+                    "warning".to_string(),
+                    "unused variable 'x' [-Wunused-variable]".to_string()
+                ),
+                (3, "note".to_string(), "declared here".to_string()),
+                (
+                    5,
+                    "error".to_string(),
+                    "b.h: No such file or directory".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_windows_paths() {
+        let stderr = "C:\\src\\main.c:3:12: error: 'x' undeclared\n\
+                      C:\\src\\util.c:8: warning: implicit declaration of function 'f'\n";
+        let diagnostics = parse(stderr);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].file, "C:\\src\\main.c");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, Some(12)));
+        assert_eq!(diagnostics[1].file, "C:\\src\\util.c");
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (8, None));
+    }
+
+    #[test]
+    fn diagnostics_skip_other_lines() {
+        let stderr = "In file included from src/main.c:1:\n\
+                      src/main.c: In function 'main':\n\
+                      \x20   3 |     return x;\n\
+                      \x20     |            ^\n\
+                      collect2: error: ld returned 1 exit status\n\
+                      ld: warning: cannot find entry symbol _start\n\
+                      make: *** [all] Error 1\n";
+        assert_eq!(parse(stderr), []);
+    }
+}
@@ -1,10 +1,10 @@
 //! Environment Configuration
 
-use crate::parser::{OSConfig, PlatformConfig, QemuConfig};
+use crate::parser::{OSConfig, QemuConfig};
 
 // This function is used to configure environment variables
 pub fn config_env(os_config: &OSConfig) {
-    if os_config != &OSConfig::default() && os_config.platform != PlatformConfig::default() {
+    if os_config.has_platform() {
         std::env::set_var("RUX_ARCH", &os_config.platform.arch);
         std::env::set_var("RUX_PLATFORM", &os_config.platform.name);
         std::env::set_var("RUX_SMP", &os_config.platform.smp);
@@ -15,11 +15,24 @@ pub fn config_env(os_config: &OSConfig) {
             // ip and gw is for QEMU user netdev
             std::env::set_var("RUX_IP", &os_config.platform.qemu.ip);
             std::env::set_var("RUX_GW", &os_config.platform.qemu.gw);
+            // devices, "y" or "n", for the build of the OS and the app to adapt to
+            std::env::set_var("RUX_NET", &os_config.platform.qemu.net);
+            std::env::set_var("RUX_BLK", &os_config.platform.qemu.blk);
+            std::env::set_var("RUX_GRAPHIC", &os_config.platform.qemu.graphic);
             // v9p option
             if os_config.platform.qemu.v9p == "y" {
                 std::env::set_var("RUX_9P_ADDR", "127.0.0.1:564");
                 std::env::set_var("RUX_ANAME_9P", "./");
                 std::env::set_var("RUX_PROTOCOL_9P", "9P2000.L");
+                // mount tags of all the shares, the first one is the root
+                let tags: Vec<&str> = os_config
+                    .platform
+                    .qemu
+                    .v9p_shares
+                    .iter()
+                    .map(|share| share.mount_tag.as_str())
+                    .collect();
+                std::env::set_var("RUX_9P_TAGS", tags.join(","));
             }
         }
         // musl
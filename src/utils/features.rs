@@ -2,8 +2,54 @@
 
 use crate::parser::OSConfig;
 use crate::utils::log::{log, LogLevel};
+use crate::utils::suggest::closest_match;
+use itertools::Itertools;
 
-pub fn cfg_feat(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
+/// Features of the `ruxfeat` crate that can be enabled as services
+static RUX_FEATURES: [&str; 37] = [
+    "smp",
+    "fp_simd",
+    "irq",
+    "alloc",
+    "alloc-tlsf",
+    "alloc-slab",
+    "alloc-buddy",
+    "paging",
+    "tls",
+    "multitask",
+    "sched_fifo",
+    "sched_rr",
+    "sched_cfs",
+    "fs",
+    "blkfs",
+    "myfs",
+    "9pfs",
+    "net",
+    "signal",
+    "pipe",
+    "select",
+    "poll",
+    "epoll",
+    "fd",
+    "display",
+    "rtc",
+    "tty",
+    "virtio-9p",
+    "net-9p",
+    "random-hw",
+    "musl",
+    "virtio_console",
+    "bus-mmio",
+    "bus-pci",
+    "driver-ramdisk",
+    "driver-ixgbe",
+    "driver-bcm2835-sdhci",
+];
+
+/// Returns the features that belong to the ulib rather than to ruxfeat
+/// # Arguments
+/// * `ulib` - The ulib of the OS config, `ruxlibc` or `ruxmusl`
+pub fn lib_features(ulib: &str) -> Vec<&'static str> {
     let mut lib_features = vec![
         "fp_simd",
         "alloc",
@@ -18,11 +64,36 @@ pub fn cfg_feat(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
         "random-hw",
         "signal",
     ];
-    if os_config.ulib == "ruxmusl" {
+    if ulib == "ruxmusl" {
         lib_features.push("irq");
         lib_features.push("musl");
         lib_features.push("sched_rr");
     }
+    lib_features
+}
+
+/// Returns an error message for each service that is neither a ruxfeat nor a ulib feature
+/// # Arguments
+/// * `os_config` - The os configuration
+pub fn unknown_services(os_config: &OSConfig) -> Vec<String> {
+    let lib_features = lib_features(&os_config.ulib);
+    let known = || RUX_FEATURES.iter().chain(lib_features.iter()).copied();
+    os_config
+        .features
+        .iter()
+        .filter(|feat| !known().any(|known| known == feat.as_str()))
+        .map(|feat| {
+            let mut msg = format!("Unknown service \"{}\"", feat);
+            if let Some(suggestion) = closest_match(feat, known()) {
+                msg.push_str(&format!(", did you mean \"{}\"?", suggestion));
+            }
+            msg
+        })
+        .collect()
+}
+
+pub fn cfg_feat(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
+    let lib_features = lib_features(&os_config.ulib);
 
     let mut rux_feats = Vec::new();
     let mut lib_feats = Vec::new();
@@ -39,11 +110,13 @@ pub fn cfg_feat(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
             std::process::exit(1);
         }
     }
-    if os_config.platform.qemu.bus == "pci" {
-        rux_feats.push("bus-pci".to_string());
-    }
-    if os_config.platform.smp.parse::<i32>().unwrap_or(0) > 1 {
-        lib_feats.push("smp".to_string());
+    if os_config.default_features {
+        if os_config.platform.qemu.bus == "pci" {
+            rux_feats.push("bus-pci".to_string());
+        }
+        if os_config.platform.smp.parse::<i32>().unwrap_or(0) > 1 {
+            lib_feats.push("smp".to_string());
+        }
     }
 
     // get content of features
@@ -74,12 +147,16 @@ pub fn cfg_feat_addprefix(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
 
     // Add prefix
     let (rux_feats_pre, lib_feats_pre) = cfg_feat(os_config);
+    // A feature can be added by several config paths (e.g. `fd` by both `fs` and `net`),
+    // so keep only its first occurrence
     let rux_feats_final = rux_feats_pre
         .into_iter()
+        .unique()
         .map(|feat| format!("{}{}", rux_feat_prefix, feat))
         .collect::<Vec<String>>();
     let lib_feats_final = lib_feats_pre
         .into_iter()
+        .unique()
         .map(|feat| format!("{}{}", lib_feat_prefix, feat))
         .collect::<Vec<String>>();
     log(
@@ -93,3 +170,115 @@ pub fn cfg_feat_addprefix(os_config: &OSConfig) -> (Vec<String>, Vec<String>) {
 
     (rux_feats_final, lib_feats_final)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PlatformConfig;
+
+    /// Returns an OS config with the given ulib and services, logging at warn level
+    fn os_config(ulib: &str, services: &[&str]) -> OSConfig {
+        OSConfig {
+            name: "ruxos".to_string(),
+            features: services.iter().map(|s| s.to_string()).collect(),
+            ulib: ulib.to_string(),
+            platform: PlatformConfig {
+                smp: "1".to_string(),
+                log: "warn".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lib_features_depend_on_the_ulib() {
+        let ruxlibc = lib_features("ruxlibc");
+        assert_eq!(
+            ruxlibc,
+            [
+                "fp_simd",
+                "alloc",
+                "multitask",
+                "fs",
+                "net",
+                "fd",
+                "pipe",
+                "select",
+                "poll",
+                "epoll",
+                "random-hw",
+                "signal"
+            ]
+        );
+        // ruxmusl adds its own on top of the ruxlibc ones
+        let ruxmusl = lib_features("ruxmusl");
+        assert_eq!(ruxmusl[..ruxlibc.len()], ruxlibc[..]);
+        assert_eq!(ruxmusl[ruxlibc.len()..], ["irq", "musl", "sched_rr"]);
+    }
+
+    #[test]
+    fn services_are_split_between_ruxfeat_and_the_ulib() {
+        let services = ["alloc", "irq", "paging", "musl", "fs"];
+        let (rux_feats, lib_feats) = cfg_feat_addprefix(&os_config("ruxlibc", &services));
+        assert_eq!(
+            rux_feats,
+            [
+                "ruxfeat/log-level-warn",
+                "ruxfeat/irq",
+                "ruxfeat/paging",
+                "ruxfeat/musl"
+            ]
+        );
+        assert_eq!(lib_feats, ["ruxlibc/alloc", "ruxlibc/fs"]);
+
+        let (rux_feats, lib_feats) = cfg_feat_addprefix(&os_config("ruxmusl", &services));
+        assert_eq!(rux_feats, ["ruxfeat/log-level-warn", "ruxfeat/paging"]);
+        assert_eq!(
+            lib_feats,
+            ["ruxmusl/alloc", "ruxmusl/irq", "ruxmusl/musl", "ruxmusl/fs"]
+        );
+    }
+
+    #[test]
+    fn misspelled_services_are_named_with_a_suggestion() {
+        let config = os_config("ruxlibc", &["alloc", "multitsk", "paging", "9pfss"]);
+        assert_eq!(
+            unknown_services(&config),
+            [
+                "Unknown service \"multitsk\", did you mean \"multitask\"?",
+                "Unknown service \"9pfss\", did you mean \"9pfs\"?"
+            ]
+        );
+        assert!(unknown_services(&os_config("ruxmusl", &["musl", "sched_rr"])).is_empty());
+    }
+
+    #[test]
+    fn features_are_deduplicated_in_first_seen_order() {
+        let config = os_config(
+            "ruxlibc",
+            &["fs", "fd", "paging", "net", "fd", "paging", "fs"],
+        );
+        let (rux_feats, lib_feats) = cfg_feat_addprefix(&config);
+        assert_eq!(rux_feats, ["ruxfeat/log-level-warn", "ruxfeat/paging"]);
+        assert_eq!(lib_feats, ["ruxlibc/fs", "ruxlibc/fd", "ruxlibc/net"]);
+    }
+
+    #[test]
+    fn default_features_add_the_bus_and_smp() {
+        let mut config = os_config("ruxlibc", &["alloc"]);
+        config.platform.qemu.bus = "pci".to_string();
+        config.platform.smp = "2".to_string();
+
+        config.default_features = true;
+        let (rux_feats, lib_feats) = cfg_feat(&config);
+        assert_eq!(rux_feats, ["log-level-warn", "bus-pci"]);
+        assert_eq!(lib_feats, ["smp", "alloc"]);
+
+        // Only the listed services and the log level are left
+        config.default_features = false;
+        let (rux_feats, lib_feats) = cfg_feat_addprefix(&config);
+        assert_eq!(rux_feats, ["ruxfeat/log-level-warn"]);
+        assert_eq!(lib_feats, ["ruxlibc/alloc"]);
+    }
+}
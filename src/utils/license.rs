@@ -0,0 +1,129 @@
+//! License Module
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The bundled license templates, by SPDX id
+static LICENSES: [(&str, &str); 4] = [
+    ("MIT", include_str!("licenses/MIT.txt")),
+    ("Apache-2.0", include_str!("licenses/Apache-2.0.txt")),
+    ("GPL-3.0", include_str!("licenses/GPL-3.0.txt")),
+    ("BSD-3-Clause", include_str!("licenses/BSD-3-Clause.txt")),
+];
+
+/// Returns the SPDX ids of the bundled license templates
+pub fn spdx_ids() -> Vec<&'static str> {
+    LICENSES.iter().map(|(id, _)| *id).collect()
+}
+
+/// Returns the canonical SPDX id if `id` names a bundled license template, ignoring case
+pub fn spdx_id(id: &str) -> Option<&'static str> {
+    LICENSES
+        .iter()
+        .map(|(spdx, _)| *spdx)
+        .find(|spdx| spdx.eq_ignore_ascii_case(id))
+}
+
+/// Returns the text of the LICENSE file of a new project
+/// # Arguments
+/// * `license` - The stored license: an SPDX id, `NONE`, or the text of a license file
+/// * `year` - The copyright year filled into the template
+/// * `author` - The copyright holder filled into the template
+pub fn license_text(license: &str, year: i64, author: &str) -> String {
+    if license == "NONE" {
+        return "No license".to_string();
+    }
+    match LICENSES.iter().find(|(id, _)| *id == license) {
+        Some((_, template)) => template
+            .replace("{year}", &year.to_string())
+            .replace("{author}", author),
+        None => license.to_string(),
+    }
+}
+
+/// Returns the current year in UTC
+pub fn current_year() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    // Convert days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = secs / 86400 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    yoe + era * 400 + i64::from(month <= 2)
+}
+
+/// Returns the name of the author, taken from `git config user.name` or the `USER` variable
+pub fn author() -> String {
+    Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "the authors".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the first non-empty line of a license text
+    fn header(text: &str) -> &str {
+        text.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn each_spdx_id_has_its_header() {
+        for (id, expected) in [
+            ("MIT", "MIT License"),
+            ("Apache-2.0", "Apache License"),
+            ("GPL-3.0", "GNU GENERAL PUBLIC LICENSE"),
+            ("BSD-3-Clause", "BSD 3-Clause License"),
+        ] {
+            let text = license_text(id, 2024, "Jane Doe");
+            assert_eq!(header(&text), expected, "{}", id);
+            assert!(
+                !text.contains("{year}") && !text.contains("{author}"),
+                "{}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn year_and_author_are_filled_in() {
+        let text = license_text("MIT", 2024, "Jane Doe");
+        assert!(text.contains("Copyright (c) 2024 Jane Doe\n"));
+        let text = license_text("BSD-3-Clause", 2024, "Jane Doe");
+        assert!(text.contains("Copyright (c) 2024, Jane Doe\n"));
+    }
+
+    #[test]
+    fn spdx_ids_ignore_case() {
+        assert_eq!(spdx_id("mit"), Some("MIT"));
+        assert_eq!(spdx_id("apache-2.0"), Some("Apache-2.0"));
+        assert_eq!(spdx_id("GPL-2.0"), None);
+        assert_eq!(spdx_ids(), ["MIT", "Apache-2.0", "GPL-3.0", "BSD-3-Clause"]);
+    }
+
+    #[test]
+    fn none_and_license_files_are_kept() {
+        assert_eq!(license_text("NONE", 2024, "Jane Doe"), "No license");
+        // The text of a license file is written as is
+        assert_eq!(
+            license_text("My license {year}\n", 2024, "Jane Doe"),
+            "My license {year}\n"
+        );
+    }
+}
@@ -1,13 +1,32 @@
 //! Log Module
 
 use colored::Colorize;
-use std::sync::{Once, RwLock};
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::sync::{Once, OnceLock, RwLock};
+use std::time::Instant;
 
 static INIT: Once = Once::new();
 static LOG_LEVEL: RwLock<LogLevel> = RwLock::new(LogLevel::Info);
+static LOG_FORMAT: RwLock<LogFormat> = RwLock::new(LogFormat::Text);
+static LOG_TIMESTAMPS: RwLock<bool> = RwLock::new(false);
+static START: OnceLock<Instant> = OnceLock::new();
+static LOG_FILE: RwLock<LogFile> = RwLock::new(LogFile::Disabled);
+/// Whether the log level was asked to be `Info` or `Debug`, the unset default is not verbose
+static VERBOSE: RwLock<bool> = RwLock::new(false);
+
+/// State of the log file sink set by `RUXGO_LOG_FILE`
+enum LogFile {
+    Disabled,
+    /// The file is created on the first message written to it
+    Pending(String),
+    Open(File),
+    /// Creating the file failed, it is not retried
+    Failed,
+}
 
 /// This enum is used to represent the different log levels
-#[derive(PartialEq, PartialOrd, Debug)]
+#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
 pub enum LogLevel {
     Debug,
     Info,
@@ -16,9 +35,21 @@ pub enum LogLevel {
     Error,
 }
 
+/// This enum is used to represent the different output formats of the log
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LogFormat {
+    /// `[LEVEL] message` lines
+    Text,
+    /// One JSON object per line, e.g. `{"level":"log","msg":"..."}`
+    Json,
+}
+
 /// Initializes the log level, which is called only once when the program starts
 fn init_log_level() {
-    let level = std::env::var("RUXGO_LOG_LEVEL").unwrap_or_else(|_| "Info".to_string());
+    START.get_or_init(Instant::now);
+    let level = std::env::var("RUXGO_LOG_LEVEL").ok();
+    let verbose = matches!(level.as_deref(), Some("Info" | "Debug"));
+    let level = level.unwrap_or_else(|| "Info".to_string());
     let log_level = match level.as_str() {
         "Debug" => LogLevel::Debug,
         "Info" => LogLevel::Info,
@@ -31,6 +62,91 @@ fn init_log_level() {
     // Use write lock to update the log level
     let mut write_lock = LOG_LEVEL.write().unwrap();
     *write_lock = log_level;
+    *VERBOSE.write().unwrap() = verbose;
+
+    // Disable colors when asked to with NO_COLOR, or when the output is not a terminal
+    // unless CLICOLOR_FORCE is set
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let force_color = std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| v != "0");
+    if no_color || (!force_color && !std::io::stdout().is_terminal()) {
+        colored::control::set_override(false);
+    }
+
+    if std::env::var("RUXGO_LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        *LOG_FORMAT.write().unwrap() = LogFormat::Json;
+    }
+
+    if let Some(path) = std::env::var("RUXGO_LOG_FILE")
+        .ok()
+        .filter(|p| !p.is_empty())
+    {
+        *LOG_FILE.write().unwrap() = LogFile::Pending(path);
+    }
+
+    if std::env::var("RUXGO_LOG_TIMESTAMPS").is_ok_and(|v| v == "1" || v == "true") {
+        *LOG_TIMESTAMPS.write().unwrap() = true;
+    }
+}
+
+/// Overrides the log level set by `RUXGO_LOG_LEVEL`
+pub fn set_log_level(level: LogLevel) {
+    INIT.call_once(init_log_level);
+    *VERBOSE.write().unwrap() = level <= LogLevel::Info;
+    *LOG_LEVEL.write().unwrap() = level;
+}
+
+/// Overrides the log format set by `RUXGO_LOG_FORMAT`
+pub fn set_log_format(format: LogFormat) {
+    INIT.call_once(init_log_level);
+    *LOG_FORMAT.write().unwrap() = format;
+}
+
+/// Overrides whether log lines are prefixed with a timestamp, set by `RUXGO_LOG_TIMESTAMPS`
+pub fn set_log_timestamps(enabled: bool) {
+    INIT.call_once(init_log_level);
+    *LOG_TIMESTAMPS.write().unwrap() = enabled;
+}
+
+/// Appends a message to the log file, creating it on first use
+fn write_log_file(line: &str) {
+    let mut log_file = LOG_FILE.write().unwrap();
+    if let LogFile::Pending(path) = &*log_file {
+        *log_file = match File::create(path) {
+            Ok(file) => LogFile::Open(file),
+            Err(why) => {
+                // Warn once on stderr to keep JSON output on stdout intact
+                eprintln!(
+                    "{} Could not create log file '{}': {}",
+                    "[WARN]".yellow(),
+                    path,
+                    why
+                );
+                LogFile::Failed
+            }
+        };
+    }
+    if let LogFile::Open(file) = &mut *log_file {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// Returns the current log format
+pub fn log_format() -> LogFormat {
+    INIT.call_once(init_log_level);
+    *LOG_FORMAT.read().unwrap()
+}
+
+/// Returns the current log level, set by `RUXGO_LOG_LEVEL` or `set_log_level`
+pub fn log_level() -> LogLevel {
+    INIT.call_once(init_log_level);
+    *LOG_LEVEL.read().unwrap()
+}
+
+/// Returns true if progress bars may be drawn, i.e. the log is plain text and the level was
+/// not set to `Info` or `Debug`, which log each step the bar would count
+pub fn progress_enabled() -> bool {
+    INIT.call_once(init_log_level);
+    *LOG_FORMAT.read().unwrap() == LogFormat::Text && !*VERBOSE.read().unwrap()
 }
 
 /// This function is used to log messages to the console
@@ -39,6 +155,8 @@ fn init_log_level() {
 /// * `message` - The message to log
 /// # Example
 /// ```
+/// # use ruxgo::utils::log::{log, LogLevel};
+/// # let error = "error";
 /// log(LogLevel::Info, "Hello World!");
 /// log(LogLevel::Error, &format!("Something went wrong! {}", error));
 /// ```
@@ -51,20 +169,84 @@ fn init_log_level() {
 /// * `Log`
 /// * `Warn`
 /// * `Error`
+///
 /// If the environment variable is not set, the default log level is `Log`
+///
+/// Colors are disabled if `NO_COLOR` is set or stdout is not a terminal,
+/// unless `CLICOLOR_FORCE` is set
+///
+/// # Format setting
+/// Setting the environment variable `RUXGO_LOG_FORMAT` to `json` prints one JSON
+/// object per line instead, e.g. `{"level":"log","msg":"Building..."}`
+///
+/// Setting `RUXGO_LOG_TIMESTAMPS` to `1` prefixes each line with the seconds
+/// elapsed since the first log line, or adds a `time` field in JSON
+///
+/// # Log file
+/// Setting `RUXGO_LOG_FILE` to a path also writes every message to that file,
+/// including the ones below the console log level
 pub fn log(level: LogLevel, message: &str) {
-    INIT.call_once(|| {
-        init_log_level();
-    });
-    let level_str = match level {
-        LogLevel::Debug => "[DEBUG]".purple(),
-        LogLevel::Info => "[INFO]".blue(),
-        LogLevel::Log => "[LOG]".green(),
-        LogLevel::Warn => "[WARN]".yellow(),
-        LogLevel::Error => "[ERROR]".red(),
+    log_fields(level, message, serde_json::Value::Null);
+}
+
+/// Logs a message like `log`, adding the entries of `fields` to the JSON object
+/// when the log format is JSON, they are left out of text lines
+/// # Arguments
+/// * `level` - The log level of the message
+/// * `message` - The message to log
+/// * `fields` - A JSON object of extra fields, e.g. `{"diagnostic": {...}}`
+pub fn log_fields(level: LogLevel, message: &str, fields: serde_json::Value) {
+    INIT.call_once(init_log_level);
+    // Seconds elapsed since the first log line, if timestamps are enabled
+    let elapsed = if *LOG_TIMESTAMPS.read().unwrap() {
+        Some(START.get_or_init(Instant::now).elapsed().as_secs_f64())
+    } else {
+        None
     };
+    // The log file gets every message, whatever the console level
+    if !matches!(
+        *LOG_FILE.read().unwrap(),
+        LogFile::Disabled | LogFile::Failed
+    ) {
+        let level_str = format!("[{:?}]", level).to_uppercase();
+        match elapsed {
+            Some(elapsed) => {
+                write_log_file(&format!("[{:>10.3}s] {} {}", elapsed, level_str, message))
+            }
+            None => write_log_file(&format!("{} {}", level_str, message)),
+        }
+    }
     // Use read lock to check log level
-    if level >= *LOG_LEVEL.read().unwrap() {
-        println!("{} {}", level_str, message);
+    if level < *LOG_LEVEL.read().unwrap() {
+        return;
+    }
+    match *LOG_FORMAT.read().unwrap() {
+        LogFormat::Text => {
+            let level_str = match level {
+                LogLevel::Debug => "[DEBUG]".purple(),
+                LogLevel::Info => "[INFO]".blue(),
+                LogLevel::Log => "[LOG]".green(),
+                LogLevel::Warn => "[WARN]".yellow(),
+                LogLevel::Error => "[ERROR]".red(),
+            };
+            if let Some(elapsed) = elapsed {
+                println!("[{:>10.3}s] {} {}", elapsed, level_str, message);
+            } else {
+                println!("{} {}", level_str, message);
+            }
+        }
+        LogFormat::Json => {
+            let level_str = format!("{:?}", level).to_lowercase();
+            let mut line = serde_json::json!({ "level": level_str, "msg": message });
+            if let Some(elapsed) = elapsed {
+                line["time"] = serde_json::json!(elapsed);
+            }
+            if let serde_json::Value::Object(fields) = fields {
+                for (key, value) in fields {
+                    line[key] = value;
+                }
+            }
+            println!("{}", line);
+        }
     }
 }
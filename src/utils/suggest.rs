@@ -0,0 +1,64 @@
+//! Suggestion Module
+
+/// Returns the Levenshtein edit distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut cur = vec![0; b_chars.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j + 1] + 1).min(cur[j] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b_chars.len()]
+}
+
+/// Returns the candidate closest to `input`, if any is close enough to be a likely typo
+/// # Arguments
+/// * `input` - The value given by the user
+/// * `candidates` - The valid values
+pub fn closest_match<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let input = input.to_lowercase();
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let candidate_lower = candidate.to_lowercase();
+            let distance = edit_distance(&input, &candidate_lower);
+            // About one typo per three characters of the longer string, so that short inputs
+            // like "dl" still match. A candidate contained in the input (e.g. "exe" in
+            // "executable") is accepted even when the edit distance is large
+            let longest = input.chars().count().max(candidate_lower.chars().count());
+            if distance <= (longest / 3).max(1) || input.contains(&candidate_lower) {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_match_suggests_likely_typos() {
+        let types = ["exe", "dll", "static", "object", "header-only", "test"];
+        assert_eq!(closest_match("exee", types), Some("exe"));
+        assert_eq!(closest_match("dl", types), Some("dll"));
+        assert_eq!(closest_match("Statc", types), Some("static"));
+        assert_eq!(closest_match("ojbect", types), Some("object"));
+        assert_eq!(closest_match("header_only", types), Some("header-only"));
+        assert_eq!(closest_match("executable", types), Some("exe"));
+        assert_eq!(closest_match("shared", types), None);
+        assert_eq!(closest_match("x", types), None);
+        assert_eq!(closest_match("exe", std::iter::empty()), None);
+    }
+}
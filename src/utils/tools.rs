@@ -0,0 +1,277 @@
+//! External Tools Module
+
+use crate::utils::log::{log, progress_enabled, LogLevel};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::time::Duration;
+
+/// Returns true if the tool can be run, given either as a path or as a name searched on PATH
+pub fn is_available(tool: &str) -> bool {
+    find(tool).is_some()
+}
+
+/// Returns the path of the executable the tool resolves to, like `which` or `where`
+/// # Arguments
+/// * `tool` - Either a path or a name searched on PATH
+pub fn find(tool: &str) -> Option<PathBuf> {
+    let path = Path::new(tool);
+    if path.components().count() > 1 {
+        return executable(path);
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| executable(&dir.join(tool)))
+}
+
+/// Returns the path if it is a file that can be executed
+fn executable(path: &Path) -> Option<PathBuf> {
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path)
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .then(|| path.to_path_buf())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let exe = path.with_extension("exe");
+        if path.is_file() {
+            Some(path.to_path_buf())
+        } else {
+            exe.is_file().then_some(exe)
+        }
+    }
+}
+
+/// Returns how to get a missing tool
+fn install_hint(tool: &str) -> &'static str {
+    let name = Path::new(tool)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(tool);
+    if name.starts_with("qemu") {
+        "install QEMU (e.g. `apt install qemu-system`) or set `qemu_bin` in [os.platform.qemu]"
+    } else if name.contains("-linux-musl-") {
+        "install a musl cross toolchain from https://musl.cc and add its bin dir to PATH"
+    } else {
+        match name {
+            "rust-objcopy" => {
+                "run `cargo install cargo-binutils && rustup component add llvm-tools-preview`"
+            }
+            "rust-lld" => "add the bin dir of `rustc --print sysroot`/lib/rustlib/<host> to PATH",
+            "cargo" => "install Rust from https://rustup.rs",
+            "mkfs.fat" => "install dosfstools (e.g. `apt install dosfstools`)",
+            "dd" => "install coreutils",
+            "make" => "install make (e.g. `apt install make`)",
+            "git" => "install git (e.g. `apt install git`)",
+            _ => "install it or check your PATH",
+        }
+    }
+}
+
+/// Checks that all the tools are available, logging each missing one with a hint
+/// on how to get it and exiting if any is missing
+/// # Arguments
+/// * `tools` - Names or paths of the tools
+pub fn require<S: AsRef<str>>(tools: &[S]) {
+    let missing: Vec<&str> = tools
+        .iter()
+        .map(|tool| tool.as_ref())
+        .filter(|tool| !is_available(tool))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    for tool in &missing {
+        log(
+            LogLevel::Error,
+            &format!("Required tool not found: {}, {}", tool, install_hint(tool)),
+        );
+    }
+    std::process::exit(1);
+}
+
+/// Splits a command or flags string from the config into arguments, following shell quoting,
+/// e.g. `-DNAME="a b" -O2` gives `-DNAME=a b` and `-O2`
+pub fn split_args(args: &str) -> Vec<String> {
+    shlex::split(args).unwrap_or_else(|| {
+        log(
+            LogLevel::Error,
+            &format!("Unbalanced quotes or trailing escape in: {}", args),
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Returns a command running the program of `argv` with the rest of it as arguments,
+/// passed as is without going through a shell
+/// # Arguments
+/// * `argv` - The program followed by its arguments
+pub fn command(argv: &[String]) -> Command {
+    let Some((program, args)) = argv.split_first() else {
+        log(
+            LogLevel::Error,
+            "Empty command, check the compiler and linker in the config",
+        );
+        std::process::exit(1);
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd
+}
+
+/// Returns `argv` as a line that can be pasted into a shell, for logging
+pub fn display(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            // Only quote when needed to keep common flags like `-std=c++20` readable
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_+=/.,:@%".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                shlex::try_quote(arg).map_or_else(|_| arg.clone(), |quoted| quoted.into_owned())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns a spinner showing `message` and the elapsed time for a long step, hidden
+/// unless progress bars are enabled. It should be cleared with `finish_and_clear`
+pub fn spinner(message: &str) -> ProgressBar {
+    if !progress_enabled() {
+        return ProgressBar::hidden().with_message(message.to_string());
+    }
+    let spinner = ProgressBar::new_spinner().with_style(
+        ProgressStyle::with_template("    {spinner:.cyan} {msg} [{elapsed_precise}]").unwrap(),
+    );
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+/// Runs the command, logging every line it prints with `prefix` in front so that it
+/// can be told apart from the log of ruxgo
+/// Returns the exit status of the command and what it printed on stderr
+/// # Arguments
+/// * `cmd` - The command to run, its stdout and stderr are replaced by pipes
+/// * `prefix` - Put before each line, e.g. `[os]`
+/// * `progress` - A spinner kept below the logged lines
+pub fn run_prefixed(
+    cmd: &mut Command,
+    prefix: &str,
+    progress: &ProgressBar,
+) -> io::Result<(ExitStatus, String)> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let emit = |line: Vec<u8>| {
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        progress.suspend(|| log(LogLevel::Log, &format!("{} {}", prefix, line)));
+        line.to_string()
+    };
+    let captured = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for line in BufReader::new(stdout).split(b'\n').map_while(Result::ok) {
+                emit(line);
+            }
+        });
+        BufReader::new(stderr)
+            .split(b'\n')
+            .map_while(Result::ok)
+            .map(emit)
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    Ok((child.wait()?, captured))
+}
+
+/// Returns the message logged when a command fails, with its exit status and its stderr
+/// as text, if it was captured
+pub fn failure_message(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr = stderr.trim_end();
+    if stderr.is_empty() {
+        format!("Command execution failed ({})", output.status)
+    } else {
+        format!("Command execution failed ({}): {}", output.status, stderr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tools_are_found_on_path_or_by_path() {
+        assert!(is_available("sh"));
+        assert!(!is_available("ruxgo-no-such-tool"));
+        let sh = find("sh").unwrap();
+        assert!(is_available(sh.to_str().unwrap()));
+        // A file that can't be executed is not a tool
+        assert!(!is_available(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/Cargo.toml"
+        )));
+    }
+
+    #[test]
+    fn install_hints_name_how_to_get_the_tool() {
+        assert!(install_hint("qemu-system-riscv64").contains("qemu_bin"));
+        assert!(install_hint("/opt/qemu/bin/qemu-system-x86_64").contains("qemu_bin"));
+        assert!(install_hint("x86_64-linux-musl-ar").contains("musl.cc"));
+        assert!(install_hint("rust-objcopy").contains("cargo-binutils"));
+        assert!(install_hint("mkfs.fat").contains("dosfstools"));
+        assert_eq!(install_hint("foo"), "install it or check your PATH");
+    }
+
+    #[test]
+    fn commands_pass_shell_special_args_literally() {
+        let arg = "a b;$(echo x) `y` 'q\"";
+        let argv = ["printf", "%s", arg].map(String::from);
+        let output = command(&argv).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), arg);
+        // The logged line gives the same args back to a shell
+        assert_eq!(split_args(&display(&argv)), argv);
+        assert_eq!(display(&argv[..2]), "printf %s");
+    }
+
+    #[test]
+    fn config_flags_follow_shell_quoting() {
+        assert_eq!(
+            split_args("-DNAME=\"a b\" -O2  -I'dir with space'"),
+            ["-DNAME=a b", "-O2", "-Idir with space"]
+        );
+        assert!(split_args("").is_empty());
+    }
+
+    #[test]
+    fn prefixed_runs_capture_stderr_as_text() {
+        let argv = ["sh", "-c", "echo out; echo 'error: caf\u{e9}' >&2; exit 3"].map(String::from);
+        let (status, stderr) =
+            run_prefixed(&mut command(&argv), "[os]", &ProgressBar::hidden()).unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(stderr, "error: caf\u{e9}");
+    }
+
+    #[test]
+    fn failures_show_stderr_as_text() {
+        let failed = |script: &str| {
+            let argv = ["sh", "-c", script].map(String::from);
+            failure_message(&command(&argv).output().unwrap())
+        };
+        assert_eq!(
+            failed("echo 'qemu: could not open kernel' >&2; exit 2"),
+            "Command execution failed (exit status: 2): qemu: could not open kernel"
+        );
+        assert_eq!(
+            failed("exit 1"),
+            "Command execution failed (exit status: 1)"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+/// Returns the names of the targets listed in the build manifest
+fn manifest_names(project: &Project) -> Vec<String> {
+    let manifest = std::fs::read_to_string(project.path("ruxgo_bld/build-manifest.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    manifest["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn build_manifest_keeps_the_targets_built_before() {
+    let project = Project::new("build-manifest");
+    project.write("app/main.c", MAIN_C);
+    project.write("util/util.c", "int util(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("app", "exe", "./app"),
+        target("libutil", "static", "./util"),
+    ]);
+
+    project.run_ok(&["-b", "--target", "libutil"]);
+    assert_eq!(manifest_names(&project), ["libutil"]);
+    project.run_ok(&["-b", "--target", "app"]);
+    assert_eq!(manifest_names(&project), ["libutil", "app"]);
+    // Building a target again replaces its entry
+    project.run_ok(&["-b", "--target", "app"]);
+    assert_eq!(manifest_names(&project), ["libutil", "app"]);
+
+    // Targets removed from the config are dropped
+    project.config(&[target("app", "exe", "./app")]);
+    project.run_ok(&["-b"]);
+    assert_eq!(manifest_names(&project), ["app"]);
+}
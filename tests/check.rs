@@ -0,0 +1,54 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn check_summarizes_a_valid_config() {
+    let project = Project::new("check-valid");
+    project.write("src/main.c", MAIN_C);
+    project.write("src/util.c", "int util(void) {\n    return 0;\n}\n");
+    project.write("lib/lib.c", "int lib(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("app", "exe", "./src").set("deps", "[\"util\"]"),
+    ]);
+
+    let stdout = project.run_ok(&["check"]);
+    assert!(stdout.contains("app (exe): 2 source files"), "{}", stdout);
+    assert!(
+        stdout.contains("util (static): 1 source files"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("config_linux.toml is valid"), "{}", stdout);
+    // Nothing is built
+    assert!(!project.path("ruxgo_bld").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn check_lists_every_problem() {
+    let project = Project::new("check-problems");
+    project.write("src/main.c", MAIN_C);
+    project.write("lib/lib.c", "int lib(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("app", "exe", "./src")
+            .set("deps", "[\"utl\"]")
+            .set("include_dir", "[\"./inc\"]"),
+        target("other", "static", "./missing"),
+    ]);
+
+    let output = project.run(&["check"]);
+    assert!(!output.status.success());
+    let stdout = stdout(&output);
+    for problem in [
+        "Target \"app\": dependency \"utl\" not found, did you mean \"util\"?",
+        "Target \"app\": include_dir \"./inc\" does not exist",
+        "Target \"other\": src directory \"./missing\" does not exist",
+        "Found 3 problem(s) in ./config_linux.toml",
+    ] {
+        assert!(stdout.contains(problem), "{}\n{}", problem, stdout);
+    }
+}
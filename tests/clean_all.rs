@@ -0,0 +1,36 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::fs;
+
+/// Returns the names of the entries of a directory, sorted
+fn entries(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn build_and_clean_leave_no_stray_dirs() {
+    let project = Project::new("clean-all");
+    project.write("src/main.c", MAIN_C);
+    project.write("lib/lib.c", "int lib(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("app", "exe", "./src").set("deps", "[\"util\"]"),
+    ]);
+
+    project.run_ok(&["-b", "-r"]);
+    // Every output of the build, the run included, is under ruxgo_bld
+    assert_eq!(
+        entries(&project.root),
+        ["config_linux.toml", "lib", "ruxgo_bld", "src"]
+    );
+
+    project.run_ok(&["-c", "all"]);
+    assert_eq!(entries(&project.root), ["config_linux.toml", "lib", "src"]);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn clean_all_but_os_keeps_the_os_build() {
+    let project = Project::new("clean-keep-os");
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            "[os]\nname = \"ruxos\"\nservices = [\"alloc\"]\nulib = \"ruxlibc\"\n\n[os.platform]\nname = \"x86_64-qemu-q35\"\n",
+            &[target("main", "exe", "./src/")],
+        ),
+    );
+    // Outputs as left by a build, without needing ruxos to build them
+    for file in [
+        "target/x86_64-unknown-none/release/libruxlibc.a",
+        "os_config.hash",
+        "main.linux.hash",
+        "libc.linux.hash",
+        "bin/main.bin",
+        "bin/main.elf",
+        "bin/libc.a",
+        "obj_linux/main-main.o",
+    ] {
+        project.write(&format!("ruxgo_bld/{}", file), "");
+    }
+
+    project.run_ok(&["--clean=all-but-os"]);
+    assert!(project
+        .path("ruxgo_bld/target/x86_64-unknown-none/release/libruxlibc.a")
+        .exists());
+    assert!(project.path("ruxgo_bld/os_config.hash").exists());
+    for removed in ["main.linux.hash", "libc.linux.hash", "bin", "obj_linux"] {
+        assert!(
+            !project.path(&format!("ruxgo_bld/{}", removed)).exists(),
+            "{} was not removed",
+            removed
+        );
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+/// Sets up and builds a project with one exe
+fn built_project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src")]);
+    project.run_ok(&["-b"]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn clean_removes_only_the_given_parts() {
+    let project = built_project("clean-parts");
+    let stdout = project.run_ok(&["--clean", "obj"]);
+    assert!(!stdout.contains("deprecated"), "{}", stdout);
+    assert!(!project.path("ruxgo_bld/obj_linux").exists());
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+
+    let output = project.run(&["--clean", "objs"]);
+    let stdout = common::stdout(&output);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(stdout.contains("did you mean \"obj\"?"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn positional_parts_are_a_deprecated_alias() {
+    let project = built_project("clean-parts-positional");
+    let stdout = project.run_ok(&["obj,bins"]);
+    assert!(
+        stdout.contains("deprecated, use `--clean obj,bins`"),
+        "{}",
+        stdout
+    );
+    assert!(!project.path("ruxgo_bld/obj_linux").exists());
+    assert!(!project.path("ruxgo_bld/bin/app.bin").exists());
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn clean_target_removes_only_its_outputs() {
+    let project = Project::new("clean-target");
+    project.write("util/main.c", "int util(void) {\n    return 0;\n}\n");
+    project.write("app/main.c", MAIN_C);
+    project.config(&[
+        target("util", "static", "./util"),
+        target("app", "exe", "./app"),
+    ]);
+    let outputs = |name: &str, bin: &str| {
+        [
+            format!("ruxgo_bld/obj_linux/{}-main.o", name),
+            format!("ruxgo_bld/{}.linux.hash", name),
+            format!("ruxgo_bld/bin/{}", bin),
+        ]
+        .map(|path| project.path(&path))
+    };
+
+    project.run_ok(&["-b"]);
+    assert!(outputs("app", "app.bin").iter().all(|path| path.exists()));
+    assert!(outputs("util", "util.a").iter().all(|path| path.exists()));
+
+    project.run_ok(&["-c", "--target", "app"]);
+    for path in outputs("app", "app.bin") {
+        assert!(!path.exists(), "{} was not removed", path.display());
+    }
+    for path in outputs("util", "util.a") {
+        assert!(path.exists(), "{} was removed", path.display());
+    }
+}
@@ -0,0 +1,261 @@
+//! Fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+
+/// The ruxgo binary under test
+pub const RUXGO: &str = env!("CARGO_BIN_EXE_ruxgo");
+
+/// A C program that does nothing
+pub const MAIN_C: &str = "int main(void) {\n    return 0;\n}\n";
+
+/// Returns a command running ruxgo, logging at the `Log` level
+pub fn ruxgo() -> Command {
+    let mut cmd = Command::new(RUXGO);
+    cmd.env("RUXGO_LOG_LEVEL", "Log");
+    cmd
+}
+
+/// Returns the stdout of a finished command
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// A `[[targets]]` entry of a config file
+pub struct TargetToml {
+    fields: Vec<(String, String)>,
+}
+
+/// Returns a target without include dirs, flags or deps, static libs are archived with `ar`
+/// # Arguments
+/// * `name` - The name of the target
+/// * `typ` - The type of the target, e.g. `exe`
+/// * `src` - The source directory of the target
+pub fn target(name: &str, typ: &str, src: &str) -> TargetToml {
+    let mut target = TargetToml { fields: Vec::new() }
+        .set("name", &format!("{:?}", name))
+        .set("src", &format!("{:?}", src))
+        .set("include_dir", "[]")
+        .set("type", &format!("{:?}", typ))
+        .set("cflags", "\"\"")
+        .set("deps", "[]");
+    if typ == "static" {
+        target = target.set("archive", "\"ar rcs\"");
+    }
+    target
+}
+
+impl TargetToml {
+    /// Sets a field to a TOML value, replacing the previous one
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        match self.fields.iter_mut().find(|(k, _)| k == key) {
+            Some(field) => field.1 = value.to_string(),
+            None => self.fields.push((key.to_string(), value.to_string())),
+        }
+        self
+    }
+}
+
+/// Returns a config file building the targets with gcc
+/// # Arguments
+/// * `sections` - Sections placed between `[build]` and the targets, e.g. `[os]`
+/// * `targets` - The targets of the project
+pub fn config(sections: &str, targets: &[TargetToml]) -> String {
+    config_with_compiler("gcc", sections, targets)
+}
+
+/// Returns a config file building the targets with the given compiler
+pub fn config_with_compiler(compiler: &str, sections: &str, targets: &[TargetToml]) -> String {
+    let mut config = format!("[build]\ncompiler = {:?}\n\n{}", compiler, sections);
+    for target in targets {
+        config.push_str("\n[[targets]]\n");
+        for (key, value) in &target.fields {
+            config.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+    config
+}
+
+/// A project in a temporary directory, removed when dropped
+pub struct Project {
+    pub root: PathBuf,
+}
+
+impl Project {
+    /// Creates an empty project named after the test
+    pub fn new(name: &str) -> Project {
+        let root = std::env::temp_dir().join(format!("ruxgo-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Project { root }
+    }
+
+    /// Returns the absolute path of a file of the project
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Writes a file of the project, creating its directory
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Writes `config_linux.toml` with the given targets and no other sections
+    pub fn config(&self, targets: &[TargetToml]) {
+        self.write("config_linux.toml", &config("", targets));
+    }
+
+    /// Runs ruxgo in the project
+    pub fn run(&self, args: &[&str]) -> Output {
+        ruxgo()
+            .arg("--path")
+            .arg(&self.root)
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Runs ruxgo in the project and returns its stdout, asserting that it succeeded
+    pub fn run_ok(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(output.status.success(), "{}", stdout(&output));
+        stdout(&output)
+    }
+
+    /// Runs ruxgo in a directory of the project without `--path`, with its own `home` directory
+    /// so that the global config of the user is neither read nor changed
+    pub fn run_in(&self, dir: &str, args: &[&str]) -> Output {
+        let home = self.path("home");
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(self.path(dir)).unwrap();
+        ruxgo()
+            .current_dir(self.path(dir))
+            .env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Runs `ruxgo pkg` in the project, downloading the packages from `server`
+    pub fn pkg(&self, server: &Server, args: &[&str]) -> Output {
+        ruxgo()
+            .current_dir(&self.root)
+            .env("RUXGO_PKG_URL", &server.url)
+            .arg("pkg")
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Writes an executable shell script into the project
+    #[cfg(unix)]
+    pub fn script(&self, path: &str, contents: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        self.write(path, contents);
+        let path = self.path(path);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+}
+
+impl Project {
+    /// Writes a compiler that runs gcc after logging its arguments to `cc.log`, and returns its path
+    #[cfg(unix)]
+    pub fn logging_gcc(&self) -> String {
+        let log = self.path("cc.log");
+        self.script(
+            "cc.sh",
+            &format!("#!/bin/sh\necho \"$@\" >> {:?}\nexec gcc \"$@\"\n", log),
+        )
+        .display()
+        .to_string()
+    }
+
+    /// Returns the arguments of each compile run logged by the compiler of `logging_gcc`
+    pub fn compile_runs(&self) -> Vec<String> {
+        fs::read_to_string(self.path("cc.log"))
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| line.split(' ').any(|arg| arg == "-c"))
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// An HTTP server on localhost serving files from memory, standing in for the package mirror
+pub struct Server {
+    pub url: String,
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Server {
+    /// Starts serving in the background until the test exits, unknown paths get a 404
+    pub fn new() -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let files: Arc<Mutex<HashMap<String, Vec<u8>>>> = Arc::default();
+        let requests: Arc<Mutex<Vec<String>>> = Arc::default();
+        let (served, requested) = (files.clone(), requests.clone());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers, requests have no body
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+                let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+                let body = served.lock().unwrap().get(&path).cloned();
+                requested.lock().unwrap().push(path);
+                let (status, body) = match body {
+                    Some(body) => ("200 OK", body),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+        Server {
+            url,
+            files,
+            requests,
+        }
+    }
+
+    /// Serves `contents` at `path`, e.g. `/packages.toml`
+    pub fn serve(&self, path: &str, contents: &[u8]) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), contents.to_vec());
+    }
+
+    /// Returns the paths requested so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn config_paths_are_relative_to_the_config_file() {
+    let project = Project::new("config-path");
+    project.write("project/src/main.c", MAIN_C);
+    project.write(
+        "project/ruxgo.toml",
+        &common::config("", &[target("app", "exe", "./src/")]),
+    );
+    std::fs::create_dir_all(project.path("elsewhere/sub")).unwrap();
+
+    // Started from an unrelated subdirectory, the sources are found next to the config
+    let output = common::ruxgo()
+        .current_dir(project.path("elsewhere/sub"))
+        .args(["-b", "--config", "../../project/ruxgo.toml"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert!(project.path("project/ruxgo_bld/bin/app.bin").exists());
+    assert!(!project.path("elsewhere/sub/ruxgo_bld").exists());
+}
+
+#[test]
+#[cfg(target_os = "macos")]
+fn macos_config_is_preferred_over_the_linux_one() {
+    let project = Project::new("config-macos");
+    project.write("src/main.c", MAIN_C);
+
+    // Without a macOS config the linux one is used
+    project.config(&[target("app", "exe", "./src/")]);
+    let output = project.run(&["check"]);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert!(common::stdout(&output).contains("./config_linux.toml is valid"));
+
+    project.write(
+        "config_macos.toml",
+        &common::config("", &[target("app", "exe", "./src/")]),
+    );
+    let output = project.run(&["check"]);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert!(common::stdout(&output).contains("./config_macos.toml is valid"));
+
+    project.run_ok(&["-b"]);
+    assert!(project.path("ruxgo_bld/obj_macos").exists());
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+}
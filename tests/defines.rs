@@ -0,0 +1,78 @@
+mod common;
+
+use common::{target, Project};
+use std::process::Command;
+
+/// A program that only compiles with the defines of the target
+const CHECK_DEFINES_C: &str = "#if !defined(FOO) || BAR != 1\n#error missing defines\n#endif\n\
+    int main(void) {\n    return sizeof(MSG) == 4 ? 0 : 1;\n}\n";
+
+/// Writes a project whose target has defines, one of them quoted, and a `-DBAZ` cflag
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("src/main.c", CHECK_DEFINES_C);
+    project.config(&[target("app", "exe", "./src")
+        .set("defines", r#"["FOO", "BAR=1", "MSG=\"a b\""]"#)
+        .set("cflags", "\"-DBAZ\"")]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn defines_reach_the_compiler_and_compile_commands() {
+    let project = project("defines");
+
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .args(["-b", "--gen-cc"])
+        .env("RUXGO_LOG_LEVEL", "Info")
+        .output()
+        .unwrap();
+    let stdout = common::stdout(&output);
+    assert!(output.status.success(), "{}", stdout);
+    let compile = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("[INFO]   Command: "))
+        .find(|command| command.contains("main.c"))
+        .expect(&stdout);
+    // Given before the cflags, so that the cflags can override them
+    assert!(
+        compile.contains("-DFOO -DBAR=1 '-DMSG=\"a b\"' -DBAZ"),
+        "{}",
+        compile
+    );
+
+    let db = std::fs::read_to_string(project.path("compile_commands.json")).unwrap();
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&db).unwrap();
+    let command = entries[0]["command"].as_str().unwrap();
+    assert!(command.contains("-DFOO -DBAR=1 "), "{}", command);
+    // The entry compiles as the build did, quoting included
+    let status = Command::new("sh")
+        .args(["-c", command])
+        .current_dir(entries[0]["directory"].as_str().unwrap())
+        .status()
+        .unwrap();
+    assert!(status.success(), "{}", command);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn defines_are_added_to_the_vscode_defines() {
+    let project = project("defines-vsc");
+
+    project.run_ok(&["-b", "--gen-vsc"]);
+    let props = std::fs::read_to_string(project.path(".vscode/c_cpp_properties.json")).unwrap();
+    let props: serde_json::Value = serde_json::from_str(&props).unwrap();
+    assert_eq!(
+        props["configurations"][0]["defines"],
+        serde_json::json!([
+            "_DEBUG",
+            "UNICODE",
+            "_UNICODE",
+            "FOO",
+            "BAR=1",
+            "MSG=\"a b\""
+        ])
+    );
+}
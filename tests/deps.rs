@@ -0,0 +1,57 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+
+/// Writes a project whose exe `app` has the given deps, next to a static lib `util`
+fn project(name: &str, deps: &str) -> Project {
+    let project = Project::new(name);
+    project.write("src/main.c", MAIN_C);
+    project.write("lib/lib.c", "int lib(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("app", "exe", "./src").set("deps", deps),
+    ]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn misspelled_dependency_is_named() {
+    let project = project("deps-misspelled", "[\"util\", \"utl\"]");
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("Target \"app\": dependency \"utl\" not found, did you mean \"util\"?"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("\"util\" not found"), "{}", stdout);
+    assert!(!project.path("ruxgo_bld/bin/app.bin").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn dependency_cycle_is_named() {
+    let project = Project::new("deps-cycle");
+    project.write("src/main.c", MAIN_C);
+    project.write("a/a.c", "int a(void) {\n    return 0;\n}\n");
+    project.write("b/b.c", "int b(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("liba", "static", "./a").set("deps", "[\"libb\"]"),
+        target("libb", "static", "./b").set("deps", "[\"liba\"]"),
+        target("app", "exe", "./src").set("deps", "[\"liba\"]"),
+    ]);
+
+    for command in ["-b", "check"] {
+        let output = project.run(&[command]);
+        assert!(!output.status.success());
+        let stdout = stdout(&output);
+        assert!(
+            stdout.contains("Circular dependency detected: liba -> libb -> liba")
+                || stdout.contains("Circular dependency detected: libb -> liba -> libb"),
+            "{}",
+            stdout
+        );
+    }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{target, Project};
+use serde_json::{json, Value};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn json_log_has_a_diagnostic_field_per_compiler_message() {
+    let project = Project::new("diagnostics-json");
+    project.write(
+        "src/main.c",
+        "int main(void) {\n    int unused;\n    return x;\n}\n",
+    );
+    project.config(&[target("app", "exe", "./src").set("cflags", "\"-Wall\"")]);
+
+    let output = project.run(&["--format", "json", "-b"]);
+    assert!(!output.status.success());
+    let lines: Vec<Value> = common::stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let diagnostics: Vec<&Value> = lines
+        .iter()
+        .filter(|line| line.get("diagnostic").is_some())
+        .collect();
+    let main_c = project.path("src/main.c").display().to_string();
+    let field = |line: &Value| {
+        let mut diagnostic = line["diagnostic"].clone();
+        // The file is as given to the compiler, relative or absolute
+        assert!(
+            main_c.ends_with(
+                diagnostic["file"]
+                    .as_str()
+                    .unwrap()
+                    .trim_start_matches("./")
+            ),
+            "{}",
+            diagnostic
+        );
+        diagnostic["file"] = json!("src/main.c");
+        diagnostic
+    };
+
+    let error = diagnostics
+        .iter()
+        .find(|line| line["level"] == "error")
+        .expect("no error diagnostic");
+    assert_eq!(
+        field(error),
+        json!({
+            "file": "src/main.c",
+            "line": 3,
+            "column": 12,
+            "severity": "error",
+            "message": "'x' undeclared (first use in this function)",
+        })
+    );
+    assert!(error["msg"]
+        .as_str()
+        .unwrap()
+        .ends_with(":3:12: 'x' undeclared (first use in this function)"));
+
+    let warning = diagnostics
+        .iter()
+        .find(|line| line["level"] == "warn")
+        .expect("no warning diagnostic");
+    assert_eq!(field(warning)["severity"], "warning");
+    assert_eq!(field(warning)["line"], 2);
+}
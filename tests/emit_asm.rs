@@ -0,0 +1,18 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn emit_asm_writes_disassembly() {
+    let project = Project::new("emit-asm");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src/").set("emit_asm", "true")]);
+    project.run_ok(&["-b"]);
+
+    let asm = std::fs::read_to_string(project.path("ruxgo_bld/bin/app.asm")).unwrap();
+    assert!(asm.contains("<main>:"), "{}", asm);
+    assert!(asm
+        .split_whitespace()
+        .any(|word| ["ret", "retq", "mov", "push", "bl", "jalr"].contains(&word)));
+}
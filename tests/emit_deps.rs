@@ -0,0 +1,69 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn emit_deps_lists_headers_without_running_anything() {
+    let project = Project::new("emit-deps");
+    project.write("inc/value.h", "#define VALUE 0\n");
+    project.write("inc/unused.h", "");
+    project.write(
+        "src/main.c",
+        "#include \"value.h\"\nint main(void) {\n    return VALUE;\n}\n",
+    );
+    let cc = project.script("cc.sh", "#!/bin/sh\necho \"$@\" >> cc.log\n");
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc.display().to_string(),
+            "",
+            &[
+                target("app", "exe", "./src")
+                    .set("include_dir", "[\"./inc\"]")
+                    .set("pre_build", "\"echo run >> runs\""),
+                target("headers", "header-only", "./inc"),
+            ],
+        ),
+    );
+
+    project.run_ok(&["--emit-deps", project.path("deps.json").to_str().unwrap()]);
+    let deps: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(project.path("deps.json")).unwrap()).unwrap();
+    assert_eq!(
+        deps,
+        serde_json::json!({
+            "app": { "./src/main.c": ["./inc/value.h"] },
+            "headers": {},
+        })
+    );
+    assert!(!project.path("runs").exists(), "pre_build was run");
+    assert!(!project.path("cc.log").exists(), "the compiler was run");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn emit_deps_path_is_relative_to_the_current_dir() {
+    let project = Project::new("emit-deps-cwd");
+    project.write("app/src/main.c", MAIN_C);
+    let config = common::config("", &[target("app", "exe", "./src")]);
+    project.write("app/config_linux.toml", &config);
+    project.write("app/custom.toml", &config);
+
+    for (option, path) in [("--path", "app"), ("--config", "app/custom.toml")] {
+        let output = common::ruxgo()
+            .current_dir(&project.root)
+            .args([option, path, "--emit-deps", "deps.json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", common::stdout(&output));
+        assert!(project.path("deps.json").exists(), "{} {}", option, path);
+        assert!(
+            !project.path("app/deps.json").exists(),
+            "{} {}",
+            option,
+            path
+        );
+        std::fs::remove_file(project.path("deps.json")).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn emit_map_writes_a_map_removed_on_clean() {
+    let project = Project::new("emit-map");
+    project.write(
+        "src/main.c",
+        "int util(void);\nint main(void) {\n    return util();\n}\n",
+    );
+    project.write("lib/util.c", "int util(void) {\n    return 0;\n}\n");
+    project.config(&[
+        target("libutil", "dll", "./lib/").set("emit_map", "true"),
+        target("app", "exe", "./src/")
+            .set("emit_map", "true")
+            .set("deps", "[\"libutil\"]"),
+    ]);
+    project.run_ok(&["-b"]);
+
+    for name in ["app", "libutil"] {
+        let map =
+            std::fs::read_to_string(project.path(&format!("ruxgo_bld/bin/{}.map", name))).unwrap();
+        assert!(map.contains(".text"), "{}", map);
+    }
+    let map = std::fs::read_to_string(project.path("ruxgo_bld/bin/app.map")).unwrap();
+    assert!(map.contains("main"), "{}", map);
+
+    project.run_ok(&["--clean", "bins"]);
+    assert!(!project.path("ruxgo_bld/bin/app.map").exists());
+    assert!(!project.path("ruxgo_bld/bin/libutil.map").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn no_map_by_default() {
+    let project = Project::new("emit-map-off");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src/")]);
+    project.run_ok(&["-b"]);
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+    assert!(!project.path("ruxgo_bld/bin/app.map").exists());
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+
+/// Writes a project whose targets all build `src/main.c`
+fn project(name: &str, targets: &[(&str, &str)]) -> Project {
+    let project = Project::new(name);
+    project.write("src/main.c", MAIN_C);
+    project.config(
+        &targets
+            .iter()
+            .map(|(name, typ)| target(name, typ, "./src"))
+            .collect::<Vec<_>>(),
+    );
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn no_exe_target_is_an_error() {
+    let project = project("exe-none", &[("util", "static")]);
+
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output)
+        .contains("No executable target found, exactly one target must have type \"exe\""));
+    assert!(!stdout(&output).contains("--target <name>"));
+
+    let output = project.run(&["check"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("No executable target found"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn multiple_exe_targets_are_named() {
+    let project = project(
+        "exe-multiple",
+        &[("a", "exe"), ("util", "static"), ("b", "exe")],
+    );
+
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    let out = stdout(&output);
+    assert!(
+        out.contains("Multiple executable targets found: a, b"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("Use `--target <name>` to select which one to build or run"),
+        "{}",
+        out
+    );
+
+    let output = project.run(&["check"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Multiple executable targets found: a, b"));
+
+    // Selecting one of them lifts the constraint
+    project.run_ok(&["-b", "--target", "b"]);
+    assert!(project.path("ruxgo_bld/bin/b.bin").exists());
+    assert!(!project.path("ruxgo_bld/bin/a.bin").exists());
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn explain_reports_changed_include_file() {
+    let project = Project::new("explain");
+    project.write("inc/value.h", "#define VALUE 0\n");
+    project.write(
+        "src/main.c",
+        "#include \"value.h\"\nint main(void) {\n    return VALUE;\n}\n",
+    );
+    project.config(&[target("app", "exe", "./src").set("include_dir", "[\"./inc\"]")]);
+
+    let stdout = project.run_ok(&["-b", "--explain"]);
+    assert!(stdout.contains("Binary does not exist"), "{}", stdout);
+
+    // Without --explain the reasons stay at the debug level
+    project.write("inc/value.h", "#define VALUE 1\n");
+    let stdout = project.run_ok(&["-b"]);
+    assert!(
+        !stdout.contains("depends on changed include file"),
+        "{}",
+        stdout
+    );
+
+    project.write("inc/value.h", "#define VALUE 2\n");
+    let stdout = project.run_ok(&["-b", "--explain"]);
+    assert!(
+        stdout.contains("src/main.c depends on changed include file: ./inc/value.h"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+use std::fs;
+use std::process::Output;
+
+/// Sets up a project with one misformatted source, one formatted and one misformatted but
+/// excluded, and a fake clang-format treating "BAD" as misformatted
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.script(
+        "bin/clang-format",
+        "#!/bin/sh\nif [ \"$1\" = --dry-run ]; then\n    ! grep -q BAD \"$3\"\nelse\n    shift\n    sed -i 's/BAD/GOOD/' \"$@\"\nfi\n",
+    );
+    project.write("src/main.c", MAIN_C);
+    project.write("src/bad.c", "int BAD;\n");
+    project.write("src/unused/skipped.c", "int BAD;\n");
+    project.config(&[target("app", "exe", "./src").set("src_exclude", "[\"unused/\"]")]);
+    project
+}
+
+/// Runs `ruxgo fmt` with the fake clang-format first on PATH
+fn fmt(project: &Project, args: &[&str]) -> Output {
+    common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("fmt")
+        .args(args)
+        .env(
+            "PATH",
+            format!(
+                "{}:{}",
+                project.path("bin").display(),
+                std::env::var("PATH").unwrap()
+            ),
+        )
+        .output()
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn fmt_check_reports_unformatted_files() {
+    let project = project("fmt-check");
+    let output = fmt(&project, &["--check"]);
+    assert!(!output.status.success());
+    let out = stdout(&output);
+    assert!(out.contains("Not formatted: ./src/bad.c"), "{}", out);
+    assert!(
+        !out.contains("main.c") && !out.contains("skipped.c"),
+        "{}",
+        out
+    );
+    assert!(out.contains("1 of 2 file(s) are not formatted"), "{}", out);
+    // Nothing is modified
+    assert_eq!(
+        fs::read_to_string(project.path("src/bad.c")).unwrap(),
+        "int BAD;\n"
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn fmt_formats_all_but_excluded_files() {
+    let project = project("fmt-write");
+    let output = fmt(&project, &[]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(
+        fs::read_to_string(project.path("src/bad.c")).unwrap(),
+        "int GOOD;\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project.path("src/unused/skipped.c")).unwrap(),
+        "int BAD;\n"
+    );
+
+    let output = fmt(&project, &["--check"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("All 2 file(s) are formatted"));
+}
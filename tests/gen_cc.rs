@@ -0,0 +1,76 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::process::Command;
+
+#[test]
+#[cfg(target_os = "linux")]
+fn gen_cc_merges_separate_builds() {
+    let project = Project::new("gen-cc");
+    project.write("first/first.c", MAIN_C);
+    project.write("second/second.c", MAIN_C);
+    project.config(&[
+        target("first", "exe", "./first"),
+        target("second", "exe", "./second"),
+    ]);
+
+    project.run_ok(&["-b", "--gen-cc", "--target", "first"]);
+    project.run_ok(&["-b", "--gen-cc", "--target", "second"]);
+    // Building a target again updates its entry instead of adding another one
+    project.run_ok(&["-b", "--gen-cc", "--target", "second"]);
+
+    let db = std::fs::read_to_string(project.path("compile_commands.json")).unwrap();
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&db).unwrap();
+    let files: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["file"].as_str().unwrap())
+        .collect();
+    assert_eq!(files.len(), 2, "{}", db);
+    assert!(files.iter().any(|file| file.ends_with("first.c")));
+    assert!(files.iter().any(|file| file.ends_with("second.c")));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn gen_cc_writes_to_custom_path() {
+    let project = Project::new("gen-cc-path");
+    let app = project.path("app");
+    project.write("app/src/main.c", MAIN_C);
+    project.write(
+        "app/config_linux.toml",
+        &common::config("", &[target("app", "exe", "./src")]),
+    );
+
+    // The path is relative to where ruxgo is started, not to the project
+    let output = common::ruxgo()
+        .current_dir(&project.root)
+        .args([
+            "--path",
+            "app",
+            "-b",
+            "--gen-cc=build/compile_commands.json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert!(!app.join("compile_commands.json").exists());
+
+    let db = std::fs::read_to_string(project.path("build/compile_commands.json")).unwrap();
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&db).unwrap();
+    assert_eq!(entries.len(), 1, "{}", db);
+    let entry = &entries[0];
+    assert_eq!(entry["directory"], app.to_str().unwrap(), "{}", db);
+    assert_eq!(
+        entry["file"],
+        app.join("src/main.c").to_str().unwrap(),
+        "{}",
+        db
+    );
+    // The command still runs from the directory of the entry
+    let status = Command::new("sh")
+        .args(["-c", entry["command"].as_str().unwrap()])
+        .current_dir(entry["directory"].as_str().unwrap())
+        .status()
+        .unwrap();
+    assert!(status.success(), "{}", db);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use serde_json::{json, Map, Value};
+
+/// Parses the YAML subset of a .clangd file, maps of maps, sequences and JSON scalars,
+/// panicking on anything else
+fn parse_clangd(yaml: &str) -> Value {
+    let mut root = Map::new();
+    let mut section = None;
+    let mut key: Option<String> = None;
+    for line in yaml.lines().filter(|line| !line.starts_with('#')) {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        match (indent, line.strip_prefix("- "), line.split_once(": ")) {
+            (0, None, None) => {
+                let name = line.strip_suffix(':').expect(line).to_string();
+                root.insert(name.clone(), json!({}));
+                section = Some(name);
+            }
+            (2, None, Some((name, value))) => {
+                let value: Value = serde_json::from_str(value).expect(line);
+                root[section.as_ref().expect(line)][name] = value;
+            }
+            (2, None, None) => {
+                let name = line.strip_suffix(':').expect(line).to_string();
+                root[section.as_ref().expect(line)][&name] = json!([]);
+                key = Some(name);
+            }
+            (4, Some(item), _) => {
+                let item: Value = serde_json::from_str(item).expect(line);
+                root[section.as_ref().expect(line)][key.as_ref().expect(line)]
+                    .as_array_mut()
+                    .unwrap()
+                    .push(item);
+            }
+            _ => panic!("unexpected line {:?}", line),
+        }
+    }
+    Value::Object(root)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn gen_clangd_points_at_the_database() {
+    let project = Project::new("gen-clangd");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src")
+        .set("include_dir", "[\"./include\"]")
+        .set("cflags", "\"-mgeneral-regs-only -DX\"")]);
+
+    // A relative --gen-cc path is relative to the current dir, not the project
+    let database = project.path("build");
+    project.run_ok(&[
+        "-b",
+        &format!("--gen-cc={}/compile_commands.json", database.display()),
+        "--gen-clangd",
+    ]);
+    let clangd = std::fs::read_to_string(project.path(".clangd")).unwrap();
+    let flags = &parse_clangd(&clangd)["CompileFlags"];
+    assert_eq!(
+        flags["CompilationDatabase"],
+        database.display().to_string(),
+        "{}",
+        clangd
+    );
+    assert_eq!(
+        flags["Add"],
+        json!([format!("-I{}", project.path("include").display())]),
+        "{}",
+        clangd
+    );
+    assert_eq!(
+        flags["Remove"],
+        json!(["-mgeneral-regs-only"]),
+        "{}",
+        clangd
+    );
+
+    // Generating it again overwrites it
+    project.run_ok(&["-b", "--gen-clangd"]);
+    let regenerated = std::fs::read_to_string(project.path(".clangd")).unwrap();
+    assert_eq!(regenerated.matches("CompileFlags:").count(), 1);
+    assert_eq!(
+        parse_clangd(&regenerated)["CompileFlags"]["CompilationDatabase"],
+        "."
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+
+/// Builds the project with `--gen-vsc` and `dir` first on PATH, returning the compiler path
+/// written to c_cpp_properties.json
+fn vsc_compiler_path(project: &Project, dir: &str) -> String {
+    let path = format!("{}:{}", dir, std::env::var("PATH").unwrap());
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .args(["-b", "--gen-vsc"])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stdout(&output));
+    let props = std::fs::read_to_string(project.path(".vscode/c_cpp_properties.json")).unwrap();
+    let props: serde_json::Value = serde_json::from_str(&props).unwrap();
+    props["configurations"][0]["compilerPath"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn gen_vsc_resolves_the_compiler_from_path() {
+    let project = Project::new("gen-vsc-path");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src")]);
+    let gcc = project.script("toolchain/gcc", "#!/bin/sh\nexec /usr/bin/gcc \"$@\"\n");
+    // Not executable, so skipped by the search
+    project.write("shadow/gcc", "");
+
+    let toolchain = project.path("toolchain").display().to_string();
+    assert_eq!(
+        vsc_compiler_path(&project, &toolchain),
+        gcc.display().to_string()
+    );
+    let shadow = format!("{}:{}", project.path("shadow").display(), toolchain);
+    assert_eq!(
+        vsc_compiler_path(&project, &shadow),
+        gcc.display().to_string()
+    );
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{stdout, Project};
+
+/// Runs `ruxgo config` with the given arguments and returns its stdout
+fn config(project: &Project, args: &[&str]) -> String {
+    let output = project.run_in(".", &[&["config"], args].concat());
+    assert!(output.status.success(), "{}", stdout(&output));
+    stdout(&output)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn config_get_prints_each_parameter() {
+    let project = Project::new("global-config-get");
+    assert_eq!(config(&project, &["--get", "default_compiler"]), "gcc\n");
+    assert_eq!(config(&project, &["--get", "default_language"]), "cpp\n");
+    assert_eq!(config(&project, &["--get", "license"]), "NONE\n");
+
+    config(&project, &["default_compiler", "clang"]);
+    config(&project, &["default_language", "c"]);
+    config(&project, &["license", "MIT"]);
+    assert_eq!(config(&project, &["--get", "default_compiler"]), "clang\n");
+    assert_eq!(config(&project, &["--get", "default_language"]), "c\n");
+    assert_eq!(config(&project, &["--get", "license"]), "MIT\n");
+
+    let output = project.run_in(".", &["config", "--get", "compiler"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Invalid parameter"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn config_list_prints_all_parameters() {
+    let project = Project::new("global-config-list");
+    assert_eq!(
+        config(&project, &["--list"]),
+        "default_compiler = gcc\ndefault_language = cpp\nlicense = NONE\n"
+    );
+
+    config(&project, &["default_compiler", "clang"]);
+    assert_eq!(
+        config(&project, &["--list"]),
+        "default_compiler = clang\ndefault_language = cpp\nlicense = NONE\n"
+    );
+
+    // Setting still needs a value
+    let output = project.run_in(".", &["config", "license"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn invalid_value_leaves_the_config_unchanged() {
+    let project = Project::new("global-config-invalid");
+    config(&project, &["default_compiler", "clang"]);
+    let output = project.run_in(".", &["config", "default_compiler", "msvc"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Invalid compiler \"msvc\""));
+    assert_eq!(config(&project, &["--get", "default_compiler"]), "clang\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn config_without_home_is_a_clear_error() {
+    let project = Project::new("global-config-no-home");
+    project.write("src/main.c", common::MAIN_C);
+    project.config(&[common::target("app", "exe", "./src")]);
+    let ruxgo = |args: &[&str]| {
+        common::ruxgo()
+            .current_dir(&project.root)
+            .env_remove("HOME")
+            .env("XDG_CONFIG_HOME", project.path("xdg"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    for args in [&["config", "--list"][..], &["config", "license", "MIT"]] {
+        let output = ruxgo(args);
+        assert_eq!(output.status.code(), Some(1), "{}", stdout(&output));
+        assert!(
+            stdout(&output).contains(
+                "[ERROR] HOME is not set, it is needed to find the global config of ruxgo"
+            ),
+            "{}",
+            stdout(&output)
+        );
+    }
+    // Nothing was written in place of the home
+    assert!(!project.path("xdg").exists());
+
+    // Building doesn't depend on the home directory
+    let output = ruxgo(&["-b"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn exe_uses_the_include_dir_of_a_header_only_dep() {
+    let project = Project::new("header-only");
+    project.write("headers/inc/value.h", "#define VALUE 0\n");
+    project.write(
+        "app/main.c",
+        "#include \"value.h\"\n\nint main(void) {\n    return VALUE;\n}\n",
+    );
+    project.config(&[
+        // Its src directory does not exist, it has no sources
+        target("headers", "header-only", "./headers/src").set("include_dir", "[\"./headers/inc\"]"),
+        target("app", "exe", "./app").set("deps", "[\"headers\"]"),
+    ]);
+
+    let stdout = project.run_ok(&["check"]);
+    assert!(
+        stdout.contains("headers (header-only): 0 source files"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("is valid"), "{}", stdout);
+
+    let stdout = project.run_ok(&["-b", "--gen-cc"]);
+    assert!(
+        stdout.contains("Target: headers is header-only"),
+        "{}",
+        stdout
+    );
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+    let db = std::fs::read_to_string(project.path("compile_commands.json")).unwrap();
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&db).unwrap();
+    assert_eq!(entries.len(), 1, "{}", db);
+    let command = entries[0]["command"].as_str().unwrap();
+    assert!(command.contains("-I./headers/inc"), "{}", command);
+
+    let manifest = std::fs::read_to_string(project.path("ruxgo_bld/build-manifest.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    assert_eq!(manifest["targets"][0]["name"], "headers");
+    assert_eq!(manifest["targets"][0]["type"], "header-only");
+}
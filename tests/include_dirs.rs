@@ -0,0 +1,49 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn overlapping_include_dirs_are_passed_once() {
+    let project = Project::new("include-dirs");
+    project.write("inc/add.h", "int add(int a, int b);\n");
+    project.write(
+        "lib/add.c",
+        "#include \"add.h\"\nint add(int a, int b) {\n    return a + b;\n}\n",
+    );
+    project.write(
+        "src/main.c",
+        "#include \"add.h\"\nint main(void) {\n    return add(0, 0);\n}\n",
+    );
+    // The exe and both libs share the include dir
+    project.config(&[
+        target("add", "static", "./lib").set("include_dir", "[\"./inc\"]"),
+        target("libadd2", "static", "./lib").set("include_dir", "[\"./inc\"]"),
+        target("app", "exe", "./src")
+            .set("include_dir", "[\"./inc\", \"./inc\"]")
+            .set("deps", "[\"add\", \"libadd2\"]"),
+    ]);
+
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .args(["-b", "--gen-cc"])
+        .env("RUXGO_LOG_LEVEL", "Info")
+        .output()
+        .unwrap();
+    let stdout = common::stdout(&output);
+    assert!(output.status.success(), "{}", stdout);
+
+    let build_cmd = stdout
+        .lines()
+        .find(|line| line.contains("Command:") && line.contains("src/main.c"))
+        .unwrap();
+    assert_eq!(build_cmd.matches("-I./inc").count(), 1, "{}", build_cmd);
+
+    let compile_commands = std::fs::read_to_string(project.path("compile_commands.json")).unwrap();
+    let app_entry = compile_commands
+        .split("},")
+        .find(|entry| entry.contains("src/main.c"))
+        .unwrap();
+    assert_eq!(app_entry.matches("-I./inc").count(), 1, "{}", app_entry);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{stdout, Project};
+use std::fs;
+
+#[test]
+#[cfg(target_os = "linux")]
+fn init_here_populates_the_current_dir() {
+    let project = Project::new("init-here");
+    let output = project.run_in("app", &["init", "app", "--here", "--c"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    for file in ["config_linux.toml", "src/main.c", "README.md", ".gitignore"] {
+        assert!(project.path("app").join(file).exists(), "{}", file);
+    }
+    // No subdirectory named after the project
+    assert!(!project.path("app/app").exists());
+
+    let output = project.run_in("app", &["-b"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn init_force_keeps_existing_files() {
+    let project = Project::new("init-force");
+    project.write("app/README.md", "my readme\n");
+    project.write("app/src/main.c", "int main(void) {\n    return 3;\n}\n");
+
+    let output = project.run_in("app", &["init", "app", "--here", "--c"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("use --force"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(!project.path("app/config_linux.toml").exists());
+
+    let output = project.run_in("app", &["init", "app", "--here", "--force", "--c"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(project.path("app/config_linux.toml").exists());
+    assert!(project.path("app/LICENSE").exists());
+    let read = |file: &str| fs::read_to_string(project.path("app").join(file)).unwrap();
+    assert_eq!(read("README.md"), "my readme\n");
+    assert_eq!(read("src/main.c"), "int main(void) {\n    return 3;\n}\n");
+
+    // An existing config is kept as well
+    fs::write(project.path("app/config_linux.toml"), "# mine\n").unwrap();
+    let output = project.run_in("app", &["init", "app", "--here", "--force", "--c"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(read("config_linux.toml"), "# mine\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn init_force_populates_an_existing_subdir() {
+    let project = Project::new("init-force-subdir");
+    project.write("app/README.md", "my readme\n");
+    let output = project.run_in(".", &["init", "app", "--force", "--c"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(project.path("app/src/main.c").exists());
+    assert_eq!(
+        fs::read_to_string(project.path("app/README.md")).unwrap(),
+        "my readme\n"
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn init_os_writes_a_valid_os_config() {
+    let project = Project::new("init-os");
+    for (arch, platform, ulib, lang) in [
+        ("x86_64", "x86_64-qemu-q35", "ruxlibc", "--c"),
+        ("riscv64", "riscv64-qemu-virt", "ruxlibc", "--c"),
+        ("aarch64", "aarch64-qemu-virt", "ruxmusl", "--cpp"),
+    ] {
+        let name = format!("app_{}", arch);
+        let output = project.run_in(".", &["init", &name, lang, "--os", "--arch", arch]);
+        assert!(output.status.success(), "{}", stdout(&output));
+
+        let output = project.run_in(&name, &["check"]);
+        assert!(output.status.success(), "{}", stdout(&output));
+
+        let config: toml::Value = toml::from_str(
+            &fs::read_to_string(project.path(&name).join("config_linux.toml")).unwrap(),
+        )
+        .unwrap();
+        let os = &config["os"];
+        assert_eq!(os["name"].as_str(), Some("ruxos"));
+        assert_eq!(os["ulib"].as_str(), Some(ulib));
+        assert!(os["services"].is_array());
+        assert_eq!(os["platform"]["name"].as_str(), Some(platform));
+        assert!(os["platform"]["qemu"].is_table());
+        assert!(config["targets"][0]["linker"]
+            .as_str()
+            .unwrap()
+            .starts_with("rust-lld"));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn init_writes_the_license_of_an_spdx_id() {
+    let project = Project::new("init-license");
+    let output = project.run_in(".", &["config", "license", "mit"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    let output = project.run_in(".", &["init", "app", "--c"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    let license = fs::read_to_string(project.path("app/LICENSE")).unwrap();
+    assert!(license.starts_with("MIT License\n"), "{}", license);
+    assert!(license.contains("Copyright (c) 20"), "{}", license);
+}
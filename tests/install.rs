@@ -0,0 +1,65 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+use std::os::unix::fs::PermissionsExt;
+
+/// Writes a project with an exe and a static lib whose include dir has a nested header
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("app/main.c", MAIN_C);
+    project.write("util/src/util.c", "int util(void) {\n    return 1;\n}\n");
+    project.write("util/inc/util.h", "int util(void);\n");
+    project.write("util/inc/util/detail.h", "#define UTIL_DETAIL 1\n");
+    project.config(&[
+        target("libutil", "static", "./util/src").set("include_dir", "[\"./util/inc\"]"),
+        target("app", "exe", "./app").set("deps", "[\"libutil\"]"),
+    ]);
+    project.run_ok(&["-b"]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn install_copies_into_the_prefix_subdirs() {
+    let project = project("install-prefix");
+    let prefix = project.path("prefix");
+
+    let out = project.run_ok(&["install", "--prefix", prefix.to_str().unwrap()]);
+    assert!(out.contains("Installed 4 file(s)"), "{}", out);
+    let bin = prefix.join("bin/app.bin");
+    assert!(bin.is_file(), "{}", out);
+    assert_ne!(bin.metadata().unwrap().permissions().mode() & 0o111, 0);
+    assert!(prefix.join("lib/libutil.a").is_file(), "{}", out);
+    assert!(prefix.join("include/util.h").is_file(), "{}", out);
+    assert!(prefix.join("include/util/detail.h").is_file(), "{}", out);
+    // Only the exe goes to bin and only the lib to lib
+    assert_eq!(std::fs::read_dir(prefix.join("bin")).unwrap().count(), 1);
+    assert_eq!(std::fs::read_dir(prefix.join("lib")).unwrap().count(), 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn install_dry_run_copies_nothing() {
+    let project = project("install-dry-run");
+    let prefix = project.path("prefix");
+
+    let out = project.run_ok(&["install", "--prefix", prefix.to_str().unwrap(), "--dry-run"]);
+    for dest in ["bin/app.bin", "lib/libutil.a", "include/util.h"] {
+        let line = format!("-> {}", prefix.join(dest).display());
+        assert!(out.contains(&line), "{}", out);
+    }
+    assert!(out.contains("Would install"), "{}", out);
+    assert!(!prefix.exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn install_before_a_build_asks_to_build() {
+    let project = Project::new("install-unbuilt");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src")]);
+
+    let output = project.run(&["install", "--prefix", "prefix"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("run `ruxgo -b` first"));
+}
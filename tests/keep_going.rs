@@ -0,0 +1,42 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn keep_going_reports_every_link_failure() {
+    let project = Project::new("keep-going");
+    project.write("app/main.c", MAIN_C);
+    project.write(
+        "bad/bad.c",
+        "int missing(void);\n\nint bad(void) {\n    return missing();\n}\n",
+    );
+    project.write(
+        "wrapper/wrapper.c",
+        "int wrapper(void) {\n    return 0;\n}\n",
+    );
+    project.config(&[
+        target("app", "exe", "./app"),
+        target("libbad", "dll", "./bad").set("ldflags", "\"-Wl,--no-undefined\""),
+        target("libwrapper", "dll", "./wrapper").set("deps", "[\"libbad\"]"),
+    ]);
+
+    let output = project.run(&["-b", "--keep-going"]);
+    let stdout = common::stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("Linked: app"), "{}", stdout);
+    assert!(stdout.contains("Failed to link: libbad"), "{}", stdout);
+    assert!(
+        stdout.contains("Skipped because of a failed dependency: libwrapper"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("Build complete!"), "{}", stdout);
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+
+    // Without it the build stops at the first failure
+    let output = project.run(&["-b"]);
+    let stdout = common::stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(!stdout.contains("Failed to link:"), "{}", stdout);
+}
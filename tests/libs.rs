@@ -0,0 +1,67 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn libs_are_linked_after_the_objects_and_deps() {
+    let project = Project::new("libs");
+    project.write(
+        "app/main.c",
+        "#include <math.h>\nint util(void);\nint main(int argc, char **argv) {\n    \
+         (void)argv;\n    return (int)sqrt((double)argc) - 1 + util();\n}\n",
+    );
+    project.write("util/util.c", "int util(void) {\n    return 0;\n}\n");
+    project.write("libdir/.keep", "");
+    let cc = project.logging_gcc();
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc,
+            "",
+            &[
+                target("libutil", "static", "./util"),
+                target("app", "exe", "./app")
+                    .set("deps", "[\"libutil\"]")
+                    .set("libs", "[\"m\"]")
+                    .set("lib_dirs", "[\"./libdir\"]")
+                    .set("ldflags", "\"-Wl,--no-as-needed\""),
+            ],
+        ),
+    );
+
+    project.run_ok(&["-b"]);
+    let log = std::fs::read_to_string(project.path("cc.log")).unwrap();
+    let link: Vec<&str> = log
+        .lines()
+        .find(|line| line.contains("app.bin"))
+        .expect(&log)
+        .split(' ')
+        .collect();
+    let position = |arg: &str| {
+        link.iter()
+            .position(|a| *a == arg)
+            .unwrap_or_else(|| panic!("{} not in {:?}", arg, link))
+    };
+    let obj = link
+        .iter()
+        .position(|arg| arg.ends_with("app-main.o"))
+        .unwrap();
+    let dep = link
+        .iter()
+        .position(|arg| arg.ends_with("libutil.a"))
+        .unwrap();
+    // After the objects and deps so that they resolve their symbols, before the ldflags
+    assert!(obj < position("-L./libdir"), "{:?}", link);
+    assert!(dep < position("-lm"), "{:?}", link);
+    assert!(position("-L./libdir") < position("-lm"), "{:?}", link);
+    assert!(
+        position("-lm") < position("-Wl,--no-as-needed"),
+        "{:?}",
+        link
+    );
+    let status = std::process::Command::new(project.path("ruxgo_bld/bin/app.bin"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
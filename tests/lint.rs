@@ -0,0 +1,46 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn lint_runs_neither_pre_build_nor_the_compiler() {
+    let project = Project::new("lint");
+    project.write("src/main.c", MAIN_C);
+    project.write("compile_commands.json", "[]");
+    let cc = project.script("cc.sh", "#!/bin/sh\necho \"$@\" >> cc.log\n");
+    project.script(
+        "bin/clang-tidy",
+        "#!/bin/sh\nfor arg in \"$@\"; do last=\"$arg\"; done\necho \"$last:1:1: warning: checked\"\n",
+    );
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc.display().to_string(),
+            "",
+            &[target("app", "exe", "./src").set("pre_build", "\"echo run >> runs\"")],
+        ),
+    );
+
+    let path = format!(
+        "{}:{}",
+        project.path("bin").display(),
+        std::env::var("PATH").unwrap()
+    );
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("lint")
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    let stdout = common::stdout(&output);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("Linted 1 file(s): 1 warning(s)"),
+        "{}",
+        stdout
+    );
+    assert!(!project.path("runs").exists(), "pre_build was run");
+    assert!(!project.path("cc.log").exists(), "the compiler was run");
+}
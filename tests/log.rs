@@ -0,0 +1,194 @@
+mod common;
+
+use common::{stdout, target, Project, MAIN_C};
+use serde_json::Value;
+
+/// Writes a project with a single exe target
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src")]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn json_format_prints_a_json_object_per_line() {
+    for (project, args, env) in [
+        (
+            project("log-json-flag"),
+            &["--format", "json", "-b"][..],
+            None,
+        ),
+        (project("log-json-env"), &["-b"][..], Some("json")),
+    ] {
+        let mut command = common::ruxgo();
+        command.arg("--path").arg(&project.root).args(args);
+        if let Some(format) = env {
+            command.env("RUXGO_LOG_FORMAT", format);
+        }
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{}", stdout(&output));
+
+        let lines: Vec<Value> = stdout(&output)
+            .lines()
+            .map(|line| serde_json::from_str(line).expect(line))
+            .collect();
+        assert!(!lines.is_empty());
+        for line in &lines {
+            assert!(line["level"].is_string(), "{}", line);
+            assert!(line["msg"].is_string(), "{}", line);
+        }
+        assert!(lines
+            .iter()
+            .any(|line| line["level"] == "log" && line["msg"] == "Build complete!"));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn quiet_only_prints_warnings_and_errors() {
+    let project = project("log-quiet");
+    let output = project.run(&["--quiet", "-b"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert!(project.path("ruxgo_bld/bin/app.bin").exists());
+
+    let output = project.run(&["--quiet", "-b", "--target", "nope"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).starts_with("[ERROR] "),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn colors_follow_no_color_and_the_terminal() {
+    let project = project("log-color");
+    let run = |env: &[(&str, &str)]| {
+        let output = common::ruxgo()
+            .arg("--path")
+            .arg(&project.root)
+            .arg("check")
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .envs(env.iter().copied())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", stdout(&output));
+        stdout(&output)
+    };
+
+    // Piped output has no colors unless forced
+    assert!(!run(&[]).contains('\x1b'));
+    assert!(run(&[("CLICOLOR_FORCE", "1")]).contains("\x1b["));
+    // NO_COLOR wins over CLICOLOR_FORCE
+    let out = run(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]);
+    assert!(!out.contains('\x1b'), "{:?}", out);
+    assert!(out.contains("[LOG] "));
+}
+
+/// Returns the seconds of a `[    12.345s] ` line prefix, if the line has one
+fn elapsed(line: &str) -> Option<f64> {
+    let prefix = line.strip_prefix('[')?.get(..11)?;
+    let seconds = prefix.strip_suffix('s')?;
+    let (whole, millis) = seconds.trim_start().split_once('.')?;
+    if line.get(12..14) != Some("] ")
+        || whole.is_empty()
+        || millis.len() != 3
+        || !whole
+            .chars()
+            .chain(millis.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    seconds.trim_start().parse().ok()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn timestamps_prefix_each_line() {
+    let project = project("log-timestamps");
+    let out = stdout(&project.run(&["check"]));
+    assert!(out.lines().all(|line| elapsed(line).is_none()), "{}", out);
+
+    for (args, env) in [
+        (&["--log-timestamps", "-b"][..], None),
+        (&["-c", "all", "-b"][..], Some("1")),
+    ] {
+        let mut command = common::ruxgo();
+        command.arg("--path").arg(&project.root).args(args);
+        if let Some(value) = env {
+            command.env("RUXGO_LOG_TIMESTAMPS", value);
+        }
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{}", stdout(&output));
+        let times: Vec<f64> = stdout(&output)
+            .lines()
+            .map(|line| elapsed(line).unwrap_or_else(|| panic!("{:?}", line)))
+            .collect();
+        assert!(!times.is_empty());
+        assert!(
+            times.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{:?}",
+            times
+        );
+    }
+
+    let output = project.run(&["--log-timestamps", "--format", "json", "check"]);
+    for line in stdout(&output).lines() {
+        let line: Value = serde_json::from_str(line).unwrap();
+        assert!(line["time"].is_f64(), "{}", line);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn log_file_gets_the_messages_filtered_from_the_console() {
+    let project = project("log-file");
+    let log_file = project.path("ruxgo.log");
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("-b")
+        .env("RUXGO_LOG_LEVEL", "Warn")
+        .env("RUXGO_LOG_FILE", &log_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let logged = std::fs::read_to_string(&log_file).unwrap();
+    assert!(logged.contains("[LOG] Build complete!"), "{}", logged);
+    assert!(
+        logged.lines().any(|line| line.starts_with("[DEBUG] ")),
+        "{}",
+        logged
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn unwritable_log_file_warns_once() {
+    let project = project("log-file-unwritable");
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("-b")
+        .env("RUXGO_LOG_FILE", project.path("missing/ruxgo.log"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("Could not create log file").count(),
+        1,
+        "{}",
+        stderr
+    );
+    // The console is unaffected
+    assert!(stdout(&output).contains("Build complete!"));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn reverted_source_is_served_from_the_cache() {
+    let project = Project::new("obj-cache");
+    project.write("src/main.c", MAIN_C);
+    let cc = project.logging_gcc();
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(&cc, "obj_cache = true\n", &[target("app", "exe", "./src")]),
+    );
+
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 1);
+
+    project.write("src/main.c", "int main(void) {\n    return 1;\n}\n");
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 2);
+
+    // Back to the first state, no compiler run
+    project.write("src/main.c", MAIN_C);
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 2);
+    let status = std::process::Command::new(project.path("ruxgo_bld/bin/app.bin"))
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn no_cache_by_default() {
+    let project = Project::new("obj-cache-off");
+    project.write("src/main.c", MAIN_C);
+    let cc = project.logging_gcc();
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(&cc, "", &[target("app", "exe", "./src")]),
+    );
+    project.run_ok(&["-b"]);
+    project.write("src/main.c", "int main(void) {\n    return 1;\n}\n");
+    project.run_ok(&["-b"]);
+    project.write("src/main.c", MAIN_C);
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 3);
+    assert!(!project.path("ruxgo_bld/obj_cache").exists());
+}
@@ -0,0 +1,471 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::fs;
+use std::process::Output;
+
+/// Sets up a project on Ruxos whose cargo, linker and objcopy are fakes, cargo logging each run
+/// and the `RUX_*` variables it sees, and creating its target dir
+fn project(name: &str, services: &str) -> Project {
+    let project = Project::new(name);
+    project.script(
+        "bin/cargo",
+        &format!(
+            "#!/bin/sh\necho \"$@\" >> {}\nenv | grep '^RUX_' | sort > {}\n\
+             while [ $# -gt 0 ]; do\n    [ \"$1\" = --target-dir ] && mkdir -p \"$2\"\n    shift\ndone\n",
+            project.path("cargo.log").display(),
+            project.path("cargo.env").display()
+        ),
+    );
+    project.script(
+        "bin/rust-objcopy",
+        "#!/bin/sh\nfor arg; do :; done\ntouch \"$arg\"\n",
+    );
+    let ld = project.script(
+        "bin/ld.sh",
+        "#!/bin/sh\nwhile [ $# -gt 0 ]; do\n    [ \"$1\" = -o ] && touch \"$2\"\n    shift\ndone\n",
+    );
+    project.write("ruxos/modules/ruxhal/linker_x86_64-qemu-q35.lds", "");
+    project.write("ruxos/modules/ruxhal/linker_x86_64-pc-oslab.lds", "");
+    project.write("ruxos/ulib/ruxlibc/c/errno.c", "int errno;\n");
+    project.write("src/main.c", MAIN_C);
+    write_config(&project, services, QEMU_PLATFORM, &ld.display().to_string());
+    project
+}
+
+/// The platform of the project, running on QEMU
+const QEMU_PLATFORM: &str = "name = \"x86_64-qemu-q35\"";
+
+/// Writes the config of the project with the given `[os]` services
+/// # Arguments
+/// * `platform` - The body of `[os.platform]`, without `cross_compile`
+fn write_config(project: &Project, services: &str, platform: &str, ld: &str) {
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            &format!(
+                "[os]\nname = \"ruxos\"\nservices = [{}]\nulib = \"ruxlibc\"\nruxos_dir = {:?}\n\n[os.platform]\ncross_compile = \"\"\n{}\n",
+                services,
+                project.path("ruxos").display(),
+                platform
+            ),
+            &[target("app", "exe", "./src").set("linker", &format!("{:?}", ld))],
+        ),
+    );
+}
+
+/// Builds the project with the fakes first on PATH
+fn build(project: &Project) -> String {
+    build_at(project, "Log")
+}
+
+/// Builds the project like `build`, with `RUXGO_LOG_LEVEL` set to `level`
+fn build_at(project: &Project, level: &str) -> String {
+    let output = try_build_at(project, level);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    common::stdout(&output)
+}
+
+/// Builds the project with the fakes first on PATH, whether the build succeeds or not
+fn try_build_at(project: &Project, level: &str) -> Output {
+    let path = format!(
+        "{}:{}",
+        project.path("bin").display(),
+        std::env::var("PATH").unwrap()
+    );
+    common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("-b")
+        .env("PATH", path)
+        .env("RUXGO_LOG_LEVEL", level)
+        .output()
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn os_is_rebuilt_only_when_its_config_changes() {
+    let project = project("os-build", "\"alloc\"");
+    let cargo_runs = || {
+        fs::read_to_string(project.path("cargo.log"))
+            .unwrap()
+            .lines()
+            .count()
+    };
+
+    let stdout = build(&project);
+    assert!(stdout.contains("all need to be relinked"), "{}", stdout);
+    assert_eq!(cargo_runs(), 1);
+
+    // A no-op build does not run cargo
+    let stdout = build(&project);
+    assert!(stdout.contains("OS: ruxos is up to date"), "{}", stdout);
+    assert!(stdout.contains("Target: app is up to date"), "{}", stdout);
+    assert_eq!(cargo_runs(), 1);
+
+    // Neither does a change of the C sources only
+    project.write("src/main.c", "int main(void) {\n    return 1;\n}\n");
+    let stdout = build(&project);
+    assert!(stdout.contains("OS: ruxos is up to date"), "{}", stdout);
+    assert!(!stdout.contains("Target: app is up to date"), "{}", stdout);
+    assert_eq!(cargo_runs(), 1);
+
+    // Cleaning the OS is the way to rebuild it from changed Ruxos sources
+    project.run_ok(&["-c", "os"]);
+    let stdout = build(&project);
+    assert!(stdout.contains("Compiling OS: ruxos"), "{}", stdout);
+    assert_eq!(cargo_runs(), 2);
+
+    let ld = project.path("bin/ld.sh").display().to_string();
+    write_config(&project, "\"alloc\", \"fs\"", QEMU_PLATFORM, &ld);
+    let stdout = build(&project);
+    assert!(stdout.contains("all need to be relinked"), "{}", stdout);
+    assert!(!stdout.contains("Target: app is up to date"), "{}", stdout);
+    assert_eq!(cargo_runs(), 3);
+    assert!(fs::read_to_string(project.path("cargo.log"))
+        .unwrap()
+        .lines()
+        .last()
+        .unwrap()
+        .contains("fs"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn debug_log_shows_the_cargo_commands() {
+    let project = project("os-build-debug", "\"alloc\"");
+    let cargo_args = || {
+        let log = fs::read_to_string(project.path("cargo.log")).unwrap();
+        log.lines()
+            .last()
+            .unwrap()
+            .split(' ')
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    build(&project);
+    assert!(!cargo_args().contains(&"-v".to_string()));
+    project.run_ok(&["-c", "os"]);
+    build_at(&project, "Debug");
+    assert!(cargo_args().contains(&"-v".to_string()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn qemu_devices_are_exported_to_the_build() {
+    let project = project("os-build-env", "\"alloc\"");
+    let env = || fs::read_to_string(project.path("cargo.env")).unwrap();
+    let ld = project.path("bin/ld.sh").display().to_string();
+
+    build(&project);
+    for var in ["RUX_NET=n", "RUX_BLK=n", "RUX_GRAPHIC=n"] {
+        assert!(env().lines().any(|line| line == var), "{}", env());
+    }
+
+    write_config(
+        &project,
+        "\"alloc\"",
+        &format!(
+            "{}\n\n[os.platform.qemu]\nnet = \"y\"\nblk = \"y\"",
+            QEMU_PLATFORM
+        ),
+        &ld,
+    );
+    build(&project);
+    for var in ["RUX_NET=y", "RUX_BLK=y", "RUX_GRAPHIC=n"] {
+        assert!(env().lines().any(|line| line == var), "{}", env());
+    }
+
+    // Without QEMU there are no devices to report
+    write_config(&project, "\"alloc\"", "name = \"x86_64-pc-oslab\"", &ld);
+    build(&project);
+    assert!(env().contains("RUX_PLATFORM=x86_64-pc-oslab"), "{}", env());
+    for var in ["RUX_NET", "RUX_BLK", "RUX_GRAPHIC"] {
+        assert!(!env().contains(var), "{}", env());
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn misspelled_service_fails_before_building_the_os() {
+    let project = project("os-build-unknown-service", "\"alloc\", \"multitsk\"");
+    let output = try_build_at(&project, "Log");
+    assert!(!output.status.success());
+    assert!(
+        common::stdout(&output)
+            .contains("Unknown service \"multitsk\", did you mean \"multitask\"?"),
+        "{}",
+        common::stdout(&output)
+    );
+    // cargo never ran
+    assert!(!project.path("cargo.log").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn emit_map_is_passed_to_the_os_link() {
+    let project = project("os-build-emit-map", "\"alloc\"");
+    project.script(
+        "bin/ld.sh",
+        &format!(
+            "#!/bin/sh\necho \"$@\" > {}\nwhile [ $# -gt 0 ]; do\n    [ \"$1\" = -o ] && touch \"$2\"\n    shift\ndone\n",
+            project.path("ld.log").display()
+        ),
+    );
+    // The target is the last table of the config
+    let config = fs::read_to_string(project.path("config_linux.toml")).unwrap();
+    project.write("config_linux.toml", &format!("{}emit_map = true\n", config));
+    build(&project);
+
+    let ld_args = fs::read_to_string(project.path("ld.log")).unwrap();
+    assert!(
+        ld_args
+            .split_whitespace()
+            .any(|arg| arg == "-Map=ruxgo_bld/bin/app.map"),
+        "{}",
+        ld_args
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn sysroot_and_search_paths_reach_compile_and_link() {
+    let project = project("os-build-sysroot", "\"alloc\"");
+    fs::create_dir_all(project.path("sysroot")).unwrap();
+    fs::create_dir_all(project.path("extra/include")).unwrap();
+    fs::create_dir_all(project.path("extra/lib")).unwrap();
+    write_config(
+        &project,
+        "\"alloc\"",
+        &format!(
+            "{}\nsysroot = {:?}\nextra_includes = [{:?}]\nextra_lib_dirs = [{:?}]",
+            QEMU_PLATFORM,
+            project.path("sysroot").display(),
+            project.path("extra/include").display(),
+            project.path("extra/lib").display()
+        ),
+        &project.path("bin/ld.sh").display().to_string(),
+    );
+    let stdout = build_at(&project, "Info");
+    let commands: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("[INFO]   Command: "))
+        .collect();
+    let compile = commands
+        .iter()
+        .find(|command| command.contains("main.c"))
+        .expect(&stdout);
+    let link = commands
+        .iter()
+        .find(|command| command.contains("ld.sh"))
+        .expect(&stdout);
+    let sysroot = format!("--sysroot={}", project.path("sysroot").display());
+    let include = format!("-I{}", project.path("extra/include").display());
+    let lib_dir = format!("-L{}", project.path("extra/lib").display());
+    for flag in [&sysroot, &include] {
+        assert!(compile.split(' ').any(|arg| arg == flag), "{}", compile);
+    }
+    for flag in [&sysroot, &lib_dir] {
+        assert!(link.split(' ').any(|arg| arg == flag), "{}", link);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn missing_tool_is_named_before_building() {
+    let project = project("os-build-missing-tool", "\"alloc\"");
+    fs::remove_file(project.path("bin/rust-objcopy")).unwrap();
+    let output = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .arg("-b")
+        .env(
+            "PATH",
+            format!("{}:/usr/bin:/bin", project.path("bin").display()),
+        )
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = common::stdout(&output);
+    assert!(
+        stdout.contains("Required tool not found: rust-objcopy, run `cargo install cargo-binutils"),
+        "{}",
+        stdout
+    );
+    // Only the missing tool is named, and nothing was built
+    assert_eq!(
+        stdout.matches("Required tool not found").count(),
+        1,
+        "{}",
+        stdout
+    );
+    assert!(!project.path("cargo.log").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn failing_os_build_shows_the_cargo_error_as_text() {
+    let project = project("os-build-cargo-error", "\"alloc\"");
+    project.script(
+        "bin/cargo",
+        "#!/bin/sh\necho '   Compiling ruxhal v0.1.0'\n\
+         echo '   Compiling ruxhal v0.1.0' >&2\n\
+         echo 'error[E0432]: unresolved import `crate::caf\u{e9}`' >&2\nexit 101\n",
+    );
+    let output = try_build_at(&project, "Log");
+    assert!(!output.status.success());
+    let stdout = common::stdout(&output);
+    // Each line of cargo is prefixed, and the error is readable text
+    assert!(
+        stdout.contains("[os]    Compiling ruxhal v0.1.0"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "OS build failed (exit status: 101): error[E0432]: unresolved import `crate::caf\u{e9}`"
+        ),
+        "{}",
+        stdout
+    );
+    // Only the error part of stderr is repeated in the failure
+    let failure = stdout
+        .lines()
+        .find(|line| line.contains("OS build failed"))
+        .unwrap();
+    assert!(!failure.contains("Compiling"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn libs_are_left_out_of_the_os_link() {
+    let project = project("os-build-libs", "\"alloc\"");
+    // The app is the last table of the config
+    let config = fs::read_to_string(project.path("config_linux.toml")).unwrap();
+    project.write(
+        "config_linux.toml",
+        &format!("{}libs = [\"m\"]\nlib_dirs = [\"./libdir\"]\n", config),
+    );
+    let stdout = build_at(&project, "Info");
+    assert!(
+        stdout.contains("Ignoring libs of target app, system libraries are not linked into the OS"),
+        "{}",
+        stdout
+    );
+    let link = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("[INFO]   Command: "))
+        .find(|command| command.contains("ld.sh"))
+        .expect(&stdout);
+    assert!(
+        !link
+            .split(' ')
+            .any(|arg| arg == "-lm" || arg == "-L./libdir"),
+        "{}",
+        link
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn missing_linker_script_is_named() {
+    let project = project("os-build-no-lds", "\"alloc\"");
+    fs::remove_file(project.path("ruxos/modules/ruxhal/linker_x86_64-qemu-q35.lds")).unwrap();
+    let output = try_build_at(&project, "Log");
+    assert!(!output.status.success());
+    let stdout = common::stdout(&output);
+    let expected = format!(
+        "Linker script {} not found, set `ruxos_dir` in [os] to the directory of the Ruxos sources",
+        project
+            .path("ruxos/modules/ruxhal/linker_x86_64-qemu-q35.lds")
+            .display()
+    );
+    assert!(stdout.contains(&expected), "{}", stdout);
+    assert!(!project.path("ruxgo_bld/bin/app.bin").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn v9p_mount_tags_are_exported_to_the_build() {
+    let project = project("os-build-9p", "\"alloc\"");
+    write_config(
+        &project,
+        "\"alloc\"",
+        &format!(
+            "{}\n\n[os.platform.qemu]\nv9p = \"y\"\nv9p_shares = [\n\
+             {{ path = \"./root\", mount_tag = \"root\" }},\n\
+             {{ path = \"./data\", mount_tag = \"data\" }},\n]",
+            QEMU_PLATFORM
+        ),
+        &project.path("bin/ld.sh").display().to_string(),
+    );
+    build(&project);
+    let env = fs::read_to_string(project.path("cargo.env")).unwrap();
+    for var in ["RUX_9P_TAGS=root,data", "RUX_PROTOCOL_9P=9P2000.L"] {
+        assert!(env.lines().any(|line| line == var), "{}", env);
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn without_default_features_only_the_services_reach_cargo() {
+    let project = project("os-build-no-default-features", "\"alloc\", \"fs\"");
+    let features = |default_features: bool| {
+        write_config(
+            &project,
+            "\"alloc\", \"fs\"",
+            &format!("{}\nsmp = \"2\"", QEMU_PLATFORM),
+            &project.path("bin/ld.sh").display().to_string(),
+        );
+        let config = fs::read_to_string(project.path("config_linux.toml")).unwrap();
+        project.write(
+            "config_linux.toml",
+            &config.replace(
+                "ulib = ",
+                &format!("default_features = {}\nulib = ", default_features),
+            ),
+        );
+        build(&project);
+        let log = fs::read_to_string(project.path("cargo.log")).unwrap();
+        let last = log.lines().last().unwrap().to_string();
+        let (_, features) = last.split_once("--features ").expect(&last);
+        features.split(' ').map(String::from).collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        features(true),
+        [
+            "ruxfeat/log-level-warn",
+            "ruxfeat/bus-pci",
+            "ruxlibc/smp",
+            "ruxlibc/alloc",
+            "ruxlibc/fs",
+            "ruxlibc/fd"
+        ]
+    );
+    assert_eq!(
+        features(false),
+        ["ruxfeat/log-level-warn", "ruxlibc/alloc", "ruxlibc/fs"]
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn reordered_services_do_not_rebuild_the_os() {
+    let project = project("os-build-reorder", "\"alloc\", \"fs\", \"net\"");
+    build(&project);
+    write_config(
+        &project,
+        "\"net\", \"alloc\", \"fs\", \"alloc\"",
+        QEMU_PLATFORM,
+        &project.path("bin/ld.sh").display().to_string(),
+    );
+    let stdout = build(&project);
+    assert!(stdout.contains("OS: ruxos is up to date"), "{}", stdout);
+    let cargo_runs = fs::read_to_string(project.path("cargo.log"))
+        .unwrap()
+        .lines()
+        .count();
+    assert_eq!(cargo_runs, 1);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn clean_all_removes_exactly_the_output_dir() {
+    let project = Project::new("output-dir");
+    project.write("src/main.c", MAIN_C);
+    project.write("out/notes.txt", "kept\n");
+    project.write(
+        "config_linux.toml",
+        &common::config("", &[target("app", "exe", "./src")])
+            .replace("[build]\n", "[build]\noutput_dir = \"out/build\"\n"),
+    );
+
+    project.run_ok(&["-b"]);
+    assert!(project.path("out/build/bin/app.bin").exists());
+    assert!(!project.path("ruxgo_bld").exists());
+
+    project.run_ok(&["-c", "all"]);
+    assert!(!project.path("out/build").exists());
+    assert!(project.path("out/notes.txt").exists());
+    assert!(project.path("src/main.c").exists());
+    assert!(project.path("config_linux.toml").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn output_dirs_holding_the_project_are_rejected() {
+    let project = Project::new("output-dir-rejected");
+    project.write("src/main.c", MAIN_C);
+    let config = |output_dir: &str| {
+        project.write(
+            "config_linux.toml",
+            &common::config("", &[target("app", "exe", "./src")]).replace(
+                "[build]\n",
+                &format!("[build]\noutput_dir = {:?}\n", output_dir),
+            ),
+        );
+    };
+
+    for output_dir in ["", ".", "./", "..", "src", "./src/../src"] {
+        config(output_dir);
+        let output = project.run(&["-c", "all"]);
+        assert!(!output.status.success(), "{:?}", output_dir);
+        assert!(
+            common::stdout(&output).contains("output_dir: "),
+            "{}",
+            common::stdout(&output)
+        );
+    }
+    assert!(project.path("src/main.c").exists());
+
+    // The command line override is checked as well
+    config("ruxgo_bld");
+    for output_dir in [".", "src"] {
+        let output = project.run(&["--output-dir", output_dir, "-c", "all"]);
+        assert!(!output.status.success(), "{:?}", output_dir);
+    }
+    assert!(project.path("src/main.c").exists());
+    // --target-dir means something else to cargo
+    assert!(!project.run(&["--target-dir", "out", "-b"]).status.success());
+}
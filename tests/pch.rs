@@ -0,0 +1,36 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pch_is_rebuilt_when_its_inputs_change() {
+    let project = Project::new("pch");
+    project.write("inc/common.h", "#include \"value.h\"\n");
+    project.write("inc/value.h", "#define VALUE 0\n");
+    project.write("src/main.c", "int main(void) {\n    return VALUE;\n}\n");
+    let app = |cflags: &str| {
+        target("app", "exe", "./src")
+            .set("include_dir", "[\"./inc\"]")
+            .set("pch", "\"./inc/common.h\"")
+            .set("cflags", &format!("{:?}", cflags))
+    };
+    project.config(&[app("")]);
+
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("Precompiling header"), "{}", stdout);
+    let stdout = project.run_ok(&["-b"]);
+    assert!(!stdout.contains("Precompiling header"), "{}", stdout);
+
+    // A header included by the precompiled one
+    project.write("inc/value.h", "#define VALUE 0 + 0\n");
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("Precompiling header"), "{}", stdout);
+
+    // The flags it is compiled with
+    project.config(&[app("-O2")]);
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("Precompiling header"), "{}", stdout);
+    let stdout = project.run_ok(&["-b"]);
+    assert!(!stdout.contains("Precompiling header"), "{}", stdout);
+}
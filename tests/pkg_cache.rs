@@ -0,0 +1,75 @@
+mod common;
+
+use common::{Project, Server};
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+const PACKAGES: &str = "[[packages]]\ntyp = \"app-bin\"\nname = \"hello\"\nbranch = \"main\"\nversion = \"1.0\"\ndescription = \"Says hello\"\n";
+
+#[test]
+#[cfg(target_os = "linux")]
+fn package_list_is_fetched_again_only_when_the_cache_expired() {
+    let project = Project::new("pkg-cache");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+    let list = |args: &[&str]| {
+        let output = project.pkg(&server, args);
+        assert!(output.status.success(), "{}", common::stdout(&output));
+        assert!(common::stdout(&output).contains("hello"));
+        server.requests().len()
+    };
+
+    assert_eq!(list(&["--list"]), 1);
+    // A fresh cache is reused
+    assert_eq!(list(&["--list"]), 1);
+    // unless a refresh is asked for
+    assert_eq!(list(&["--list", "--refresh"]), 2);
+
+    // An hour old cache has expired
+    let cache = project.path("ruxgo_pkg/cache/package_cache.toml");
+    File::options()
+        .write(true)
+        .open(&cache)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(2 * 3600))
+        .unwrap();
+    assert_eq!(list(&["--list"]), 3);
+    assert_eq!(list(&["--list"]), 3);
+    assert_eq!(server.requests(), vec!["/packages.toml"; 3]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn frozen_uses_only_the_cache() {
+    let project = Project::new("pkg-frozen");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+
+    // Nothing is cached yet
+    let output = project.pkg(&server, &["--list", "--frozen"]);
+    assert!(!output.status.success());
+    assert!(common::stdout(&output)
+        .contains("The package cache is empty and --frozen forbids fetching it"));
+    assert!(server.requests().is_empty());
+    assert!(!project.path("ruxgo_pkg/cache").exists());
+
+    project.pkg(&server, &["--list"]);
+    assert_eq!(server.requests().len(), 1);
+
+    // An expired cache is still used as is
+    let cache = project.path("ruxgo_pkg/cache/package_cache.toml");
+    File::options()
+        .write(true)
+        .open(&cache)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(2 * 3600))
+        .unwrap();
+    let output = project.pkg(&server, &["--list", "--frozen"]);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert!(common::stdout(&output).contains("hello"));
+
+    let output = project.pkg(&server, &["--pull", "hello", "--frozen"]);
+    assert!(!output.status.success());
+    assert!(common::stdout(&output).contains("Cannot pull package 'hello' with --frozen"));
+    assert_eq!(server.requests().len(), 1);
+}
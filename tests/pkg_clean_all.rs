@@ -0,0 +1,77 @@
+mod common;
+
+use common::{stdout, Project, Server};
+use std::process::{Output, Stdio};
+
+/// Writes a pulled app-bin and a package cache
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("ruxgo_pkg/app-bin/hello", "binary");
+    project.write("ruxgo_pkg/app-bin/hello.sh", "#!/bin/sh\n");
+    project.write("ruxgo_pkg/cache/package_cache.toml", "");
+    project.write("ruxgo_pkg/installed.toml", "packages = []\n");
+    project
+}
+
+/// Runs `ruxgo pkg --clean-all` with the given parts and stdin not a terminal
+fn clean_all(project: &Project, server: &Server, args: &[&str]) -> Output {
+    common::ruxgo()
+        .current_dir(&project.root)
+        .env("RUXGO_PKG_URL", &server.url)
+        .env_remove("RUXGO_PKG_YES")
+        .args(["pkg", "--clean-all"])
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn all_with_other_parts_removes_everything_once() {
+    let project = project("pkg-clean-all");
+    let server = Server::new();
+
+    // Not confirmed
+    let output = clean_all(&project, &server, &["all,cache"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("use `--yes` or RUXGO_PKG_YES=1"));
+    assert!(project.path("ruxgo_pkg/app-bin/hello").exists());
+
+    let output = clean_all(&project, &server, &["cache,All,app-src", "--yes"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    let out = stdout(&output);
+    assert_eq!(
+        out.matches("All packages removed successfully!").count(),
+        1,
+        "{}",
+        out
+    );
+    assert!(!out.contains("Cache cleaned"), "{}", out);
+    assert!(!project.path("ruxgo_pkg").exists());
+    // The package list was not needed
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn parts_are_cleaned_without_asking() {
+    let project = project("pkg-clean-parts");
+    let server = Server::new();
+    let output = clean_all(&project, &server, &["cache"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(!project.path("ruxgo_pkg/cache").exists());
+    assert!(project.path("ruxgo_pkg/app-bin/hello").exists());
+
+    let output = clean_all(&project, &server, &["app-bins"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("Unknown part to clean 'app-bins', did you mean 'app-bin'?"),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = clean_all(&project, &server, &[]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("use e.g. `--clean-all all`"));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{stdout, Project, Server};
+use std::fs;
+
+const PACKAGES: &str = "[[packages]]\ntyp = \"app-bin\"\nname = \"hello\"\nbranch = \"main\"\nversion = \"1.0\"\ndescription = \"Says hello\"\n";
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pull_writes_the_package_and_its_script_to_dest() {
+    let project = Project::new("pkg-dest");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+    server.serve("/hello", b"binary");
+    server.serve("/hello.sh", b"#!/bin/sh\necho hello\n");
+
+    let output = project.pkg(&server, &["--pull", "hello", "--dest", "vendor/bin"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(
+        fs::read(project.path("vendor/bin/hello")).unwrap(),
+        b"binary"
+    );
+    assert_eq!(
+        fs::read(project.path("vendor/bin/hello.sh")).unwrap(),
+        b"#!/bin/sh\necho hello\n"
+    );
+    // Nothing lands in the default dir, nor is recorded as installed
+    assert!(!project.path("ruxgo_pkg/app-bin/hello").exists());
+    assert!(!project.path("ruxgo_pkg/installed.toml").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pull_rejects_a_dest_that_is_not_a_directory() {
+    let project = Project::new("pkg-dest-file");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+    server.serve("/hello", b"binary");
+    project.write("vendor", "a file");
+
+    let output = project.pkg(&server, &["--pull", "hello", "--dest", "vendor"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("Failed to create destination 'vendor'"),
+        "{}",
+        stdout(&output)
+    );
+    assert_eq!(
+        fs::read_to_string(project.path("vendor")).unwrap(),
+        "a file"
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{stdout, Project, Server};
+
+const PACKAGES: &str = "[[packages]]\ntyp = \"app-bin\"\nname = \"hello\"\nbranch = \"main\"\nversion = \"1.0\"\ndescription = \"Says hello\"\n";
+
+#[test]
+#[cfg(target_os = "linux")]
+fn info_shows_the_metadata_of_a_package() {
+    let project = Project::new("pkg-info");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+    server.serve("/hello", b"binary");
+    server.serve("/hello.sh", b"#!/bin/sh\necho hello\n");
+
+    let output = project.pkg(&server, &["--info", "hello"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Name:         hello\nType:         app-bin\nBranch:       main\nVersion:      1.0\n\
+             Description:  Says hello\nSource:       {}/hello\nInstalled:    no\n",
+            server.url
+        )
+    );
+
+    let output = project.pkg(&server, &["--pull", "hello"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    let output = project.pkg(&server, &["--info", "hello"]);
+    assert!(
+        stdout(&output).contains("Installed:    yes, version 1.0 at "),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn info_of_an_unknown_package_suggests_a_near_match() {
+    let project = Project::new("pkg-info-unknown");
+    let server = Server::new();
+    server.serve("/packages.toml", PACKAGES.as_bytes());
+
+    let output = project.pkg(&server, &["--info", "helo"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("[ERROR] Package 'helo' not found, did you mean 'hello'?"),
+        "{}",
+        stdout(&output)
+    );
+}
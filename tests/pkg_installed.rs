@@ -0,0 +1,83 @@
+mod common;
+
+use common::{stdout, Project, Server};
+
+/// Serves the `hello` and `bye` app-bins
+fn server() -> Server {
+    let server = Server::new();
+    let mut packages = String::new();
+    for (name, version) in [("hello", "1.0"), ("bye", "2.1")] {
+        packages.push_str(&format!(
+            "[[packages]]\ntyp = \"app-bin\"\nname = \"{}\"\nbranch = \"main\"\nversion = \"{}\"\ndescription = \"\"\n\n",
+            name, version
+        ));
+        server.serve(&format!("/{}", name), b"binary");
+        server.serve(&format!("/{}.sh", name), b"#!/bin/sh\n");
+    }
+    server.serve("/packages.toml", packages.as_bytes());
+    server
+}
+
+/// Returns the names and versions recorded in the installed-manifest
+fn installed(project: &Project) -> Vec<(String, String)> {
+    let manifest: toml::Value =
+        toml::from_str(&std::fs::read_to_string(project.path("ruxgo_pkg/installed.toml")).unwrap())
+            .unwrap();
+    manifest["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pkg| {
+            assert_eq!(pkg["typ"].as_str(), Some("app-bin"));
+            assert!(pkg["installed_at"].as_str().is_some());
+            (
+                pkg["name"].as_str().unwrap().to_string(),
+                pkg["version"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pull_and_clean_update_the_installed_manifest() {
+    let project = Project::new("pkg-installed");
+    let server = server();
+    let pkg = |args: &[&str]| {
+        let output = project.pkg(&server, args);
+        assert!(output.status.success(), "{}", stdout(&output));
+        stdout(&output)
+    };
+
+    assert!(pkg(&["--installed"]).contains("No packages installed"));
+
+    pkg(&["--pull", "hello"]);
+    pkg(&["--pull", "bye"]);
+    assert_eq!(
+        installed(&project),
+        [
+            ("hello".to_string(), "1.0".to_string()),
+            ("bye".to_string(), "2.1".to_string())
+        ]
+    );
+    let listing = pkg(&["--installed"]);
+    assert!(
+        listing
+            .lines()
+            .any(|line| line.starts_with("app-bin") && line.contains(" hello ")),
+        "{}",
+        listing
+    );
+    assert!(listing.contains(" 2.1 "), "{}", listing);
+
+    // Pulling again replaces the entry
+    pkg(&["--pull", "hello"]);
+    assert_eq!(installed(&project).len(), 2);
+
+    pkg(&["--clean", "hello"]);
+    assert_eq!(
+        installed(&project),
+        [("bye".to_string(), "2.1".to_string())]
+    );
+    assert!(!pkg(&["--installed"]).contains("hello"));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{Project, Server};
+use sha2::{Digest, Sha256};
+
+/// Returns a package list of the `hello` app-bin at the given version
+fn packages(version: &str) -> String {
+    format!(
+        "[[packages]]\ntyp = \"app-bin\"\nname = \"hello\"\nbranch = \"main\"\nversion = {:?}\ndescription = \"Says hello\"\n",
+        version
+    )
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn app_bin_pull_is_locked_to_its_checksum() {
+    let project = Project::new("pkg-lock");
+    let server = Server::new();
+    server.serve("/packages.toml", packages("1.0").as_bytes());
+    server.serve("/hello", b"binary v1");
+    server.serve("/hello.sh", b"#!/bin/sh\necho hello\n");
+
+    let output = project.pkg(&server, &["--pull", "hello"]);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    assert_eq!(
+        std::fs::read(project.path("ruxgo_pkg/app-bin/hello")).unwrap(),
+        b"binary v1"
+    );
+    assert!(project.path("ruxgo_pkg/app-bin/hello.sh").exists());
+    let lock = std::fs::read_to_string(project.path("ruxgo.lock")).unwrap();
+    let sha256 = format!("{:x}", Sha256::digest(b"binary v1"));
+    assert!(
+        lock.contains(&format!("sha256 = \"{}\"", sha256)),
+        "{}",
+        lock
+    );
+    assert!(lock.contains("version = \"1.0\""), "{}", lock);
+
+    // The remote binary changed without a new version
+    server.serve("/hello", b"binary v2");
+    let output = project.pkg(&server, &["--pull", "hello"]);
+    let stdout = common::stdout(&output);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("Checksum of package 'hello' does not match ruxgo.lock"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        std::fs::read(project.path("ruxgo_pkg/app-bin/hello")).unwrap(),
+        b"binary v1"
+    );
+
+    // A new version in the package list is not pulled over the locked one
+    server.serve("/packages.toml", packages("2.0").as_bytes());
+    // Drop the cached list for the new one to be fetched
+    std::fs::remove_file(project.path("ruxgo_pkg/cache/package_cache.toml")).unwrap();
+    let output = project.pkg(&server, &["--pull", "hello"]);
+    let stdout = common::stdout(&output);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("is locked to version 1.0 in ruxgo.lock"),
+        "{}",
+        stdout
+    );
+
+    // Updating pins it again
+    let output = project.pkg(&server, &["--update", "hello", "--yes"]);
+    assert!(output.status.success(), "{}", common::stdout(&output));
+    let lock = std::fs::read_to_string(project.path("ruxgo.lock")).unwrap();
+    let sha256 = format!("{:x}", Sha256::digest(b"binary v2"));
+    assert!(
+        lock.contains(&format!("sha256 = \"{}\"", sha256)),
+        "{}",
+        lock
+    );
+    assert!(lock.contains("version = \"2.0\""), "{}", lock);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{stdout, Project};
+use std::process::{Output, Stdio};
+
+/// Runs `ruxgo pkg --run hello` with stdin not a terminal
+fn run(project: &Project, args: &[&str]) -> Output {
+    common::ruxgo()
+        .current_dir(&project.root)
+        .env_remove("RUXGO_PKG_YES")
+        .args(["pkg", "--run", "hello"])
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn invalid_scripts_are_not_run() {
+    let project = Project::new("pkg-run-invalid");
+    for (script, error) in [
+        ("", "is empty"),
+        ("  \n", "is empty"),
+        ("touch ran\n", "does not start with a shebang (#!)"),
+    ] {
+        project.write("ruxgo_pkg/app-bin/hello.sh", script);
+        let output = run(&project, &["--yes"]);
+        assert!(!output.status.success());
+        let out = stdout(&output);
+        assert!(
+            out.contains("Failed to run app-bin: Script 'ruxgo_pkg/app-bin/hello.sh'"),
+            "{}",
+            out
+        );
+        assert!(out.contains(error), "{}", out);
+        assert!(!project.path("ran").exists());
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn new_scripts_need_approval() {
+    let project = Project::new("pkg-run-approve");
+    project.write("ruxgo_pkg/app-bin/hello.sh", "#!/bin/sh\necho run >> ran\n");
+    let runs = || {
+        std::fs::read_to_string(project.path("ran"))
+            .unwrap_or_default()
+            .lines()
+            .count()
+    };
+
+    // Denied without a terminal to ask on
+    let output = run(&project, &[]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("use `--yes` or RUXGO_PKG_YES=1"));
+    assert_eq!(runs(), 0);
+
+    let output = run(&project, &["--yes"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(runs(), 1);
+
+    // Approved once, it runs without asking until it changes
+    let output = run(&project, &[]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(runs(), 2);
+
+    project.write(
+        "ruxgo_pkg/app-bin/hello.sh",
+        "#!/bin/sh\necho changed >> ran\n",
+    );
+    let output = run(&project, &[]);
+    assert!(!output.status.success());
+    assert_eq!(runs(), 2);
+    let output = common::ruxgo()
+        .current_dir(&project.root)
+        .env("RUXGO_PKG_YES", "1")
+        .args(["pkg", "--run", "hello"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(runs(), 3);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn platform_without_os_runs_the_plain_exe_on_qemu() {
+    let project = Project::new("platform-only");
+    let qemu = project.script(
+        "qemu.sh",
+        &format!(
+            "#!/bin/sh\necho \"$RUX_ARCH $RUX_PLATFORM $*\" > {}\n",
+            project.path("qemu.log").display()
+        ),
+    );
+    project.write("src/main.c", MAIN_C);
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            &format!(
+                "[platform]\nname = \"x86_64-qemu-q35\"\ncross_compile = \"\"\n\n[platform.qemu]\nqemu_bin = {:?}\n",
+                qemu.display().to_string()
+            ),
+            &[target("app", "exe", "./src")],
+        ),
+    );
+
+    let stdout = project.run_ok(&["-b", "-r"]);
+    assert!(!stdout.contains("Compiling OS"), "{}", stdout);
+    let log = std::fs::read_to_string(project.path("qemu.log")).unwrap();
+    assert!(log.starts_with("x86_64 x86_64-qemu-q35 "), "{}", log);
+    assert!(log.contains("-kernel ruxgo_bld/bin/app.bin"), "{}", log);
+
+    let config: serde_json::Value =
+        serde_json::from_str(&project.run_ok(&["--print-config"])).unwrap();
+    assert_eq!(config["os"]["platform"]["name"], "x86_64-qemu-q35");
+    assert!(config["os"].get("features_final").is_none());
+}
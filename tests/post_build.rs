@@ -0,0 +1,50 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::fs;
+
+#[test]
+#[cfg(target_os = "linux")]
+fn post_build_runs_only_when_relinked() {
+    let project = Project::new("post-build");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src").set(
+        "post_build",
+        "\"echo \\\"$RUXGO_BIN_PATH $RUXGO_ELF_PATH\\\" >> hook.log\"",
+    )]);
+    let runs = || {
+        fs::read_to_string(project.path("hook.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    project.run_ok(&["-b"]);
+    // Built for the host, the binary is the elf
+    assert_eq!(runs(), ["ruxgo_bld/bin/app.bin ruxgo_bld/bin/app.bin"]);
+
+    // Up to date, not relinked
+    project.run_ok(&["-b"]);
+    assert_eq!(runs().len(), 1);
+
+    project.write("src/main.c", "int main(void) {\n    return 1;\n}\n");
+    project.run_ok(&["-b"]);
+    assert_eq!(runs().len(), 2);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn failing_post_build_fails_the_build() {
+    let project = Project::new("post-build-fail");
+    project.write("src/main.c", MAIN_C);
+    project.config(&[target("app", "exe", "./src").set("post_build", "\"exit 3\"")]);
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    let stdout = common::stdout(&output);
+    assert!(
+        stdout.contains("post_build of target app failed with exit status: 3"),
+        "{}",
+        stdout
+    );
+}
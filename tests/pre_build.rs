@@ -0,0 +1,28 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pre_build_runs_only_when_building() {
+    let project = Project::new("pre-build");
+    project.write(
+        "src/main.c",
+        "int gen(void);\nint main(void) {\n    return gen();\n}\n",
+    );
+    // The generated source must be compiled, so the sources are collected after the command
+    project.config(&[target("app", "exe", "./src").set(
+        "pre_build",
+        "\"echo 'int gen(void) { return 0; }' > src/gen.c && echo run >> runs\"",
+    )]);
+
+    project.run_ok(&["--emit-deps", "deps.json"]);
+    assert!(!project.path("runs").exists());
+
+    project.run_ok(&["-b"]);
+    assert_eq!(
+        std::fs::read_to_string(project.path("runs")).unwrap(),
+        "run\n"
+    );
+    assert!(project.path("ruxgo_bld/obj_linux/app-gen.o").exists());
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use serde_json::{json, Value};
+
+/// Returns the `[os]` sections of an aarch64 Ruxos on ruxlibc, with `platform` added to `[os.platform]`
+fn os_sections(platform: &str) -> String {
+    format!(
+        "[os]\nname = \"ruxos\"\nservices = [\"alloc\", \"fs\"]\nulib = \"ruxlibc\"\n\n\
+         [os.platform]\nname = \"aarch64-qemu-virt\"\n{}\n",
+        platform
+    )
+}
+
+/// Writes a project with the given sections and prints its resolved config
+fn print_config(name: &str, sections: &str) -> Value {
+    let project = Project::new(name);
+    project.write("src/main.c", MAIN_C);
+    project.write(
+        "config_linux.toml",
+        &common::config(sections, &[target("app", "exe", "./src")]),
+    );
+    serde_json::from_str(&project.run_ok(&["--print-config"])).unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn print_config_shows_the_resolved_os_config() {
+    let config = print_config("print-config", &os_sections(""));
+    // The compiler gets the cross prefix of the architecture
+    assert_eq!(config["build"]["compiler"], "aarch64-linux-musl-gcc");
+    let os = &config["os"];
+    assert_eq!(os["platform"]["cross_compile"], "aarch64-linux-musl-");
+    assert_eq!(os["platform"]["target"], "aarch64-unknown-none-softfloat");
+    // fs brings in fd
+    assert_eq!(os["features"], json!(["alloc", "fs", "fd"]));
+    assert_eq!(
+        os["features_final"],
+        json!([
+            "ruxfeat/log-level-warn",
+            "ruxlibc/alloc",
+            "ruxlibc/fs",
+            "ruxlibc/fd"
+        ])
+    );
+    assert_eq!(config["targets"][0]["name"], "app");
+    assert_eq!(config["targets"][0]["src"], json!(["./src"]));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn print_config_without_os_has_no_os_section() {
+    let config = print_config("print-config-native", "");
+    assert_eq!(config["build"]["compiler"], "gcc");
+    assert!(config.get("os").is_none());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn print_config_uses_a_custom_cross_prefix() {
+    let config = print_config(
+        "print-config-cross",
+        &os_sections("cross_compile = \"aarch64-none-linux-gnu-\""),
+    );
+    assert_eq!(config["build"]["compiler"], "aarch64-none-linux-gnu-gcc");
+    assert_eq!(
+        config["os"]["platform"]["cross_compile"],
+        "aarch64-none-linux-gnu-"
+    );
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+/// Writes a project run on a QEMU that logs when it starts, with the given `[platform.qemu]` lines
+fn project(name: &str, qemu: &str) -> Project {
+    let project = Project::new(name);
+    let qemu_bin = project.script(
+        "qemu.sh",
+        &format!("#!/bin/sh\ntouch {:?}\n", project.path("qemu.ran")),
+    );
+    project.write("src/main.c", MAIN_C);
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            &format!(
+                "[platform]\nname = \"x86_64-qemu-q35\"\ncross_compile = \"\"\n\n[platform.qemu]\nqemu_bin = {:?}\n{}",
+                qemu_bin.display().to_string(),
+                qemu
+            ),
+            &[target("app", "exe", "./src")],
+        ),
+    );
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn print_qemu_cmd_prints_the_kernel_and_devices() {
+    let project = project("print-qemu-cmd", "blk = \"y\"\nnet = \"y\"\n");
+
+    let stdout = project.run_ok(&["-r", "--print-qemu-cmd", "--bin-args=x,y"]);
+    let argv: Vec<&str> = stdout.trim_end().split(' ').collect();
+    assert_eq!(argv[0], project.path("qemu.sh").display().to_string());
+    let has = |expected: &[&str]| argv.windows(expected.len()).any(|w| w == expected);
+    assert!(has(&["-kernel", "ruxgo_bld/bin/app.bin"]), "{}", stdout);
+    assert!(
+        has(&["-device", "virtio-blk-pci,drive=disk0"]),
+        "{}",
+        stdout
+    );
+    assert!(
+        has(&["-device", "virtio-net-pci,netdev=net0"]),
+        "{}",
+        stdout
+    );
+    // The bin args come last, as QEMU is run with them
+    assert!(argv.ends_with(&["x", "y"]), "{}", stdout);
+    assert!(!argv.contains(&"-S"), "{}", stdout);
+    // Nothing was run nor created
+    assert!(!project.path("qemu.ran").exists());
+    assert!(!project.path("disk.img").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn print_qemu_cmd_prints_the_debug_command() {
+    let project = project("print-qemu-cmd-debug", "debug = \"y\"\n");
+    let stdout = project.run_ok(&["-r", "--print-qemu-cmd"]);
+    assert!(stdout.trim_end().ends_with(" -s -S"), "{}", stdout);
+    assert!(!project.path("qemu.ran").exists());
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::process::Command;
+
+/// Runs ruxgo under `script` so that it draws its progress bar on a pseudo terminal
+fn build_on_tty(project: &Project) -> (bool, String) {
+    build_on_tty_at(project, Some("Log"))
+}
+
+/// Runs ruxgo like `build_on_tty` with `RUXGO_LOG_LEVEL` set to `level`, or unset
+fn build_on_tty_at(project: &Project, level: Option<&str>) -> (bool, String) {
+    let cmd = format!("{} --path {} -b", common::RUXGO, project.root.display());
+    let mut script = Command::new("script");
+    script.args(["-qec", &cmd, "/dev/null"]);
+    match level {
+        Some(level) => script.env("RUXGO_LOG_LEVEL", level),
+        None => script.env_remove("RUXGO_LOG_LEVEL"),
+    };
+    let output = script.output().unwrap();
+    (output.status.success(), common::stdout(&output))
+}
+
+/// Sets up a project of three sources built by a compiler that fails on `FAIL` in a source
+fn project(name: &str) -> Project {
+    let project = Project::new(&format!("progress-{}", name));
+    let cc = project.script(
+        "cc.sh",
+        "#!/bin/sh\nfor arg in \"$@\"; do\n    case \"$arg\" in\n        *.c) grep -q FAIL \"$arg\" && exit 1 ;;\n    esac\ndone\nexec gcc \"$@\"\n",
+    );
+    project.write("src/a.c", "int a(void) {\n    return 0;\n}\n");
+    project.write("src/b.c", "int b(void) {\n    return 0;\n}\n");
+    project.write("src/main.c", MAIN_C);
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc.display().to_string(),
+            "",
+            &[target("app", "exe", "./src")],
+        ),
+    );
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn progress_bar_finishes_at_full_length() {
+    let project = project("ok");
+    let (success, stdout) = build_on_tty(&project);
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("3/3 (100%)"), "{}", stdout);
+    assert!(stdout.contains("done"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn progress_bar_is_abandoned_on_failure() {
+    let project = project("fail");
+    project.write("src/b.c", "FAIL\n");
+    let (success, stdout) = build_on_tty(&project);
+    assert!(!success, "{}", stdout);
+    assert!(stdout.contains("failed:"), "{}", stdout);
+    assert!(stdout.contains("b.c"), "{}", stdout);
+    assert!(!stdout.contains("done"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn progress_bar_is_shown_unless_verbose() {
+    for (name, level, shown) in [
+        ("default", None, true),
+        ("warn", Some("Warn"), true),
+        ("error", Some("Error"), true),
+        ("info", Some("Info"), false),
+        ("debug", Some("Debug"), false),
+    ] {
+        let project = project(name);
+        let (success, stdout) = build_on_tty_at(&project, level);
+        assert!(success, "{}", stdout);
+        assert_eq!(
+            stdout.contains("3/3 (100%)"),
+            shown,
+            "{:?}: {}",
+            level,
+            stdout
+        );
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::fs;
+
+/// Returns the line of the target hash file stored under `key`
+fn hash_line(project: &Project, target: &str, key: &str) -> Option<String> {
+    fs::read_to_string(project.path(&format!("ruxgo_bld/{}.linux.hash", target)))
+        .unwrap()
+        .lines()
+        .find(|line| line.starts_with(&format!("{} ", key)))
+        .map(String::from)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn compiler_version_change_rebuilds() {
+    let project = Project::new("rebuild-compiler");
+    project.write("src/main.c", MAIN_C);
+    project.write("version", "gcc 1.0\n");
+    let log = project.path("cc.log");
+    let cc = project.script(
+        "cc.sh",
+        &format!(
+            "#!/bin/sh\nif [ \"$1\" = --version ]; then cat {:?}; exit 0; fi\n\
+             echo \"$@\" >> {:?}\nexec gcc \"$@\"\n",
+            project.path("version"),
+            log
+        ),
+    );
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc.display().to_string(),
+            "",
+            &[target("app", "exe", "./src")],
+        ),
+    );
+
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 1);
+    let first = hash_line(&project, "app", "<compiler>").expect("no compiler hash");
+
+    // Same toolchain, nothing to do
+    project.run_ok(&["-b"]);
+    assert_eq!(project.compile_runs().len(), 1);
+
+    // An upgraded compiler rebuilds every object
+    project.write("version", "gcc 2.0\n");
+    let stdout = project.run_ok(&["-b", "--explain"]);
+    assert!(
+        stdout.contains("Compiler changed, rebuilding target: app"),
+        "{}",
+        stdout
+    );
+    assert_eq!(project.compile_runs().len(), 2);
+    assert_ne!(hash_line(&project, "app", "<compiler>").unwrap(), first);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn cflags_change_rebuilds_only_that_target() {
+    let project = Project::new("rebuild-cflags");
+    project.write(
+        "src/main.c",
+        "int util(void);\nint main(void) {\n#ifdef FOO\n    return 1;\n#endif\n    return util();\n}\n",
+    );
+    project.write("util/util.c", "int util(void) {\n    return 0;\n}\n");
+    let cc = project.logging_gcc();
+    let write_config = |cflags: &str| {
+        project.write(
+            "config_linux.toml",
+            &common::config_with_compiler(
+                &cc,
+                "",
+                &[
+                    target("util", "static", "./util"),
+                    target("app", "exe", "./src")
+                        .set("cflags", &format!("{:?}", cflags))
+                        .set("deps", "[\"util\"]"),
+                ],
+            ),
+        );
+    };
+    let compiled = |file: &str| {
+        project
+            .compile_runs()
+            .iter()
+            .filter(|run| run.contains(file))
+            .count()
+    };
+
+    write_config("");
+    project.run_ok(&["-b"]);
+    assert_eq!((compiled("main.c"), compiled("util.c")), (1, 1));
+    let first = hash_line(&project, "app", "<cflags>").expect("no cflags hash");
+
+    write_config("-DFOO");
+    let stdout = project.run_ok(&["-b", "--explain"]);
+    assert!(
+        stdout.contains("cflags changed, rebuilding target: app"),
+        "{}",
+        stdout
+    );
+    assert_eq!((compiled("main.c"), compiled("util.c")), (2, 1));
+    assert_ne!(hash_line(&project, "app", "<cflags>").unwrap(), first);
+    assert!(compiled_with(&project, "main.c", "-DFOO"));
+
+    // Toggling it back rebuilds again
+    write_config("");
+    project.run_ok(&["-b"]);
+    assert_eq!((compiled("main.c"), compiled("util.c")), (3, 1));
+}
+
+/// Returns whether the last compile run of `file` was given `flag`
+fn compiled_with(project: &Project, file: &str, flag: &str) -> bool {
+    project
+        .compile_runs()
+        .iter()
+        .rev()
+        .find(|run| run.contains(file))
+        .is_some_and(|run| run.split(' ').any(|arg| arg == flag))
+}
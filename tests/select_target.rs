@@ -0,0 +1,72 @@
+mod common;
+
+use common::{target, Project};
+
+/// Writes a project with a static lib listed first, then two exes, `b` using the lib
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("lib/lib.c", "int lib(void) {\n    return 7;\n}\n");
+    project.write(
+        "a/main.c",
+        "#include <stdio.h>\nint main(void) {\n    puts(\"running a\");\n    return 0;\n}\n",
+    );
+    project.write(
+        "b/main.c",
+        "#include <stdio.h>\nint lib(void);\nint main(void) {\n    printf(\"running b %d\\n\", lib());\n    return 0;\n}\n",
+    );
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("a", "exe", "./a"),
+        target("b", "exe", "./b").set("deps", "[\"util\"]"),
+    ]);
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn build_and_run_the_selected_exe() {
+    let project = project("select-target");
+
+    let stdout = project.run_ok(&["-b", "-r", "--target", "b"]);
+    assert!(stdout.contains("running b 7"), "{}", stdout);
+    assert!(!stdout.contains("running a"), "{}", stdout);
+    // Only the target and its deps are built
+    assert!(project.path("ruxgo_bld/bin/util.a").exists());
+    assert!(!project.path("ruxgo_bld/bin/a.bin").exists());
+
+    let stdout = project.run_ok(&["-b", "-r", "--target", "a"]);
+    assert!(stdout.contains("running a"), "{}", stdout);
+    assert!(!stdout.contains("running b"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn run_picks_the_exe_when_libs_are_listed_first() {
+    let project = Project::new("select-target-lib-first");
+    project.write("lib/lib.c", "int lib(void) {\n    return 7;\n}\n");
+    project.write(
+        "src/main.c",
+        "#include <stdio.h>\nint lib(void);\nint main(void) {\n    printf(\"running app %d\\n\", lib());\n    return 0;\n}\n",
+    );
+    project.config(&[
+        target("util", "static", "./lib"),
+        target("app", "exe", "./src").set("deps", "[\"util\"]"),
+    ]);
+
+    let stdout = project.run_ok(&["-b", "-r"]);
+    assert!(
+        stdout.contains("Running: ruxgo_bld/bin/app.bin"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("running app 7"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn unknown_target_is_reported() {
+    let project = project("select-target-unknown");
+    let output = project.run(&["-b", "--target", "c"]);
+    assert!(!output.status.success());
+    assert!(common::stdout(&output).contains("Target \"c\" not found"));
+}
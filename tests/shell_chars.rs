@@ -0,0 +1,23 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn paths_and_flags_with_shell_characters_are_passed_literally() {
+    let project = Project::new("shell-chars");
+    let dir = "src/a b;$(touch pwned)";
+    project.write(
+        &format!("{}/main.c", dir),
+        "#include <string.h>\nint main(void) {\n    return strcmp(MSG, \"it's a $HOME\");\n}\n",
+    );
+    project.config(&[target("app", "exe", &format!("./{}", dir))
+        .set("cflags", "\"'-DMSG=\\\"it'\\\\''s a $HOME\\\"'\"")]);
+
+    project.run_ok(&["-b"]);
+    assert!(!project.path("pwned").exists());
+    let status = std::process::Command::new(project.path("ruxgo_bld/bin/app.bin"))
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(0));
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn every_dir_matching_an_exclude_is_skipped() {
+    let project = Project::new("src-exclude");
+    project.write("src/main.c", MAIN_C);
+    project.write("src/a/unused/broken.c", "BROKEN\n");
+    project.write("src/b/deeper/unused/broken2.c", "BROKEN\n");
+    project.write("src/b/kept.c", "int kept(void) {\n    return 0;\n}\n");
+    project.config(&[target("app", "exe", "./src").set("src_exclude", "[\"unused/\"]")]);
+
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("2 of 2 source files"), "{}", stdout);
+    assert!(project.path("ruxgo_bld/obj_linux/app-kept.o").exists());
+    assert!(!project.path("ruxgo_bld/obj_linux/app-broken.o").exists());
+    assert!(!project.path("ruxgo_bld/obj_linux/app-broken2.o").exists());
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn only_sources_matching_src_only_are_compiled() {
+    let project = Project::new("src-only");
+    project.write("src/core/main.c", MAIN_C);
+    project.write(
+        "src/core/util/util.c",
+        "int util(void) {\n    return 0;\n}\n",
+    );
+    project.write("src/extra/broken.c", "BROKEN\n");
+    project.write("src/broken2.c", "BROKEN\n");
+    project.config(&[target("app", "exe", "./src").set("src_only", "[\"core/\"]")]);
+
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("2 of 2 source files"), "{}", stdout);
+    assert!(project.path("ruxgo_bld/obj_linux/app-main.o").exists());
+    assert!(project.path("ruxgo_bld/obj_linux/app-util.o").exists());
+    assert!(!project.path("ruxgo_bld/obj_linux/app-broken.o").exists());
+    assert!(!project.path("ruxgo_bld/obj_linux/app-broken2.o").exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn check_and_build_see_the_same_sources() {
+    let project = Project::new("src-only-check");
+    project.write("src/core/main.c", MAIN_C);
+    // Same stem as main.c, outside src_only or excluded, so neither built nor a duplicate
+    project.write("src/other/main.c", "BROKEN\n");
+    project.write("src/core/skip/main.c", "BROKEN\n");
+    project.config(&[target("app", "exe", "./src")
+        .set("src_only", "[\"core/\"]")
+        .set("src_exclude", "[\"core/skip\"]")]);
+
+    // No duplicate sources reported
+    project.run_ok(&["check"]);
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("1 of 1 source files"), "{}", stdout);
+}
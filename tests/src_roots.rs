@@ -0,0 +1,57 @@
+mod common;
+
+use common::{stdout, target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn sources_are_merged_from_every_root() {
+    let project = Project::new("src-roots");
+    project.write(
+        "src/main.c",
+        "int vendored(void);\nint main(void) {\n    return vendored();\n}\n",
+    );
+    project.write(
+        "vendor/lib/vendored.c",
+        "int vendored(void) {\n    return 0;\n}\n",
+    );
+    project.config(&[target("app", "exe", "./src").set("src", "[\"./src\", \"./vendor\"]")]);
+
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("2 of 2 source files"), "{}", stdout);
+    assert!(project.path("ruxgo_bld/obj_linux/app-main.o").exists());
+    assert!(project.path("ruxgo_bld/obj_linux/app-vendored.o").exists());
+    let status = std::process::Command::new(project.path("ruxgo_bld/bin/app.bin"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn same_stem_in_two_roots_is_an_error() {
+    let project = Project::new("src-roots-duplicate");
+    project.write("src/main.c", common::MAIN_C);
+    project.write("src/util.c", "int util(void) {\n    return 0;\n}\n");
+    project.write("vendor/util.cpp", "int other(void) {\n    return 0;\n}\n");
+    project.config(&[target("app", "exe", "./src").set("src", "[\"./src\", \"./vendor\"]")]);
+
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("Duplicate source files found for target app")
+            && stdout(&output).contains("must be unique: util"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(!project.path("ruxgo_bld/bin/app.bin").exists());
+
+    // A missing root is named by check
+    project.config(&[target("app", "exe", "./src").set("src", "[\"./src\", \"./missing\"]")]);
+    let output = project.run(&["check"]);
+    assert!(!output.status.success());
+    assert!(
+        stdout(&output).contains("src directory \"./missing\" does not exist"),
+        "{}",
+        stdout(&output)
+    );
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn cstd_and_cppstd_apply_by_language() {
+    let project = Project::new("std-flags");
+    project.write(
+        "src/main.c",
+        "int twice(int x);\nint main(void) {\n    return twice(0);\n}\n",
+    );
+    project.write(
+        "src/twice.cpp",
+        "extern \"C\" int twice(int x) {\n    return x * 2;\n}\n",
+    );
+    let cc = project.logging_gcc();
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(
+            &cc,
+            "",
+            &[target("app", "exe", "./src")
+                .set("cstd", "\"c11\"")
+                .set("cppstd", "\"c++20\"")],
+        ),
+    );
+
+    project.run_ok(&["-b"]);
+    let runs = project.compile_runs();
+    assert_eq!(runs.len(), 2, "{:?}", runs);
+    for run in &runs {
+        let args: Vec<&str> = run.split(' ').collect();
+        if run.contains("twice.cpp") {
+            assert!(args.contains(&"-std=c++20"), "{}", run);
+            assert!(!args.contains(&"-std=c11"), "{}", run);
+        } else {
+            assert!(args.contains(&"-std=c11"), "{}", run);
+            assert!(!args.contains(&"-std=c++20"), "{}", run);
+        }
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn no_std_flag_by_default() {
+    let project = Project::new("std-flags-default");
+    project.write("src/main.c", common::MAIN_C);
+    let cc = project.logging_gcc();
+    project.write(
+        "config_linux.toml",
+        &common::config_with_compiler(&cc, "", &[target("app", "exe", "./src")]),
+    );
+
+    project.run_ok(&["-b"]);
+    let runs = project.compile_runs();
+    assert_eq!(runs.len(), 1, "{:?}", runs);
+    assert!(!runs[0].contains("-std="), "{}", runs[0]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn gen_vsc_uses_the_standards_of_the_exe() {
+    let project = Project::new("std-flags-vsc");
+    project.write("src/main.c", common::MAIN_C);
+    project.config(&[target("app", "exe", "./src")
+        .set("cstd", "\"gnu99\"")
+        .set("cppstd", "\"c++20\"")]);
+
+    project.run_ok(&["-b", "--gen-vsc"]);
+    let props = std::fs::read_to_string(project.path(".vscode/c_cpp_properties.json")).unwrap();
+    assert!(props.contains("\"cStandard\": \"gnu99\""), "{}", props);
+    assert!(props.contains("\"cppStandard\": \"c++20\""), "{}", props);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{target, Project};
+
+/// Builds an exe with the given strip option and returns its contents
+fn build_exe(strip: &str) -> Vec<u8> {
+    let project = Project::new(&format!("strip-{}", strip));
+    project.write(
+        "src/main.c",
+        "int ruxgo_strip_marker(void) {\n    return 0;\n}\nint main(void) {\n    return ruxgo_strip_marker();\n}\n",
+    );
+    project.config(&[target("app", "exe", "./src/")
+        .set("cflags", "\"-g\"")
+        .set("strip", &format!("{:?}", strip))]);
+    project.run_ok(&["-b"]);
+    std::fs::read(project.path("ruxgo_bld/bin/app.bin")).unwrap()
+}
+
+/// Returns true if the symbol name is in the binary, i.e. its symbol table was kept
+fn has_symbol(bin: &[u8]) -> bool {
+    let name = b"ruxgo_strip_marker";
+    bin.windows(name.len()).any(|window| window == name)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn strip_never_keeps_symbols_of_native_exe() {
+    assert!(has_symbol(&build_exe("never")));
+    assert!(!has_symbol(&build_exe("always")));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+/// Writes a config running the tests on a fake qemu, which logs one argument per line
+fn project(name: &str) -> Project {
+    let project = Project::new(name);
+    let qemu = project.script(
+        "qemu dir/qemu.sh",
+        &format!(
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > {:?}\n",
+            project.path("qemu.log").display()
+        ),
+    );
+    project.write("tests/main.c", MAIN_C);
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            &format!(
+                "[platform]\nname = \"x86_64-qemu-q35\"\ncross_compile = \"\"\n\n[platform.qemu]\nqemu_bin = {:?}\nargs = \"a b\"\nenvs = \"K=V\"\n",
+                qemu.display().to_string()
+            ),
+            &[target("unit", "test", "./tests")],
+        ),
+    );
+    project
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn tests_run_on_qemu_without_a_shell() {
+    let project = project("test-qemu");
+    let stdout = project.run_ok(&["test"]);
+    assert!(stdout.contains("Test unit passed"), "{}", stdout);
+    let log = std::fs::read_to_string(project.path("qemu.log")).unwrap();
+    let args: Vec<&str> = log.lines().collect();
+    assert!(
+        args.windows(2).any(|args| args == ["-append", ";a b;K=V"]),
+        "{}",
+        log
+    );
+    assert!(
+        args.windows(2)
+            .any(|args| args == ["-kernel", "ruxgo_bld/bin/unit.bin"]),
+        "{}",
+        log
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_reports_config_errors() {
+    let project = project("test-qemu-config");
+    project.write("config_linux.toml", "[build]\ncompiler = 1\n");
+    let output = project.run(&["test"]);
+    let stdout = common::stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("[ERROR]"), "{}", stdout);
+    assert!(stdout.contains("compiler"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn run_reports_a_missing_qemu_bin() {
+    let project = project("run-qemu-missing");
+    project.write("src/main.c", MAIN_C);
+    // Not executable
+    project.write("qemu.txt", "");
+    let missing = project.path("no/qemu").display().to_string();
+    let not_executable = project.path("qemu.txt").display().to_string();
+    for qemu_bin in [
+        missing.as_str(),
+        not_executable.as_str(),
+        "qemu-system-nonexistent",
+    ] {
+        project.write(
+            "config_linux.toml",
+            &common::config(
+                &format!(
+                    "[platform]\nname = \"x86_64-qemu-q35\"\ncross_compile = \"\"\n\n[platform.qemu]\nqemu_bin = {:?}\n",
+                    qemu_bin
+                ),
+                &[target("app", "exe", "./src")],
+            ),
+        );
+        let output = project.run(&["-r"]);
+        assert!(!output.status.success());
+        let stdout = common::stdout(&output);
+        assert!(
+            stdout.contains(qemu_bin) && stdout.contains("set `qemu_bin` in [os.platform.qemu]"),
+            "{}",
+            stdout
+        );
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn run_reports_a_failing_qemu_with_its_exit_status() {
+    let project = project("run-qemu-failing");
+    project.write("src/main.c", MAIN_C);
+    let qemu = project.script(
+        "bin/qemu.sh",
+        "#!/bin/sh\necho 'qemu: could not load kernel' >&2\nexit 2\n",
+    );
+    project.write(
+        "config_linux.toml",
+        &common::config(
+            &format!(
+                "[platform]\nname = \"x86_64-qemu-q35\"\ncross_compile = \"\"\n\n[platform.qemu]\nqemu_bin = {:?}\n",
+                qemu.display().to_string()
+            ),
+            &[target("app", "exe", "./src")],
+        ),
+    );
+    let output = project.run(&["-b", "-r"]);
+    assert!(!output.status.success());
+    let stdout = common::stdout(&output);
+    assert!(
+        stdout.contains("[ERROR] Command execution failed (exit status: 2)"),
+        "{}",
+        stdout
+    );
+    // QEMU's own stderr is passed through as text
+    assert!(String::from_utf8_lossy(&output.stderr).contains("qemu: could not load kernel"));
+}
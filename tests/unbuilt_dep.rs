@@ -0,0 +1,33 @@
+mod common;
+
+use common::{stdout, target, Project};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn a_dep_that_was_not_built_is_named() {
+    let project = Project::new("unbuilt-dep");
+    project.write(
+        "app/main.c",
+        "int util(void);\nint main(void) {\n    return util();\n}\n",
+    );
+    project.write("util/util.c", "int util(void) {\n    return 0;\n}\n");
+    // The archiver succeeds without writing the library
+    project.config(&[
+        target("libutil", "static", "./util").set("archive", "\"true\""),
+        target("app", "exe", "./app").set("deps", "[\"libutil\"]"),
+    ]);
+
+    let output = project.run(&["-b"]);
+    assert!(!output.status.success());
+    let out = stdout(&output);
+    assert!(
+        out.contains(
+            "Dependency libutil of target app was not built, ruxgo_bld/bin/libutil.a is missing"
+        ),
+        "{}",
+        out
+    );
+    // Reported before the linker runs
+    assert!(!out.contains("undefined reference"), "{}", out);
+    assert!(!project.path("ruxgo_bld/bin/app.bin").exists());
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Waits until `done` holds, then stops ruxgo and returns its stdout
+fn stop_when(mut child: Child, done: impl Fn() -> bool) -> String {
+    let start = Instant::now();
+    while !done() && start.elapsed() < Duration::from_secs(30) {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let _ = child.kill();
+    let output = child.wait_with_output().unwrap();
+    assert!(done(), "{}", common::stdout(&output));
+    common::stdout(&output)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn watch_builds_with_the_given_config() {
+    let project = Project::new("watch-config");
+    project.write("app/src/main.c", MAIN_C);
+    project.write(
+        "app/custom.toml",
+        &common::config("", &[target("app", "exe", "./src")]),
+    );
+
+    // Started from the project root, away from the config file
+    let child = common::ruxgo()
+        .current_dir(&project.root)
+        .args(["--config", "app/custom.toml", "-b", "--watch"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let bin = project.path("app/ruxgo_bld/bin/app.bin");
+    let stdout = stop_when(child, || bin.exists());
+    assert!(!stdout.contains("Could not read config file"), "{}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn watch_rejects_a_config_from_stdin() {
+    let project = Project::new("watch-stdin");
+    let output = common::ruxgo()
+        .current_dir(&project.root)
+        .args(["--config", "-", "-b", "--watch"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(common::stdout(&output).contains("--watch can't read the config from stdin"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn a_burst_of_saves_rebuilds_once() {
+    let project = Project::new("watch-burst");
+    project.write("main.c", MAIN_C);
+    // The output dir is inside the watched source dir
+    project.config(&[target("app", "exe", ".")]);
+
+    let mut child = common::ruxgo()
+        .arg("--path")
+        .arg(&project.root)
+        .args(["-b", "--watch"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Collects the output while ruxgo runs
+    let log = Arc::new(Mutex::new(String::new()));
+    let reader = {
+        let log = log.clone();
+        let stdout = child.stdout.take().unwrap();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                log.lock().unwrap().push_str(&(line + "\n"));
+            }
+        })
+    };
+    let builds = || {
+        log.lock()
+            .unwrap()
+            .matches("Waiting for changes...")
+            .count()
+    };
+    let wait_for = |count: usize| {
+        let start = Instant::now();
+        while builds() < count && start.elapsed() < Duration::from_secs(30) {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    };
+
+    wait_for(1);
+    assert_eq!(builds(), 1, "{}", log.lock().unwrap());
+    // Writes to the output dir are not changes
+    project.write("ruxgo_bld/scratch.txt", "");
+    std::thread::sleep(Duration::from_secs(1));
+    for i in 0..3 {
+        project.write("main.c", &format!("{}// save {}\n", MAIN_C, i));
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    wait_for(2);
+    // Leaves time for a spurious second rebuild
+    std::thread::sleep(Duration::from_secs(1));
+    let _ = child.kill();
+    let _ = child.wait();
+    reader.join().unwrap();
+
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log.matches("Change detected, rebuilding...").count(),
+        1,
+        "{}",
+        log
+    );
+    assert_eq!(log.matches("Waiting for changes...").count(), 2, "{}", log);
+    assert_eq!(log.matches("1 of 1 source files").count(), 2, "{}", log);
+}
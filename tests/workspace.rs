@@ -0,0 +1,25 @@
+mod common;
+
+use common::{target, Project, MAIN_C};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn workspace_builds_all_members() {
+    let project = Project::new("workspace");
+    for member in ["app1", "app2"] {
+        project.write(&format!("{}/src/main.c", member), MAIN_C);
+        project.write(
+            &format!("{}/config_linux.toml", member),
+            &common::config("", &[target(member, "exe", "./src/")]),
+        );
+    }
+    project.write(
+        "config_linux.toml",
+        "[workspace]\nmembers = [\"app1\", \"app2\"]\n",
+    );
+
+    let stdout = project.run_ok(&["-b"]);
+    assert!(stdout.contains("Workspace result: 2 succeeded, 0 failed"));
+    assert!(project.path("app1/ruxgo_bld/bin/app1.bin").exists());
+    assert!(project.path("app2/ruxgo_bld/bin/app2.bin").exists());
+}